@@ -0,0 +1,143 @@
+// [2026-10-19 新增] 典藏用的附屬檔：analyze_video 解析到的完整資訊原本用完即丟，
+// 現在可依選項在下載檔旁寫入 .info.json、.description 與留言 (存在 .info.json 的 comments 欄位，與 yt-dlp 相同)。
+// 之後可從這些檔案還原下載紀錄的中繼資料 (標題、上傳者等)，不需要再連線。
+use crate::auth;
+use crate::env::AppEnv;
+use crate::events::{self, get_msg, EventSink};
+use crate::history::{self, HistoryEntry};
+use crate::network;
+use crate::process;
+use crate::ytdlp::{parse_metadata, DownloadOptions, VideoMetadata};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 與下載檔同名的附屬檔 (整理規則搬移檔案時一併搬移)
+pub const SIDECAR_SUFFIXES: [&str; 2] = ["info.json", "description"];
+
+/// 抓取留言需要逐頁讀取，比一般解析慢得多
+const COMMENTS_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// <檔名去掉副檔名>.<suffix>，例如 Title_best.info.json
+pub fn sidecar_path(media: &Path, suffix: &str) -> PathBuf {
+    let stem = media.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    media.with_file_name(format!("{}.{}", stem, suffix))
+}
+
+/// 以 yt-dlp --write-comments 重新解析一次取得留言；max 為 None 時不限數量
+pub async fn fetch_comments(env: &AppEnv, url: &str, max: Option<u32>) -> Result<Vec<serde_json::Value>, String> {
+    let mut cmd = process::command(env.yt_dlp());
+    cmd.args(["--no-config", "--quiet", "--no-warnings", "--skip-download", "--write-comments"]);
+    if let Some(max) = max {
+        cmd.args(["--extractor-args".to_string(), format!("youtube:max_comments={}", max)]);
+    }
+    let auth = auth::args_for_url(env, url);
+    cmd.args(&auth.args);
+    cmd.args(network::yt_dlp_args(&env.settings.network, url));
+    cmd.args(["--dump-json", "--", url]);
+
+    let output = process::run_capture(cmd, COMMENTS_TIMEOUT).await?;
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(|e| format!("Cannot read comments: {}", e))?;
+    let mut comments = json["comments"].as_array().cloned().unwrap_or_default();
+    // 其他網站不認得 youtube:max_comments，這裡再截斷一次
+    if let Some(max) = max {
+        comments.truncate(max as usize);
+    }
+    Ok(comments)
+}
+
+/// 移除登入後格式資訊中附帶的 cookies，避免寫進典藏檔
+fn strip_cookies(info: &mut serde_json::Value) {
+    if let Some(obj) = info.as_object_mut() {
+        obj.remove("cookies");
+        for key in ["formats", "requested_formats", "requested_downloads"] {
+            for item in obj.get_mut(key).and_then(|v| v.as_array_mut()).into_iter().flatten() {
+                if let Some(item) = item.as_object_mut() {
+                    item.remove("cookies");
+                }
+            }
+        }
+    }
+}
+
+/// 依選項寫入附屬檔，回傳 .info.json 的位置 (有寫入時)；失敗只提出警告
+pub async fn write(env: &AppEnv, sink: &dyn EventSink, lang: &str, url: &str, options: &DownloadOptions, media: &Path, info: &serde_json::Value) -> Option<PathBuf> {
+    let mut errors = Vec::new();
+    let mut info_file = None;
+
+    if options.write_description {
+        let description = info["description"].as_str().unwrap_or_default();
+        if let Err(e) = std::fs::write(sidecar_path(media, "description"), description) {
+            errors.push(e.to_string());
+        }
+    }
+    // 留言與 yt-dlp 一樣存在 .info.json 中，因此要求留言時一定寫入 .info.json
+    if options.write_info_json || options.write_comments {
+        let mut info = info.clone();
+        strip_cookies(&mut info);
+        if options.write_comments {
+            match fetch_comments(env, url, options.max_comments).await {
+                Ok(comments) => {
+                    info["comment_count"] = comments.len().into();
+                    info["comments"] = comments.into();
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        let path = sidecar_path(media, "info.json");
+        match serde_json::to_string(&info).map_err(|e| e.to_string()).and_then(|txt| std::fs::write(&path, txt).map_err(|e| e.to_string())) {
+            Ok(()) => info_file = Some(path),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    for e in errors {
+        events::log(sink, get_msg(lang, &format!("⚠️ 典藏資訊寫入失敗：{}", e), &format!("⚠️ Could not write archival files: {}", e)));
+    }
+    info_file
+}
+
+#[derive(Debug, Serialize)]
+pub struct Rehydrated {
+    /// 更新後的紀錄
+    pub entry: HistoryEntry,
+    pub metadata: VideoMetadata,
+    pub description: Option<String>,
+    pub comments: Vec<serde_json::Value>,
+}
+
+/// 紀錄對應的 .info.json：優先使用記錄的位置，否則依輸出檔名尋找 (搬移後的舊紀錄)
+fn find_info_file(entry: &HistoryEntry) -> Option<PathBuf> {
+    entry.info_file.iter().cloned()
+        .chain(entry.files.iter().map(|f| sidecar_path(f, "info.json")))
+        .find(|p| p.is_file())
+}
+
+/// 從附屬檔還原下載紀錄 (以紀錄的 id 指定) 的中繼資料，並寫回紀錄
+// [2026-10-19 修正] 改以 id 指定並在 history 的鎖內修改，不會與同時寫入的新紀錄互相覆蓋
+pub fn rehydrate(env: &AppEnv, id: &str) -> Result<Rehydrated, String> {
+    history::update(&env.history_path(), |entries| {
+        let entry = entries.iter_mut().find(|e| e.id == id).ok_or_else(|| format!("History entry not found: {}", id))?;
+        let info_file = find_info_file(entry).ok_or("No .info.json saved for this download")?;
+        let txt = std::fs::read_to_string(&info_file).map_err(|e| e.to_string())?;
+        let info: serde_json::Value = serde_json::from_str(&txt).map_err(|e| format!("Invalid .info.json: {}", e))?;
+
+        let text = |key: &str| info[key].as_str().filter(|v| !v.is_empty()).map(str::to_string);
+        if let Some(title) = text("title") {
+            entry.title = title;
+        }
+        entry.uploader = text("uploader").or_else(|| text("channel")).or(entry.uploader.take());
+        entry.playlist = text("playlist_title").or_else(|| text("playlist")).or(entry.playlist.take());
+        entry.upload_date = text("upload_date").or(entry.upload_date.take());
+        entry.duration = entry.duration.or(info["duration"].as_f64());
+        entry.info_file = Some(info_file.clone());
+
+        let description = std::fs::read_to_string(sidecar_path(&info_file.with_extension(""), "description")).ok().or_else(|| text("description"));
+        Ok(Rehydrated {
+            entry: entry.clone(),
+            metadata: parse_metadata(&info),
+            description,
+            comments: info["comments"].as_array().cloned().unwrap_or_default(),
+        })
+    })
+}
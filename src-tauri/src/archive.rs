@@ -0,0 +1,43 @@
+// [2026-10-19 新增] 以原生 Rust 解壓縮組件，取代過去拼接字串呼叫 PowerShell Expand-Archive 的做法
+use std::fs::File;
+use std::path::Path;
+
+/// 從 zip 中取出檔名為 file_name 的檔案並寫到 dest。
+/// 只比對檔名、不沿用壓縮檔內的路徑，含有 ../ 或絕對路徑的成員會直接略過 (防止 zip-slip)。
+pub fn extract_file(zip_path: &Path, file_name: &str, dest: &Path) -> Result<(), String> {
+    let file = File::open(zip_path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(name) = entry.enclosed_name() else { continue };
+        if name.file_name().and_then(|n| n.to_str()) != Some(file_name) {
+            continue;
+        }
+
+        // 先寫入暫存檔再改名，避免中斷時留下不完整的執行檔
+        let tmp = dest.with_extension("part");
+        {
+            let mut out = File::create(&tmp).map_err(|e| e.to_string())?;
+            std::io::copy(&mut entry, &mut out).map_err(|e| e.to_string())?;
+        }
+        std::fs::rename(&tmp, dest).map_err(|e| e.to_string())?;
+        return mark_executable(dest);
+    }
+    Err(format!("{} not found in {}", file_name, zip_path.display()))
+}
+
+/// 在類 Unix 系統上補上執行權限
+pub fn mark_executable(path: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).map_err(|e| e.to_string())?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
@@ -0,0 +1,357 @@
+// [2026-10-19 新增] 各網站的登入資訊：匯入 Netscape cookies.txt、使用已安裝瀏覽器的 cookies，或 netrc 帳號密碼。
+// 全部存放在使用者資料目錄的 auth/ 之下 (Unix 上目錄 0700、檔案 0600)，解析與下載時依網址的網站套用。
+// 密碼不放在命令列參數中 (其他程式可看到)，而是寫入 netrc 檔再以 --netrc-location 指定。
+// [2026-10-19 修改] Windows 上 credentials.json 以 DPAPI 加密 (只有同一個 Windows 帳號能解開)，auth/ 的 ACL 只允許目前使用者。
+// netrc 不再常駐：每次執行 yt-dlp 前才寫出暫存檔，程序結束後刪除 (AuthArgs)。
+// 其他平台的 credentials.json 與 yt-dlp 必須直接讀取的 cookies.txt 仍是明文，只靠檔案權限保護。
+use crate::env::AppEnv;
+use crate::urls;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const STORE_FILE: &str = "credentials.json";
+/// 舊版常駐的明文 netrc，儲存時刪除
+const LEGACY_NETRC_FILE: &str = "netrc";
+
+/// 暫存 netrc 的序號 (同時執行的 yt-dlp 各用一份)
+static NETRC_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// yt-dlp extractor 的 NETRC_MACHINE 與 extractor 名稱 (urls::site_extractor) 不同的網站
+const NETRC_MACHINES: &[(&str, &str)] = &[
+    ("archive.org", "archiveorg"),
+    ("bilibili.tv", "biliintl"),
+    ("crunchyroll.com", "crunchyroll"),
+    ("patreon.com", "patreon"),
+];
+
+/// 國別網域中常見的第二層 (bbc.co.uk 的 co)，猜測 machine 名稱時略過
+const SECOND_LEVEL_LABELS: [&str; 8] = ["co", "com", "net", "org", "ac", "gov", "ne", "or"];
+
+/// yt-dlp --cookies-from-browser 支援的瀏覽器
+pub const BROWSERS: [&str; 9] = ["brave", "chrome", "chromium", "edge", "firefox", "opera", "safari", "vivaldi", "whale"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Credential {
+    /// 已匯入的 cookies.txt (存放於 auth/cookies/<site>.txt)
+    CookiesFile,
+    Browser { browser: String, profile: Option<String> },
+    Login { username: String, password: String },
+}
+
+/// 給前端顯示用，不含密碼
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialSummary {
+    pub site: String,
+    pub kind: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CredentialStore {
+    sites: BTreeMap<String, Credential>,
+}
+
+/// 網站名稱一律正規化為去除 www. / m. 的小寫主機名稱
+pub fn site_key(raw: &str) -> Result<String, String> {
+    let raw = raw.trim().to_ascii_lowercase();
+    let site = if raw.contains("://") { urls::normalize(&raw)?.site } else { raw.trim_start_matches("www.").trim_start_matches("m.").to_string() };
+    // 網站名稱也是 cookies 檔名的一部分，只允許主機名稱字元
+    let valid_chars = site.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+    if site.is_empty() || !valid_chars || site.starts_with('.') || site.contains("..") {
+        return Err(format!("Invalid site: {}", raw));
+    }
+    Ok(site)
+}
+
+/// 建立只有目前使用者能讀寫的檔案 (先寫暫存檔再改名)
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let tmp = path.with_extension("tmp");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp).map_err(|e| e.to_string())?;
+    file.write_all(contents).map_err(|e| e.to_string())?;
+    drop(file);
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+fn create_private_dir(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700)).map_err(|e| e.to_string())?;
+    }
+    // [2026-10-19 新增] Windows：移除繼承的權限，只留目前使用者 (資料夾內既有與之後建立的檔案都套用)
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        let user = match (std::env::var("USERDOMAIN"), std::env::var("USERNAME")) {
+            (Ok(domain), Ok(name)) => format!("{}\\{}", domain, name),
+            (_, Ok(name)) => name,
+            _ => return Err("Cannot determine the current Windows user".into()),
+        };
+        let status = std::process::Command::new("icacls")
+            .arg(dir)
+            .args(["/inheritance:r", "/grant:r", &format!("{}:(OI)(CI)F", user), "/T", "/Q"])
+            .creation_flags(0x08000000)
+            .status()
+            .map_err(|e| e.to_string())?;
+        if !status.success() {
+            return Err(format!("Cannot restrict permissions of {}", dir.display()));
+        }
+    }
+    Ok(())
+}
+
+/// [2026-10-19 新增] 以目前 Windows 帳號的 DPAPI 加解密登入資訊
+#[cfg(windows)]
+mod dpapi {
+    use windows_sys::Win32::Foundation::LocalFree;
+    use windows_sys::Win32::Security::Cryptography::{CryptProtectData, CryptUnprotectData, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB};
+
+    fn call(data: &[u8], protect: bool) -> Result<Vec<u8>, String> {
+        let input = CRYPT_INTEGER_BLOB { cbData: data.len() as u32, pbData: data.as_ptr() as *mut u8 };
+        let mut output = CRYPT_INTEGER_BLOB { cbData: 0, pbData: std::ptr::null_mut() };
+        // SAFETY: input 指向有效的 data；成功時 output 由系統配置，複製後以 LocalFree 釋放
+        unsafe {
+            let ok = if protect {
+                CryptProtectData(&input, std::ptr::null(), std::ptr::null(), std::ptr::null(), std::ptr::null(), CRYPTPROTECT_UI_FORBIDDEN, &mut output)
+            } else {
+                CryptUnprotectData(&input, std::ptr::null_mut(), std::ptr::null(), std::ptr::null(), std::ptr::null(), CRYPTPROTECT_UI_FORBIDDEN, &mut output)
+            };
+            if ok == 0 {
+                return Err(std::io::Error::last_os_error().to_string());
+            }
+            let bytes = std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec();
+            LocalFree(output.pbData as _);
+            Ok(bytes)
+        }
+    }
+
+    pub fn protect(data: &[u8]) -> Result<Vec<u8>, String> {
+        call(data, true)
+    }
+
+    pub fn unprotect(data: &[u8]) -> Result<Vec<u8>, String> {
+        call(data, false)
+    }
+}
+
+/// 寫入前加密儲存的登入資訊 (Windows 以外不加密)
+fn seal(data: &[u8]) -> Result<Vec<u8>, String> {
+    #[cfg(windows)]
+    {
+        dpapi::protect(data)
+    }
+    #[cfg(not(windows))]
+    {
+        Ok(data.to_vec())
+    }
+}
+
+/// 讀取時解密；舊版未加密的檔案原樣回傳 (下次儲存時改為加密)，無法解密的內容之後解析失敗並回報錯誤
+fn unseal(data: Vec<u8>) -> Vec<u8> {
+    #[cfg(windows)]
+    if let Ok(plain) = dpapi::unprotect(&data) {
+        return plain;
+    }
+    data
+}
+
+/// 檢查是否為 Netscape 格式的 cookies.txt
+pub fn validate_cookies_txt(text: &str) -> Result<(), String> {
+    let mut cookies = 0;
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        // "#HttpOnly_" 開頭的是 HttpOnly cookie，不是註解
+        if line.trim().is_empty() || (line.starts_with('#') && !line.starts_with("#HttpOnly_")) {
+            continue;
+        }
+        if line.split('\t').count() != 7 {
+            return Err("Not a Netscape cookies.txt file".into());
+        }
+        cookies += 1;
+    }
+    if cookies == 0 {
+        return Err("cookies.txt contains no cookies".into());
+    }
+    Ok(())
+}
+
+impl CredentialStore {
+    /// [2026-10-19 修改] 尚未儲存過時為空；無法解密或解析時回報錯誤，避免之後的儲存覆蓋掉所有登入資訊
+    pub fn load(env: &AppEnv) -> Result<Self, String> {
+        let bytes = match std::fs::read(env.auth_dir().join(STORE_FILE)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("Cannot read stored credentials: {}", e)),
+        };
+        serde_json::from_slice(&unseal(bytes)).map_err(|e| format!("Cannot read stored credentials: {}", e))
+    }
+
+    fn save(&self, env: &AppEnv) -> Result<(), String> {
+        let dir = env.auth_dir();
+        create_private_dir(&dir)?;
+        let txt = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        write_private(&dir.join(STORE_FILE), &seal(txt.as_bytes())?)?;
+        let _ = std::fs::remove_file(dir.join(LEGACY_NETRC_FILE));
+        Ok(())
+    }
+
+    fn cookies_path(env: &AppEnv, site: &str) -> PathBuf {
+        env.auth_dir().join("cookies").join(format!("{}.txt", site))
+    }
+
+    /// yt-dlp 以 extractor 的 NETRC_MACHINE 查找帳號 (nicovideo.jp -> niconico)；
+    /// 不在對照表中的網站以註冊網域的名稱猜測 (example.co.uk -> example)
+    pub fn netrc_machine(site: &str) -> &str {
+        let matches = |domain: &str| site == domain || site.ends_with(&format!(".{}", domain));
+        if let Some((_, machine)) = NETRC_MACHINES.iter().find(|(domain, _)| matches(domain)) {
+            return machine;
+        }
+        if let Some(name) = urls::site_extractor(site) {
+            return name;
+        }
+        let labels: Vec<&str> = site.split('.').collect();
+        match labels.len() {
+            0 | 1 => site,
+            n if n >= 3 && SECOND_LEVEL_LABELS.contains(&labels[n - 2]) => labels[n - 3],
+            n => labels[n - 2],
+        }
+    }
+
+    fn netrc_entry(site: &str, username: &str, password: &str) -> String {
+        format!("machine {} login {} password {}\n", Self::netrc_machine(site), username, password)
+    }
+
+    pub fn list(&self) -> Vec<CredentialSummary> {
+        self.sites.iter().map(|(site, cred)| {
+            let (kind, detail) = match cred {
+                Credential::CookiesFile => ("cookies_file", "cookies.txt".to_string()),
+                Credential::Browser { browser, profile } => {
+                    ("browser", profile.as_ref().map(|p| format!("{}:{}", browser, p)).unwrap_or_else(|| browser.clone()))
+                }
+                Credential::Login { username, .. } => ("login", username.clone()),
+            };
+            CredentialSummary { site: site.clone(), kind: kind.into(), detail }
+        }).collect()
+    }
+
+    /// 找出網址所屬網站的登入資訊 (子網域也適用，例如 music.youtube.com 使用 youtube.com 的設定)
+    pub fn find(&self, site: &str) -> Option<(&str, &Credential)> {
+        self.sites.iter()
+            .filter(|(key, _)| site == key.as_str() || site.ends_with(&format!(".{}", key)))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(key, cred)| (key.as_str(), cred))
+    }
+
+    fn set(env: &AppEnv, site: &str, cred: Credential) -> Result<(), String> {
+        let mut store = Self::load(env)?;
+        if store.sites.get(site) == Some(&Credential::CookiesFile) && cred != Credential::CookiesFile {
+            let _ = std::fs::remove_file(Self::cookies_path(env, site));
+        }
+        store.sites.insert(site.to_string(), cred);
+        store.save(env)
+    }
+
+    /// 匯入 cookies.txt：驗證格式後複製到資料目錄
+    pub fn import_cookies(env: &AppEnv, site: &str, source: &Path) -> Result<(), String> {
+        let site = site_key(site)?;
+        let text = std::fs::read_to_string(source).map_err(|e| e.to_string())?;
+        validate_cookies_txt(&text)?;
+        create_private_dir(&env.auth_dir())?;
+        create_private_dir(&env.auth_dir().join("cookies"))?;
+        write_private(&Self::cookies_path(env, &site), text.as_bytes())?;
+        Self::set(env, &site, Credential::CookiesFile)
+    }
+
+    pub fn use_browser(env: &AppEnv, site: &str, browser: &str, profile: Option<String>) -> Result<(), String> {
+        let site = site_key(site)?;
+        let browser = browser.trim().to_ascii_lowercase();
+        if !BROWSERS.contains(&browser.as_str()) {
+            return Err(format!("Unsupported browser: {}", browser));
+        }
+        let profile = profile.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+        Self::set(env, &site, Credential::Browser { browser, profile })
+    }
+
+    pub fn set_login(env: &AppEnv, site: &str, username: &str, password: &str) -> Result<(), String> {
+        let site = site_key(site)?;
+        // netrc 以空白分隔欄位，含空白或引號的值無法正確表示
+        let invalid = |s: &str| s.is_empty() || s.chars().any(|c| c.is_whitespace() || c == '"');
+        if invalid(username) || invalid(password) {
+            return Err("Username and password must be non-empty and cannot contain spaces or quotes".into());
+        }
+        Self::set(env, &site, Credential::Login { username: username.into(), password: password.into() })
+    }
+
+    pub fn remove(env: &AppEnv, site: &str) -> Result<(), String> {
+        let site = site_key(site)?;
+        let mut store = Self::load(env)?;
+        if store.sites.remove(&site) == Some(Credential::CookiesFile) {
+            let _ = std::fs::remove_file(Self::cookies_path(env, &site));
+        }
+        store.save(env)
+    }
+}
+
+/// [2026-10-19 新增] yt-dlp 的登入參數；帳號密碼寫在暫存 netrc 中，此值釋放時刪除，
+/// 因此必須保留到 yt-dlp 結束為止
+#[derive(Debug, Default)]
+pub struct AuthArgs {
+    pub args: Vec<String>,
+    netrc: Option<PathBuf>,
+}
+
+impl AuthArgs {
+    /// 暫存 netrc 的位置 (沒有使用帳號密碼時為 None)
+    pub fn netrc_path(&self) -> Option<&Path> {
+        self.netrc.as_deref()
+    }
+}
+
+impl Drop for AuthArgs {
+    fn drop(&mut self) {
+        if let Some(path) = &self.netrc {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// 依網址 (正規化後) 產生 yt-dlp 的登入參數；沒有設定 (或無法讀取登入資訊) 時參數為空
+pub fn args_for_url(env: &AppEnv, url: &str) -> AuthArgs {
+    let Ok(normalized) = urls::normalize(url) else { return AuthArgs::default() };
+    let Ok(store) = CredentialStore::load(env) else { return AuthArgs::default() };
+    let Some((site, cred)) = store.find(&normalized.site) else { return AuthArgs::default() };
+    match cred {
+        Credential::CookiesFile => AuthArgs {
+            args: vec!["--cookies".into(), CredentialStore::cookies_path(env, site).to_string_lossy().to_string()],
+            netrc: None,
+        },
+        Credential::Browser { browser, profile } => {
+            let spec = profile.as_ref().map(|p| format!("{}:{}", browser, p)).unwrap_or_else(|| browser.clone());
+            AuthArgs { args: vec!["--cookies-from-browser".into(), spec], netrc: None }
+        }
+        Credential::Login { username, password } => {
+            // auth/ 已由儲存時建立並限制權限，暫存檔沿用相同的保護
+            let n = NETRC_COUNTER.fetch_add(1, Ordering::Relaxed);
+            let path = env.auth_dir().join(format!("netrc-{}-{}", std::process::id(), n));
+            if write_private(&path, CredentialStore::netrc_entry(site, username, password).as_bytes()).is_err() {
+                return AuthArgs::default();
+            }
+            AuthArgs {
+                args: vec!["--netrc".into(), "--netrc-location".into(), path.to_string_lossy().to_string()],
+                netrc: Some(path),
+            }
+        }
+    }
+}
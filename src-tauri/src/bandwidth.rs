@@ -0,0 +1,122 @@
+// [2026-10-19 新增] 頻寬限制：全域上限、各任務上限，以及依時段調整的排程 (例如上班時間 2 MB/s、夜間不限速)。
+// yt-dlp 以 --limit-rate 限速；組件下載 (perform_download) 由 Throttle 在讀取串流時自行節流。
+// 時段切換時，執行中的 yt-dlp 會以新的速率重新啟動 (沿用 .part 續傳)，之後的任務直接使用新的速率。
+// 已進入合併 / 轉檔等後製階段時不再重新啟動，避免中斷 ffmpeg 造成輸出損壞。
+use crate::settings::{BandwidthSettings, ThrottleRule};
+use chrono::{Local, NaiveTime};
+use std::time::{Duration, Instant};
+
+/// 執行中的任務多久檢查一次時段是否變更
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 解析 "HH:MM" 或 "HH:MM:SS"
+pub fn parse_clock(raw: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(raw.trim(), "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(raw.trim(), "%H:%M"))
+        .map_err(|_| format!("Invalid time of day: {}", raw))
+}
+
+/// 時段是否包含 t (含開始、不含結束；開始晚於結束表示跨午夜，兩者相同表示整天)
+pub fn window_contains(start: NaiveTime, end: NaiveTime, t: NaiveTime) -> bool {
+    if start == end {
+        true
+    } else if start < end {
+        start <= t && t < end
+    } else {
+        t >= start || t < end
+    }
+}
+
+impl ThrottleRule {
+    fn contains(&self, t: NaiveTime) -> bool {
+        match (parse_clock(&self.start), parse_clock(&self.end)) {
+            (Ok(start), Ok(end)) => window_contains(start, end, t),
+            _ => false,
+        }
+    }
+}
+
+impl BandwidthSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.limit == Some(0) {
+            return Err("Rate limit must be greater than zero".into());
+        }
+        for rule in &self.schedule {
+            parse_clock(&rule.start)?;
+            parse_clock(&rule.end)?;
+            if rule.limit == Some(0) {
+                return Err("Rate limit must be greater than zero".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// 指定時間的全域上限 (bytes/s)；第一個符合的時段優先，沒有符合時使用 limit
+    pub fn limit_at(&self, t: NaiveTime) -> Option<u64> {
+        match self.schedule.iter().find(|rule| rule.contains(t)) {
+            Some(rule) => rule.limit,
+            None => self.limit,
+        }
+    }
+
+    pub fn current_limit(&self) -> Option<u64> {
+        self.limit_at(Local::now().time())
+    }
+
+    /// 任務實際使用的上限：全域 (依時段) 與任務本身取較小者
+    pub fn effective_limit(&self, job_limit: Option<u64>) -> Option<u64> {
+        match (self.current_limit(), job_limit.filter(|l| *l > 0)) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// [2026-10-19 新增] yt-dlp 後製階段的輸出前綴 (下載已完成，正在由 ffmpeg 處理檔案)
+const POST_PROCESS_PREFIXES: [&str; 10] = [
+    "[Merger]", "[ExtractAudio]", "[Fixup", "[VideoRemuxer]", "[VideoConvertor]",
+    "[ModifyChapters]", "[EmbedThumbnail]", "[EmbedSubtitle]", "[Metadata]", "[SplitChapters]",
+];
+
+/// 這一行輸出是否表示 yt-dlp 已進入後製階段 (此時不能為了套用新速率而重新啟動)
+pub fn is_post_processing(line: &str) -> bool {
+    POST_PROCESS_PREFIXES.iter().any(|p| line.starts_with(p))
+}
+
+pub fn yt_dlp_args(limit: Option<u64>) -> Vec<String> {
+    match limit {
+        Some(bps) => vec!["--limit-rate".into(), bps.to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// 串流讀取用的節流器：每收到一段資料就計算是否超過目前速率，超過則等待
+pub struct Throttle {
+    limit: Option<u64>,
+    window_start: Instant,
+    window_bytes: u64,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self { limit: None, window_start: Instant::now(), window_bytes: 0 }
+    }
+}
+
+impl Throttle {
+    /// limit 可在每次呼叫時改變 (時段切換)，改變後重新計算
+    pub async fn consume(&mut self, bytes: u64, limit: Option<u64>) {
+        if limit != self.limit {
+            self.limit = limit;
+            self.window_start = Instant::now();
+            self.window_bytes = 0;
+        }
+        let Some(bps) = self.limit else { return };
+        self.window_bytes += bytes;
+        let target = Duration::from_secs_f64(self.window_bytes as f64 / bps as f64);
+        let elapsed = self.window_start.elapsed();
+        if target > elapsed {
+            tokio::time::sleep(target - elapsed).await;
+        }
+    }
+}
@@ -0,0 +1,203 @@
+// [2026-10-19 新增] 批次匯入：從文字檔 (一行一個網址，可加 # 註解)、CSV (url, profile, folder, filename 欄位) 或 JSON 讀入大量網址，
+// 檢查並去除重複 (同一檔案內與佇列中尚未完成的任務) 後一次加入下載佇列，回傳每一行的處理結果。
+use crate::env::AppEnv;
+use crate::queue::{DownloadQueue, JobSchedule};
+use crate::urls;
+use crate::ytdlp::DownloadRequest;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// CSV 沒有標題列時的欄位順序
+const CSV_COLUMNS: [&str; 4] = ["url", "profile", "folder", "filename"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+impl BatchFormat {
+    /// 依副檔名判斷，無法判斷時視為文字檔
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().map(|e| e.to_string_lossy().to_ascii_lowercase()).as_deref() {
+            Some("csv") => Self::Csv,
+            Some("json") => Self::Json,
+            _ => Self::Text,
+        }
+    }
+}
+
+/// 匯入檔中的一筆資料
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct BatchRow {
+    /// 行號 (JSON 為第幾個項目)，從 1 開始
+    #[serde(skip)]
+    pub line: usize,
+    pub url: String,
+    pub profile: Option<String>,
+    pub folder: Option<String>,
+    pub filename: Option<String>,
+}
+
+/// 呼叫端提供的預設值 (各列未指定時使用)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BatchDefaults {
+    pub folder: Option<String>,
+    pub profile: Option<String>,
+    pub lang: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchLine {
+    pub line: usize,
+    pub url: String,
+    /// 接受時為任務 ID，其餘為原因
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchReport {
+    pub accepted: Vec<BatchLine>,
+    pub duplicate: Vec<BatchLine>,
+    pub invalid: Vec<BatchLine>,
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value.map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+fn parse_text(text: &str) -> Vec<BatchRow> {
+    text.lines().enumerate().filter_map(|(i, line)| {
+        // 行尾註解需以空白隔開，網址中的 #片段 不受影響
+        let line = line.split(" #").next().unwrap_or("").trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            return None;
+        }
+        Some(BatchRow { line: i + 1, url: line.to_string(), ..Default::default() })
+    }).collect()
+}
+
+fn parse_csv(text: &str) -> Result<Vec<BatchRow>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .comment(Some(b'#'))
+        .from_reader(text.as_bytes());
+
+    let mut rows = Vec::new();
+    let mut columns: HashMap<String, usize> = CSV_COLUMNS.iter().enumerate().map(|(i, c)| (c.to_string(), i)).collect();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("Invalid CSV: {}", e))?;
+        let line = record.position().map(|p| p.line() as usize).unwrap_or(i + 1);
+        // 第一列含有 url 欄位名稱時視為標題列
+        if i == 0 && record.iter().any(|c| c.eq_ignore_ascii_case("url")) {
+            columns = record.iter().enumerate().map(|(i, c)| (c.to_ascii_lowercase(), i)).collect();
+            continue;
+        }
+        let cell = |name: &str| non_empty(columns.get(name).and_then(|i| record.get(*i)));
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        rows.push(BatchRow {
+            line,
+            url: cell("url").unwrap_or_default(),
+            profile: cell("profile"),
+            folder: cell("folder"),
+            filename: cell("filename"),
+        });
+    }
+    Ok(rows)
+}
+
+/// JSON：網址字串陣列，或含 url / profile / folder / filename 的物件陣列
+fn parse_json(text: &str) -> Result<Vec<BatchRow>, String> {
+    let items: Vec<serde_json::Value> = serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e))?;
+    Ok(items.into_iter().enumerate().map(|(i, item)| {
+        let mut row = match item {
+            serde_json::Value::String(url) => BatchRow { url, ..Default::default() },
+            other => serde_json::from_value(other).unwrap_or_default(),
+        };
+        row.line = i + 1;
+        row
+    }).collect())
+}
+
+pub fn parse(text: &str, format: BatchFormat) -> Result<Vec<BatchRow>, String> {
+    match format {
+        BatchFormat::Text => Ok(parse_text(text)),
+        BatchFormat::Csv => parse_csv(text),
+        BatchFormat::Json => parse_json(text),
+    }
+}
+
+/// 檢查並去除重複後一次加入佇列
+pub fn import_rows(env: &AppEnv, queue: &DownloadQueue, rows: Vec<BatchRow>, defaults: &BatchDefaults) -> Result<BatchReport, String> {
+    let mut report = BatchReport::default();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut accepted: Vec<(usize, String, DownloadRequest)> = Vec::new();
+    let lang = if defaults.lang.is_empty() { "en" } else { defaults.lang.as_str() };
+
+    for row in rows {
+        let line = |detail: String| BatchLine { line: row.line, url: row.url.clone(), detail };
+        let normalized = match urls::normalize(&row.url) {
+            Ok(n) => n,
+            Err(e) => {
+                report.invalid.push(line(e.to_string()));
+                continue;
+            }
+        };
+        if let Some(first) = seen.get(&normalized.url) {
+            report.duplicate.push(line(format!("Duplicate of line {}", first)));
+            continue;
+        }
+        if queue.has_pending_url(&normalized.url) {
+            report.duplicate.push(line("Already queued".into()));
+            continue;
+        }
+        let profile_name = row.profile.as_deref().or(defaults.profile.as_deref());
+        let profile = match env.settings.profile(profile_name) {
+            Ok(profile) => profile,
+            Err(e) => {
+                report.invalid.push(line(e));
+                continue;
+            }
+        };
+        // 相對路徑的資料夾放在預設資料夾之下
+        let folder = match (row.folder.as_deref(), defaults.folder.as_deref()) {
+            (Some(f), Some(base)) if Path::new(f).is_relative() => Some(Path::new(base).join(f).to_string_lossy().to_string()),
+            (Some(f), _) => Some(f.to_string()),
+            (None, base) => base.map(str::to_string),
+        };
+        let Some(folder) = folder.filter(|f| !f.trim().is_empty()) else {
+            report.invalid.push(line("No download folder".into()));
+            continue;
+        };
+
+        let mut request = DownloadRequest::new(&normalized.url, &profile.mode, &profile.quality, folder, lang);
+        request.options = profile.options;
+        request.options.file_name = row.filename.clone();
+        request.profile = Some(profile.name);
+        seen.insert(normalized.url, row.line);
+        accepted.push((row.line, row.url.clone(), request));
+    }
+
+    let (lines, requests): (Vec<(usize, String)>, Vec<DownloadRequest>) = accepted.into_iter().map(|(l, u, r)| ((l, u), r)).unzip();
+    let jobs = queue.enqueue_all(requests, JobSchedule::default())?;
+    report.accepted = lines.into_iter().zip(jobs).map(|((line, url), job)| BatchLine { line, url, detail: job.id }).collect();
+    Ok(report)
+}
+
+/// 讀取匯入檔 (格式依副檔名判斷) 並加入佇列
+pub fn import_file(env: &AppEnv, queue: &DownloadQueue, path: &Path, defaults: &BatchDefaults) -> Result<BatchReport, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    // Excel 匯出的 UTF-8 CSV 開頭常帶 BOM
+    let text = text.trim_start_matches('\u{feff}');
+    let rows = parse(text, BatchFormat::from_path(path))?;
+    import_rows(env, queue, rows, defaults)
+}
@@ -0,0 +1,117 @@
+// [2026-10-19 新增] 影片資訊快取：analyze_video 取得的 --dump-json 結果以正規化網址為 key 保存，
+// download_video 直接沿用並以 --load-info-json 交給 yt-dlp，不必再解析一次
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    fetched: SystemTime,
+    info: Arc<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    url: String,
+    fetched: u64,
+    info: serde_json::Value,
+}
+
+#[derive(Debug)]
+pub struct MetadataCache {
+    ttl: Duration,
+    /// 設定後同時寫入磁碟，重新啟動後仍可使用
+    disk_dir: Option<PathBuf>,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl MetadataCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, disk_dir: None, entries: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn with_disk_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.disk_dir = Some(dir.into());
+        self
+    }
+
+    fn is_fresh(&self, fetched: SystemTime) -> bool {
+        SystemTime::now().duration_since(fetched).map(|age| age < self.ttl).unwrap_or(true)
+    }
+
+    /// 取得尚未過期的資訊；記憶體沒有時再找磁碟
+    pub fn get(&self, url: &str) -> Option<Arc<serde_json::Value>> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.get(url) {
+            if self.is_fresh(entry.fetched) {
+                return Some(entry.info.clone());
+            }
+            entries.remove(url);
+        }
+
+        let entry = self.read_disk(url)?;
+        entries.insert(url.to_string(), entry.clone());
+        Some(entry.info)
+    }
+
+    pub fn insert(&self, url: &str, info: serde_json::Value) -> Arc<serde_json::Value> {
+        let entry = CacheEntry { fetched: SystemTime::now(), info: Arc::new(info) };
+        self.write_disk(url, &entry);
+        let info = entry.info.clone();
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).insert(url.to_string(), entry);
+        info
+    }
+
+    pub fn remove(&self, url: &str) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).remove(url);
+        if let Some(path) = self.disk_path(url) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    fn disk_path(&self, url: &str) -> Option<PathBuf> {
+        self.disk_dir.as_ref().map(|dir| dir.join(format!("{}.json", cache_key(url))))
+    }
+
+    fn read_disk(&self, url: &str) -> Option<CacheEntry> {
+        let txt = std::fs::read_to_string(self.disk_path(url)?).ok()?;
+        let disk: DiskEntry = serde_json::from_str(&txt).ok()?;
+        let fetched = UNIX_EPOCH + Duration::from_secs(disk.fetched);
+        (disk.url == url && self.is_fresh(fetched)).then(|| CacheEntry { fetched, info: Arc::new(disk.info) })
+    }
+
+    fn write_disk(&self, url: &str, entry: &CacheEntry) {
+        let Some(path) = self.disk_path(url) else { return };
+        let disk = DiskEntry {
+            url: url.to_string(),
+            fetched: entry.fetched.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            info: (*entry.info).clone(),
+        };
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(txt) = serde_json::to_string(&disk) {
+            let _ = std::fs::write(path, txt);
+        }
+    }
+}
+
+/// 由網址產生檔名用的 key
+pub fn cache_key(url: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// 把資訊寫成 yt-dlp 可讀取的 .info.json，供 --load-info-json 使用
+pub fn write_info_json(dir: &Path, url: &str, info: &serde_json::Value) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{}.info.json", cache_key(url)));
+    let txt = serde_json::to_string(info).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    Ok(path)
+}
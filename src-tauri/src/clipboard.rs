@@ -0,0 +1,161 @@
+// [2026-10-19 新增] 剪貼簿監看：在背景辨識使用者複製的影片網址 (需在設定中開啟)。
+// 網址正規化後去除重複 (youtu.be 與 watch?v= 視為同一部)，略過忽略清單中的網域與 yt-dlp 不支援的網站，
+// 再依設定只通知前端、自動解析，或以預設下載設定檔加入佇列。剪貼簿的讀取由 lib.rs 透過 clipboard-manager 插件進行。
+use crate::auth::site_key;
+use crate::env::AppEnv;
+use crate::events::{self, EventSink};
+use crate::queue::{DownloadQueue, JobSchedule};
+use crate::settings::ClipboardSettings;
+use crate::urls::{self, NormalizedUrl, UrlKind};
+use crate::ytdlp::{self, DownloadRequest, VideoMetadata};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+pub const EVT_CLIPBOARD_LINK: &str = "clipboard-link";
+
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 記住最近偵測過的網址數量
+const MAX_REMEMBERED: usize = 200;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardAction {
+    /// 只送出「偵測到連結」事件
+    #[default]
+    Notify,
+    /// 自動解析影片資訊
+    Analyze,
+    /// 以預設下載設定檔加入佇列
+    Enqueue,
+}
+
+/// 送給前端的偵測結果
+#[derive(Debug, Serialize)]
+pub struct ClipboardPayload {
+    pub url: String,
+    pub site: String,
+    pub kind: UrlKind,
+    pub action: ClipboardAction,
+    pub metadata: Option<VideoMetadata>,
+    pub job_id: Option<String>,
+    pub error: Option<String>,
+}
+
+impl ClipboardSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for domain in &self.ignore_domains {
+            site_key(domain)?;
+        }
+        if self.action == ClipboardAction::Enqueue && self.download_dir.as_deref().is_none_or(|d| d.trim().is_empty()) {
+            return Err("Download folder is required to enqueue copied links".into());
+        }
+        Ok(())
+    }
+
+    pub fn is_ignored(&self, site: &str) -> bool {
+        self.ignore_domains.iter()
+            .filter_map(|d| site_key(d).ok())
+            .any(|d| site == d || site.ends_with(&format!(".{}", d)))
+    }
+}
+
+/// 找出文字中所有的 http(s) 網址 (去除結尾的標點符號)，回傳正規化後不重複的結果
+pub fn extract_urls(text: &str) -> Vec<NormalizedUrl> {
+    let re = Regex::new(r#"https?://[^\s<>"'`]+"#).unwrap();
+    let mut found: Vec<NormalizedUrl> = Vec::new();
+    for m in re.find_iter(text) {
+        let raw = m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}']);
+        if let Ok(normalized) = urls::normalize(raw) {
+            if !found.iter().any(|f| f.url == normalized.url) {
+                found.push(normalized);
+            }
+        }
+    }
+    found
+}
+
+/// 追蹤剪貼簿內容的變化；啟動 (或重新開啟) 後第一次讀到的內容視為既有內容，不會觸發
+#[derive(Debug, Default)]
+pub struct ClipboardWatcher {
+    primed: bool,
+    last_text: String,
+    recent: VecDeque<String>,
+}
+
+impl ClipboardWatcher {
+    /// 監看關閉時呼叫，重新開啟後不處理關閉前就已複製的內容
+    pub fn reset(&mut self) {
+        self.primed = false;
+    }
+
+    /// 剪貼簿內容變更時回傳新出現的網址
+    pub fn observe(&mut self, text: &str, settings: &ClipboardSettings) -> Vec<NormalizedUrl> {
+        if self.primed && text == self.last_text {
+            return Vec::new();
+        }
+        self.last_text = text.to_string();
+        if !self.primed {
+            self.primed = true;
+            return Vec::new();
+        }
+        let mut fresh = Vec::new();
+        for url in extract_urls(text) {
+            if settings.is_ignored(&url.site) || self.recent.contains(&url.url) {
+                continue;
+            }
+            if self.recent.len() >= MAX_REMEMBERED {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(url.url.clone());
+            fresh.push(url);
+        }
+        fresh
+    }
+}
+
+/// 依設定處理偵測到的網址；不是已知影音網站或 yt-dlp 不支援時略過並回傳 None
+pub async fn handle(env: &AppEnv, sink: &dyn EventSink, queue: &DownloadQueue, url: NormalizedUrl) -> Option<ClipboardPayload> {
+    // [2026-10-19 修改] 無法取得 extractor 清單時 prevalidate 會放行，剪貼簿另外限定對照表中的影音網站
+    let url = urls::prevalidate(env, &url.url, true).await.ok().filter(|u| urls::site_extractor(&u.site).is_some())?;
+    let settings = &env.settings.clipboard;
+    let mut payload = ClipboardPayload {
+        url: url.url.clone(),
+        site: url.site.clone(),
+        kind: url.kind,
+        action: settings.action,
+        metadata: None,
+        job_id: None,
+        error: None,
+    };
+
+    match settings.action {
+        ClipboardAction::Notify => {}
+        ClipboardAction::Analyze => match ytdlp::analyze(env, sink, &url.url, &settings.lang).await {
+            Ok(metadata) => payload.metadata = Some(metadata),
+            Err(e) => payload.error = Some(e),
+        },
+        // [2026-10-19 修正] 與批次匯入相同，佇列中尚未完成的網址不重複加入
+        ClipboardAction::Enqueue if queue.has_pending_url(&url.url) => payload.error = Some("Already queued".into()),
+        ClipboardAction::Enqueue => {
+            // 未指定設定檔時一定取得到 (內建預設)
+            let profile = env.settings.profile(None).unwrap_or_default();
+            let dir = settings.download_dir.clone().unwrap_or_default();
+            let mut request = DownloadRequest::new(&url.url, &profile.mode, &profile.quality, dir, &settings.lang);
+            request.options = profile.options;
+            request.profile = Some(profile.name);
+            match queue.enqueue(request, JobSchedule::default()) {
+                Ok(job) => {
+                    payload.job_id = Some(job.id);
+                    events::emit(sink, crate::queue::EVT_QUEUE, queue.list());
+                }
+                Err(e) => payload.error = Some(e),
+            }
+        }
+    }
+
+    events::emit(sink, EVT_CLIPBOARD_LINK, &payload);
+    Some(payload)
+}
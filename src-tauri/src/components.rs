@@ -0,0 +1,363 @@
+// [2026-10-19 重構] 核心組件 (yt-dlp / ffmpeg / deno) 的偵測、修復與版本檢查
+// 從 lib.rs 拆出，所有路徑與網址改由 AppEnv 提供
+use crate::archive;
+use crate::bandwidth::Throttle;
+use crate::env::AppEnv;
+use crate::events::{self, get_msg, DownloadPayload, EventSink};
+use crate::network;
+use crate::process;
+use crate::settings::ComponentPaths;
+use futures_util::StreamExt; // 用於串流下載
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const VERSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Component {
+    YtDlp,
+    Ffmpeg,
+    Deno,
+}
+
+impl Component {
+    pub const ALL: [Component; 3] = [Component::YtDlp, Component::Ffmpeg, Component::Deno];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Component::YtDlp => "yt-dlp",
+            Component::Ffmpeg => "ffmpeg",
+            Component::Deno => "deno",
+        }
+    }
+
+    /// 依平台決定執行檔名稱 (Windows 加上 .exe)
+    pub fn file_name(self) -> String {
+        format!("{}{}", self.name(), std::env::consts::EXE_SUFFIX)
+    }
+}
+
+// [2026-10-19 新增] 組件的來源位置，依搜尋順序排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentSource {
+    /// 設定中手動指定的路徑
+    Configured,
+    /// 使用者資料目錄 (修復下載的位置)
+    UserData,
+    /// 主程式旁 (舊版的擺放方式)
+    AppDir,
+    /// 系統 PATH
+    SystemPath,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocatedComponent {
+    pub component: Component,
+    pub path: PathBuf,
+    pub source: ComponentSource,
+}
+
+/// 回報給前端的組件狀態
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentStatus {
+    pub component: Component,
+    pub found: Option<LocatedComponent>,
+    pub install_path: PathBuf,
+}
+
+// [2026-10-19 新增] 組件定位器：依序檢查設定路徑、使用者資料目錄、主程式目錄與 PATH
+// 解決安裝於 Program Files / /usr/bin 等唯讀位置時無法修復，以及無法使用系統 ffmpeg 的問題
+#[derive(Debug, Clone)]
+pub struct ComponentLocator {
+    pub configured: ComponentPaths,
+    pub user_dir: PathBuf,
+    pub app_dir: PathBuf,
+    pub search_path: Option<OsString>,
+}
+
+impl ComponentLocator {
+    fn configured_path(&self, component: Component) -> Option<&PathBuf> {
+        match component {
+            Component::YtDlp => self.configured.yt_dlp.as_ref(),
+            Component::Ffmpeg => self.configured.ffmpeg.as_ref(),
+            Component::Deno => self.configured.deno.as_ref(),
+        }
+    }
+
+    pub fn locate(&self, component: Component) -> Option<LocatedComponent> {
+        let file_name = component.file_name();
+        let found = |path: PathBuf, source| Some(LocatedComponent { component, path, source });
+
+        if let Some(path) = self.configured_path(component) {
+            // 設定的可以是執行檔本身，也可以是所在資料夾
+            let path = if path.is_dir() { path.join(&file_name) } else { path.clone() };
+            if path.is_file() {
+                return found(path, ComponentSource::Configured);
+            }
+        }
+
+        let user = self.user_dir.join(&file_name);
+        if user.is_file() {
+            return found(user, ComponentSource::UserData);
+        }
+
+        let app = self.app_dir.join(&file_name);
+        if app.is_file() {
+            return found(app, ComponentSource::AppDir);
+        }
+
+        if let Some(search_path) = &self.search_path {
+            for dir in std::env::split_paths(search_path) {
+                let candidate = dir.join(&file_name);
+                if candidate.is_file() {
+                    return found(candidate, ComponentSource::SystemPath);
+                }
+            }
+        }
+        None
+    }
+
+    /// 修復下載一律安裝到使用者資料目錄，避免寫入唯讀的安裝位置
+    pub fn install_path(&self, component: Component) -> PathBuf {
+        self.user_dir.join(component.file_name())
+    }
+
+    pub fn status(&self) -> Vec<ComponentStatus> {
+        Component::ALL.iter().map(|&component| ComponentStatus {
+            component,
+            found: self.locate(component),
+            install_path: self.install_path(component),
+        }).collect()
+    }
+}
+
+fn source_label(lang: &str, source: ComponentSource) -> String {
+    match source {
+        ComponentSource::Configured => get_msg(lang, "自訂路徑", "configured path"),
+        ComponentSource::UserData => get_msg(lang, "使用者資料夾", "user data dir"),
+        ComponentSource::AppDir => get_msg(lang, "程式目錄", "app dir"),
+        ComponentSource::SystemPath => get_msg(lang, "系統 PATH", "system PATH"),
+    }
+}
+
+// 內部輔助函數：執行實際的帶網速下載
+pub async fn perform_download(
+    env: &AppEnv,
+    sink: &dyn EventSink,
+    url: &str,
+    save_path: &PathBuf,
+    base_prog: f64,
+    max_prog: f64
+) -> Result<(), String> {
+    // [2026-10-19 修改] 套用代理 / 來源位址等網路設定
+    let client = network::client(&env.settings.network, url)?;
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    // [2026-10-19 新增] 非 2xx 回應視為失敗，避免把錯誤頁存成執行檔
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}: {}", response.status().as_u16(), url));
+    }
+    let total_size = response.content_length().unwrap_or(0);
+
+    let mut file = std::fs::File::create(save_path).map_err(|e| e.to_string())?;
+    let mut downloaded: u64 = 0;
+    let start_time = std::time::Instant::now();
+    let mut stream = response.bytes_stream();
+    // [2026-10-19 新增] 依目前時段的全域上限節流
+    let mut throttle = Throttle::default();
+
+    while let Some(item) = stream.next().await {
+        let chunk = item.map_err(|e| e.to_string())?;
+        file.write_all(&chunk).map_err(|e| e.to_string())?;
+        downloaded += chunk.len() as u64;
+        throttle.consume(chunk.len() as u64, env.settings.bandwidth.current_limit()).await;
+
+        let elapsed = start_time.elapsed().as_secs_f64();
+        if elapsed > 0.5 { // 每 0.5 秒更新一次數據
+            let speed_bps = downloaded as f64 / elapsed;
+            let progress_ratio = if total_size > 0 { downloaded as f64 / total_size as f64 } else { 0.0 };
+            let current_progress = base_prog + (progress_ratio * (max_prog - base_prog));
+
+            let speed_text = if speed_bps > 1024.0 * 1024.0 {
+                format!("{:.2} MB/s", speed_bps / (1024.0 * 1024.0))
+            } else {
+                format!("{:.2} KB/s", speed_bps / 1024.0)
+            };
+
+            let eta_text = if total_size > 0 && speed_bps > 0.0 {
+                let remaining_secs = total_size.saturating_sub(downloaded) as f64 / speed_bps;
+                format!("{:02}:{:02}", (remaining_secs / 60.0) as i32, (remaining_secs % 60.0) as i32)
+            } else {
+                "--:--".into()
+            };
+
+            events::progress(sink, DownloadPayload {
+                progress: current_progress,
+                speed: speed_text,
+                eta: eta_text,
+            });
+        }
+    }
+    Ok(())
+}
+
+// [2026-01-18 修改] 偵測邏輯新增 deno.exe，確保環境完整
+// [2026-10-19 修改] 改用組件定位器，並回報每個組件的實際來源
+pub fn check(env: &AppEnv, sink: &dyn EventSink, lang: &str) -> bool {
+    let statuses = env.locator().status();
+    let is_ok = statuses.iter().all(|st| st.found.is_some());
+    events::core_status(sink, is_ok);
+
+    for found in statuses.iter().filter_map(|st| st.found.as_ref()) {
+        events::log(sink, format!("🔎 {} ({}): {}", found.component.name(), source_label(lang, found.source), found.path.display()));
+    }
+
+    if !is_ok {
+        let missing: Vec<String> = statuses.iter()
+            .filter(|st| st.found.is_none())
+            .map(|st| st.component.file_name())
+            .collect();
+        let log_txt = get_msg(lang,
+            &format!("⚠️ 核心組件不完整，缺失: {}", missing.join(", ")),
+            &format!("⚠️ Core components incomplete, missing: {}", missing.join(", "))
+        );
+        events::log(sink, log_txt);
+    }
+    is_ok
+}
+
+// [2026-01-18 修改] 修復程序新增 Deno 下載邏輯
+// [2026-10-19 修改] 缺失的組件一律安裝到使用者資料目錄
+pub async fn repair(env: &AppEnv, sink: &dyn EventSink, lang: &str) -> Result<String, String> {
+    let locator = env.locator();
+    let app_dir = &locator.user_dir;
+    std::fs::create_dir_all(app_dir).map_err(|e| e.to_string())?;
+    let yt_path = locator.install_path(Component::YtDlp);
+    let ff_path = locator.install_path(Component::Ffmpeg);
+
+    let yt_missing = locator.locate(Component::YtDlp).is_none();
+    let ff_missing = locator.locate(Component::Ffmpeg).is_none();
+    let de_missing = locator.locate(Component::Deno).is_none(); // [2026-01-18 新增]
+    // [2026-10-19 新增] 此平台沒有可自動安裝的版本，需由使用者以套件管理員安裝
+    let mut unsupported = Vec::new();
+
+    events::log(sink, get_msg(lang, "🚀 啟動修復程序：正在下載缺失組件...", "🚀 Starting repair: Downloading missing components..."));
+
+    // 下載 yt-dlp
+    if yt_missing {
+        match env.yt_dlp_download_url() {
+            Some(url) => {
+                let name = Component::YtDlp.file_name();
+                events::log(sink, get_msg(lang, &format!("⬇️ 正在獲取 {}...", name), &format!("⬇️ Downloading {}...", name)));
+                perform_download(env, sink, &url, &yt_path, 0.0, 30.0).await?;
+                archive::mark_executable(&yt_path)?;
+            }
+            None => unsupported.push(Component::YtDlp),
+        }
+    }
+
+    // 下載 FFmpeg
+    if ff_missing {
+        match env.ffmpeg_download_url() {
+            Some(url) => {
+                let name = Component::Ffmpeg.file_name();
+                events::log(sink, get_msg(lang, &format!("⬇️ 正在獲取 {} (此檔案較大)...", name), &format!("⬇️ Downloading {} (Large file)...", name)));
+
+                let zip_path = app_dir.join("ffmpeg.zip");
+                perform_download(env, sink, &url, &zip_path, 30.0, 80.0).await?;
+
+                // [2026-10-19 修正] 改用原生解壓縮，不再把路徑拼進 PowerShell 指令字串
+                events::log(sink, get_msg(lang, "📦 正在解壓並部署 FFmpeg...", "📦 Extracting and deploying FFmpeg..."));
+                let extracted = archive::extract_file(&zip_path, &name, &ff_path);
+                let _ = std::fs::remove_file(&zip_path);
+                extracted?;
+            }
+            None => unsupported.push(Component::Ffmpeg),
+        }
+    }
+
+    // [2026-01-18 新增] 下載 Deno 引擎 (YouTube SABR 解碼必需)
+    if de_missing {
+        match env.deno_download_url() {
+            Some(url) => {
+                events::log(sink, get_msg(lang, "⬇️ 正在獲取解碼引擎 (Deno)...", "⬇️ Downloading Decode Engine (Deno)..."));
+
+                let de_zip_path = app_dir.join("deno.zip");
+                // 下載進度分配在 80% 到 95%
+                perform_download(env, sink, &url, &de_zip_path, 80.0, 95.0).await?;
+
+                events::log(sink, get_msg(lang, "📦 正在部署解碼引擎...", "📦 Deploying Decode Engine..."));
+                let extracted = archive::extract_file(&de_zip_path, &Component::Deno.file_name(), &locator.install_path(Component::Deno));
+                let _ = std::fs::remove_file(&de_zip_path);
+                extracted?;
+            }
+            None => unsupported.push(Component::Deno),
+        }
+    }
+    let names = unsupported.iter().map(|c| c.name()).collect::<Vec<_>>().join(", ");
+    if !unsupported.is_empty() {
+        events::log(sink, get_msg(
+            lang,
+            &format!("📦 此平台無法自動安裝 {}，請以系統的套件管理員安裝 (例如 brew / apt)", names),
+            &format!("📦 {} cannot be installed automatically on this platform; install it with your package manager (e.g. brew / apt)", names),
+        ));
+    }
+    let is_ready = Component::ALL.iter().all(|&c| locator.locate(c).is_some());
+    events::core_status(sink, is_ready);
+
+    if is_ready {
+        events::progress(sink, DownloadPayload { progress: 100.0, speed: "Done".into(), eta: "00:00".into() });
+        events::log(sink, get_msg(lang, "✅ 核心組件修復完成！", "✅ Core components repair completed!"));
+        Ok("OK".into())
+    } else {
+        events::progress(sink, DownloadPayload { progress: 0.0, speed: "".into(), eta: "".into() });
+        events::log(sink, get_msg(lang, "❌ 修復失敗，請檢查網路。", "❌ Repair failed."));
+        if unsupported.is_empty() {
+            Err("Fail".into())
+        } else {
+            Err(format!("Install {} with your system package manager", names))
+        }
+    }
+}
+
+// [2026-01-18 新增] 獲取本地 yt-dlp 版本號
+pub async fn local_yt_dlp_version(yt_exe: &Path) -> Result<String, String> {
+    if !yt_exe.exists() {
+        return Ok("none".into());
+    }
+
+    let mut cmd = process::command(yt_exe);
+    cmd.args(["--version"]);
+
+    let output = process::run_capture(cmd, VERSION_TIMEOUT).await?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(version)
+}
+
+// [2026-01-18 新增] 獲取遠端 GitHub 最新 yt-dlp 版本號 (方案 B)
+pub async fn remote_yt_dlp_version(env: &AppEnv) -> Result<String, String> {
+    let api_url = env.yt_dlp_latest_release_api();
+    let client = network::client_builder(&env.settings.network, &api_url)?
+        .user_agent("Tauri-Video-Downloader") // GitHub API 要求必須有 User-Agent
+        .build()
+        .map_err(|e: reqwest::Error| e.to_string())?;
+
+    let resp = client.get(&api_url)
+        .send()
+        .await
+        .map_err(|e: reqwest::Error| e.to_string())?;
+
+    // [修正] 明確標註反序列化的類型為 serde_json::Value
+    let json = resp.json::<serde_json::Value>()
+        .await
+        .map_err(|e: reqwest::Error| e.to_string())?;
+
+    // GitHub 的 tag_name 通常是日期格式，如 2025.01.15
+    let latest_version = json["tag_name"].as_str().unwrap_or("").to_string();
+
+    Ok(latest_version)
+}
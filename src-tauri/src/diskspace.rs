@@ -0,0 +1,169 @@
+// [2026-10-19 新增] 下載前的大小估計與磁碟空間檢查：過去空間不足時要等 yt-dlp 下載到一半才出現難懂的錯誤。
+// 依選擇的格式 (filesize / filesize_approx，沒有時以 tbr × 長度) 估計輸出大小，再加上合併 / 轉檔時暫存檔需要的空間，
+// 只下載片段或章節時依選取長度占全片的比例縮小，再與目的地所在磁碟的可用空間比較。確定不夠時拒絕下載；只是估計值且輸出本身放得下時僅提出警告。
+use crate::sections::Sections;
+use crate::ytdlp::{parse_chapters, DownloadRequest};
+use serde::Serialize;
+use std::path::Path;
+
+pub const EVT_SIZE_ESTIMATE: &str = "size-estimate";
+
+/// 額外保留的空間 (檔案系統中繼資料、yt-dlp 的 .part / .ytdl 等)
+const MARGIN_BYTES: u64 = 50 * 1024 * 1024;
+
+/// 音訊模式轉出的 mp3 位元率 (與 --audio-quality 256K 相同)
+const MP3_BITRATE_KBPS: f64 = 256.0;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SizeEstimate {
+    /// 預估的輸出檔大小
+    pub output_bytes: Option<u64>,
+    /// 下載過程中最多需要的空間 (含暫存檔與保留空間)
+    pub required_bytes: Option<u64>,
+    /// 目的地磁碟的可用空間
+    pub available_bytes: Option<u64>,
+    /// 是否有任何部分是估計值 (filesize_approx 或位元率 × 長度)
+    pub approximate: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpaceCheck {
+    Ok,
+    /// 無法估計大小或取得可用空間
+    Unknown,
+    /// 可能不夠 (估計值)，提出警告但繼續
+    Tight,
+    Insufficient,
+}
+
+/// 單一格式的大小 (bytes) 與是否為估計值
+pub fn format_size(format: &serde_json::Value, duration: Option<f64>) -> Option<(u64, bool)> {
+    if let Some(size) = format["filesize"].as_u64() {
+        return Some((size, false));
+    }
+    if let Some(size) = format["filesize_approx"].as_u64() {
+        return Some((size, true));
+    }
+    let kbps = format["tbr"].as_f64()?;
+    Some(((kbps * 1000.0 / 8.0 * duration?) as u64, true))
+}
+
+fn has_video(f: &serde_json::Value) -> bool {
+    f["vcodec"].as_str().is_some_and(|c| c != "none")
+}
+
+fn has_audio(f: &serde_json::Value) -> bool {
+    f["acodec"].as_str().is_some_and(|c| c != "none")
+}
+
+fn best_by(formats: &[serde_json::Value], filter: impl Fn(&serde_json::Value) -> bool, key: impl Fn(&serde_json::Value) -> (i64, i64)) -> Option<&serde_json::Value> {
+    formats.iter().filter(|f| filter(f)).max_by_key(|f| key(f))
+}
+
+fn video_rank(f: &serde_json::Value) -> (i64, i64) {
+    (f["height"].as_i64().unwrap_or_else(|| resolution_height(f)), f["tbr"].as_f64().unwrap_or(0.0) as i64)
+}
+
+fn audio_rank(f: &serde_json::Value) -> (i64, i64) {
+    (f["abr"].as_f64().or(f["tbr"].as_f64()).unwrap_or(0.0) as i64, 0)
+}
+
+fn resolution_height(f: &serde_json::Value) -> i64 {
+    f["resolution"].as_str()
+        .and_then(|r| r.rsplit('x').next())
+        .and_then(|h| h.parse().ok())
+        .unwrap_or(0)
+}
+
+/// 模擬 yt-dlp 的格式選擇 (與 download 中的 -f 相同)，回傳會下載的格式
+pub fn selected_formats<'a>(info: &'a serde_json::Value, mode: &str, quality: &str) -> Vec<&'a serde_json::Value> {
+    let Some(formats) = info["formats"].as_array() else { return Vec::new() };
+    let video_only = |f: &serde_json::Value| has_video(f) && !has_audio(f);
+    let audio_only = |f: &serde_json::Value| has_audio(f) && !has_video(f);
+    let combined = |f: &serde_json::Value| has_video(f) && has_audio(f);
+    let best_audio = best_by(formats, audio_only, audio_rank);
+
+    let by_id = |id: &str| formats.iter().find(|f| f["format_id"].as_str() == Some(id));
+    let pair = |video: Option<&'a serde_json::Value>| match (video, best_audio) {
+        (Some(v), _) if has_audio(v) => vec![v],
+        (Some(v), Some(a)) => vec![v, a],
+        _ => best_by(formats, combined, video_rank).into_iter().collect(),
+    };
+
+    match (mode, quality) {
+        ("video", "best") => pair(best_by(formats, video_only, video_rank)),
+        ("video", id) => pair(by_id(id)),
+        (_, "bestaudio") => best_audio.or_else(|| best_by(formats, combined, video_rank)).into_iter().collect(),
+        (_, id) => by_id(id).into_iter().collect(),
+    }
+}
+
+/// 依影片資訊估計大小 (不含可用空間)；sections 為要下載的片段 / 章節
+pub fn estimate(info: &serde_json::Value, req: &DownloadRequest, sections: Option<&Sections>) -> SizeEstimate {
+    let duration = info["duration"].as_f64();
+    let formats = selected_formats(info, &req.mode, &req.quality);
+    let sizes: Option<Vec<(u64, bool)>> = formats.iter().map(|f| format_size(f, duration)).collect();
+    let Some(sizes) = sizes.filter(|s| !s.is_empty()) else { return SizeEstimate::default() };
+
+    let mut downloaded: u64 = sizes.iter().map(|(s, _)| s).sum();
+    let mut approximate = sizes.iter().any(|(_, approx)| *approx);
+    let mut duration = duration;
+    if let Some(sec) = sections {
+        // 片段依長度比例估計；不知道全片長度時以整部影片為上限
+        approximate = true;
+        if let Some(total) = duration.filter(|d| *d > 0.0) {
+            let selected = sec.selected_duration(total, &parse_chapters(info));
+            downloaded = (downloaded as f64 * (selected / total).clamp(0.0, 1.0)) as u64;
+            duration = Some(selected);
+        }
+    }
+    let (output, mut required) = if req.mode == "video" {
+        // 影音分開下載再合併：合併完成前兩份同時存在
+        (downloaded, downloaded * 2)
+    } else {
+        let mp3 = match duration {
+            Some(d) => {
+                approximate = true;
+                (MP3_BITRATE_KBPS * 1000.0 / 8.0 * d) as u64
+            }
+            None => downloaded,
+        };
+        (mp3, downloaded + mp3)
+    };
+    if req.options.split_chapters {
+        // 分割時每首曲目都是一份新的檔案，原檔最後才刪除
+        required += output;
+    }
+    SizeEstimate {
+        output_bytes: Some(output),
+        required_bytes: Some(required + MARGIN_BYTES),
+        available_bytes: None,
+        approximate,
+    }
+}
+
+/// 目的地所在磁碟的可用空間；資料夾尚未建立時以最近的既有上層資料夾查詢
+pub fn available_space(dir: &Path) -> Option<u64> {
+    let existing = dir.ancestors().find(|p| p.exists())?;
+    fs2::available_space(existing).ok()
+}
+
+impl SizeEstimate {
+    pub fn with_available(mut self, dir: &Path) -> Self {
+        self.available_bytes = available_space(dir);
+        self
+    }
+
+    pub fn check(&self) -> SpaceCheck {
+        let (Some(output), Some(required), Some(available)) = (self.output_bytes, self.required_bytes, self.available_bytes) else {
+            return SpaceCheck::Unknown;
+        };
+        if available >= required {
+            SpaceCheck::Ok
+        } else if self.approximate && available >= output {
+            SpaceCheck::Tight
+        } else {
+            SpaceCheck::Insufficient
+        }
+    }
+}
@@ -0,0 +1,237 @@
+// [2026-10-19 新增] 後端執行環境：集中管理組件位置、使用者資料目錄、外部服務網址與設定
+// 原本各指令各自呼叫 get_app_dir() 並寫死 GitHub 網址，導致無法離線測試。
+// 現在由 AppEnv 統一提供，正式執行時由 run() 以 manage() 注入，測試時可替換成假程式目錄與本機伺服器。
+use crate::cache::MetadataCache;
+use crate::components::{Component, ComponentLocator};
+use crate::settings::{back_up_corrupt, Settings};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+pub const GITHUB_BASE: &str = "https://github.com";
+pub const GITHUB_API_BASE: &str = "https://api.github.com";
+
+#[derive(Debug, Clone)]
+pub struct AppEnv {
+    /// 主程式所在目錄 (可能是唯讀的安裝位置)
+    pub app_dir: PathBuf,
+    /// 使用者資料目錄 (一定可寫入，存放設定與修復下載的組件)
+    pub data_dir: PathBuf,
+    /// 用來搜尋系統組件的 PATH；None 表示不搜尋
+    pub search_path: Option<OsString>,
+    /// GitHub 下載網址的前綴 (releases 檔案)
+    pub github_base: String,
+    /// GitHub API 的前綴 (版本檢查)
+    pub github_api_base: String,
+    pub settings: Settings,
+    /// [2026-10-19 新增] 設定檔無法讀取時的錯誤；此時使用預設值執行，取得設定時回報給前端
+    pub settings_error: Option<String>,
+    /// 影片資訊快取 (快照之間共用同一份)
+    pub metadata_cache: Arc<MetadataCache>,
+}
+
+impl AppEnv {
+    /// 以單一目錄建立環境 (主程式目錄與資料目錄相同、不搜尋 PATH)，主要供測試使用
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let settings = Settings::default();
+        let metadata_cache = Arc::new(MetadataCache::new(Duration::from_secs(settings.metadata_cache.ttl_secs)));
+        Self {
+            app_dir: dir.clone(),
+            data_dir: dir,
+            search_path: None,
+            github_base: GITHUB_BASE.into(),
+            github_api_base: GITHUB_API_BASE.into(),
+            settings,
+            settings_error: None,
+            metadata_cache,
+        }
+    }
+
+    /// 正式環境：主程式目錄 + 使用者資料目錄 + 系統 PATH，並讀入設定檔
+    pub fn from_app_dir(data_dir: impl Into<PathBuf>) -> Self {
+        let mut env = Self::new(get_app_dir());
+        env.data_dir = data_dir.into();
+        env.search_path = std::env::var_os("PATH");
+        match Settings::load(&env.settings_path()) {
+            Ok(settings) => env.settings = settings,
+            Err(e) => env.settings_error = Some(e),
+        }
+        env.rebuild_cache();
+        env
+    }
+
+    /// 依目前設定重建影片資訊快取
+    pub fn rebuild_cache(&mut self) {
+        let cfg = &self.settings.metadata_cache;
+        let mut cache = MetadataCache::new(Duration::from_secs(cfg.ttl_secs));
+        if cfg.persist {
+            cache = cache.with_disk_dir(self.cache_dir().join("info"));
+        }
+        self.metadata_cache = Arc::new(cache);
+    }
+
+    pub fn with_github_base(mut self, base: impl Into<String>) -> Self {
+        self.github_base = base.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_github_api_base(mut self, base: impl Into<String>) -> Self {
+        self.github_api_base = base.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.data_dir.join("settings.json")
+    }
+
+    pub fn cache_dir(&self) -> PathBuf {
+        self.data_dir.join("cache")
+    }
+
+    /// yt-dlp --download-archive 的紀錄檔 (訂閱下載過的影片)
+    pub fn archive_path(&self) -> PathBuf {
+        self.data_dir.join("archive.txt")
+    }
+
+    pub fn subscriptions_path(&self) -> PathBuf {
+        self.data_dir.join("subscriptions.json")
+    }
+
+    /// 下載紀錄 (完成與失敗的任務)
+    pub fn history_path(&self) -> PathBuf {
+        self.data_dir.join("history.json")
+    }
+
+    /// 排程下載佇列
+    pub fn queue_path(&self) -> PathBuf {
+        self.data_dir.join("queue.json")
+    }
+
+    /// 各網站的登入資訊 (cookies / netrc)
+    pub fn auth_dir(&self) -> PathBuf {
+        self.data_dir.join("auth")
+    }
+
+    /// 修復時下載組件的目的地 (使用者可寫入)
+    pub fn user_bin_dir(&self) -> PathBuf {
+        self.data_dir.join("bin")
+    }
+
+    pub fn locator(&self) -> ComponentLocator {
+        ComponentLocator {
+            configured: self.settings.components.clone(),
+            user_dir: self.user_bin_dir(),
+            app_dir: self.app_dir.clone(),
+            search_path: self.search_path.clone(),
+        }
+    }
+
+    /// 組件的實際路徑；找不到時回傳修復後會安裝到的位置
+    pub fn component(&self, component: Component) -> PathBuf {
+        let locator = self.locator();
+        locator.locate(component)
+            .map(|found| found.path)
+            .unwrap_or_else(|| locator.install_path(component))
+    }
+
+    pub fn yt_dlp(&self) -> PathBuf {
+        self.component(Component::YtDlp)
+    }
+
+    pub fn ffmpeg(&self) -> PathBuf {
+        self.component(Component::Ffmpeg)
+    }
+
+    pub fn deno(&self) -> PathBuf {
+        self.component(Component::Deno)
+    }
+
+    // [2026-10-19 修改] 依平台選擇發行檔；沒有官方建置 (或不是 zip) 的平台回傳 None，改由套件管理員安裝。
+    // 壓縮檔內的執行檔名稱與 Component::file_name 相同 (Windows 為 .exe)
+    pub fn yt_dlp_download_url(&self) -> Option<String> {
+        let asset = match (std::env::consts::OS, std::env::consts::ARCH) {
+            ("windows", _) => "yt-dlp.exe",
+            ("macos", _) => "yt-dlp_macos",
+            ("linux", "x86_64") => "yt-dlp_linux",
+            ("linux", "aarch64") => "yt-dlp_linux_aarch64",
+            _ => return None,
+        };
+        Some(format!("{}/yt-dlp/yt-dlp/releases/latest/download/{}", self.github_base, asset))
+    }
+
+    /// BtbN 只提供 Windows 的 zip (Linux 為 tar.xz、沒有 macOS 版)
+    pub fn ffmpeg_download_url(&self) -> Option<String> {
+        let asset = match (std::env::consts::OS, std::env::consts::ARCH) {
+            ("windows", "x86_64") => "ffmpeg-master-latest-win64-gpl.zip",
+            ("windows", "aarch64") => "ffmpeg-master-latest-winarm64-gpl.zip",
+            _ => return None,
+        };
+        Some(format!("{}/BtbN/FFmpeg-Builds/releases/download/latest/{}", self.github_base, asset))
+    }
+
+    pub fn deno_download_url(&self) -> Option<String> {
+        let target = match (std::env::consts::OS, std::env::consts::ARCH) {
+            ("windows", "x86_64") => "x86_64-pc-windows-msvc",
+            ("macos", "x86_64") => "x86_64-apple-darwin",
+            ("macos", "aarch64") => "aarch64-apple-darwin",
+            ("linux", "x86_64") => "x86_64-unknown-linux-gnu",
+            ("linux", "aarch64") => "aarch64-unknown-linux-gnu",
+            _ => return None,
+        };
+        Some(format!("{}/denoland/deno/releases/latest/download/deno-{}.zip", self.github_base, target))
+    }
+
+    pub fn yt_dlp_latest_release_api(&self) -> String {
+        format!("{}/repos/yt-dlp/yt-dlp/releases/latest", self.github_api_base)
+    }
+}
+
+/// 由 tauri 管理的共享狀態；指令取用時複製一份快照，設定變更會同步寫回檔案
+pub struct EnvState(RwLock<AppEnv>);
+
+impl EnvState {
+    pub fn new(env: AppEnv) -> Self {
+        Self(RwLock::new(env))
+    }
+
+    pub fn snapshot(&self) -> AppEnv {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// [2026-10-19 新增] 目前的設定；設定檔無法讀取時回傳錯誤 (而不是看起來正常的預設值)
+    pub fn settings(&self) -> Result<Settings, String> {
+        let env = self.0.read().unwrap_or_else(|e| e.into_inner());
+        match &env.settings_error {
+            Some(e) => Err(e.clone()),
+            None => Ok(env.settings.clone()),
+        }
+    }
+
+    pub fn update_settings(&self, settings: Settings) -> Result<(), String> {
+        settings.validate()?;
+        let mut env = self.0.write().unwrap_or_else(|e| e.into_inner());
+        // [2026-10-19 新增] 覆蓋無法讀取的設定檔前先備份，使用者仍可手動救回
+        if env.settings_error.is_some() {
+            let path = env.settings_path();
+            if path.exists() {
+                back_up_corrupt(&path)?;
+            }
+        }
+        settings.save(&env.settings_path())?;
+        env.settings_error = None;
+        let cache_changed = env.settings.metadata_cache != settings.metadata_cache;
+        env.settings = settings;
+        if cache_changed {
+            env.rebuild_cache();
+        }
+        Ok(())
+    }
+}
+
+pub fn get_app_dir() -> PathBuf {
+    std::env::current_exe()
+        .map(|p| p.parent().unwrap_or(Path::new("")).to_path_buf())
+        .unwrap_or_else(|_| PathBuf::from("."))
+}
@@ -0,0 +1,54 @@
+// [2026-10-19 新增] 事件輸出抽象層
+// 核心邏輯只透過 EventSink 把日誌 / 進度送出去，正式執行時由 tauri::Window 實作，
+// 測試時可改用記錄型的實作，不需要真的開啟視窗。
+use serde::{Deserialize, Serialize};
+
+pub const EVT_LOG: &str = "backend-log";
+pub const EVT_PROGRESS: &str = "download-progress";
+pub const EVT_CORE_STATUS: &str = "core-status-update";
+pub const EVT_LIVE_PROGRESS: &str = "live-progress";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadPayload {
+    pub progress: f64,
+    pub speed: String,
+    pub eta: String,
+}
+
+/// [2026-10-19 新增] 直播錄製沒有總長度，改回報已錄製時間與大小
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LivePayload {
+    pub elapsed_secs: u64,
+    pub bytes: u64,
+    pub speed: String,
+}
+
+pub trait EventSink: Send + Sync {
+    fn emit_json(&self, event: &str, payload: serde_json::Value);
+}
+
+pub fn emit<T: Serialize>(sink: &dyn EventSink, event: &str, payload: T) {
+    if let Ok(value) = serde_json::to_value(payload) {
+        sink.emit_json(event, value);
+    }
+}
+
+pub fn log(sink: &dyn EventSink, msg: impl Into<String>) {
+    emit(sink, EVT_LOG, msg.into());
+}
+
+pub fn progress(sink: &dyn EventSink, payload: DownloadPayload) {
+    emit(sink, EVT_PROGRESS, payload);
+}
+
+pub fn live_progress(sink: &dyn EventSink, payload: LivePayload) {
+    emit(sink, EVT_LIVE_PROGRESS, payload);
+}
+
+pub fn core_status(sink: &dyn EventSink, is_ok: bool) {
+    emit(sink, EVT_CORE_STATUS, is_ok);
+}
+
+pub fn get_msg(lang: &str, zh: &str, en: &str) -> String {
+    if lang == "en" { en.to_string() } else { zh.to_string() }
+}
@@ -0,0 +1,181 @@
+// [2026-10-19 新增] 匯出下載紀錄或目前的佇列：CSV、JSON，或引用輸出檔的 M3U / M3U8 播放清單 (#EXTINF 長度與標題)。
+// 可依日期範圍、狀態與資料夾篩選。播放清單中位於清單所在資料夾之下的檔案使用相對路徑，方便整個資料夾一起分享。
+use crate::env::AppEnv;
+use crate::history::{self, HistoryEntry};
+use crate::queue::{DownloadQueue, QueuedJob};
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportSource {
+    History,
+    Queue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    /// 一律以 UTF-8 寫入，副檔名用 .m3u 或 .m3u8 皆可
+    #[serde(alias = "m3u8")]
+    M3u,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportFilter {
+    /// 開始時間 (Unix 秒，含)
+    pub from: Option<i64>,
+    /// 結束時間 (Unix 秒，含)
+    pub to: Option<i64>,
+    /// 只匯出這些狀態 (completed、failed、scheduled...)；空白表示全部
+    pub statuses: Vec<String>,
+    /// 只匯出下載到此資料夾 (含子資料夾) 的項目
+    pub folder: Option<String>,
+}
+
+/// 紀錄與佇列共用的匯出欄位
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportRow {
+    /// 紀錄為結束時間、佇列為加入時間 (Unix 秒)
+    pub timestamp: i64,
+    pub status: String,
+    pub title: String,
+    pub url: String,
+    pub folder: String,
+    pub files: Vec<PathBuf>,
+    pub duration: Option<f64>,
+    pub error: Option<String>,
+}
+
+impl ExportFilter {
+    pub fn matches(&self, row: &ExportRow) -> bool {
+        if self.from.is_some_and(|t| row.timestamp < t) || self.to.is_some_and(|t| row.timestamp > t) {
+            return false;
+        }
+        if !self.statuses.is_empty() && !self.statuses.iter().any(|s| s.eq_ignore_ascii_case(&row.status)) {
+            return false;
+        }
+        match self.folder.as_deref().map(str::trim).filter(|f| !f.is_empty()) {
+            Some(folder) => Path::new(&row.folder).starts_with(folder),
+            None => true,
+        }
+    }
+}
+
+fn status_name<T: Serialize>(status: T) -> String {
+    serde_json::to_value(status).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default()
+}
+
+pub fn history_rows(entries: &[HistoryEntry]) -> Vec<ExportRow> {
+    entries.iter().map(|e| ExportRow {
+        timestamp: e.finished_at,
+        status: status_name(e.status),
+        title: e.title.clone(),
+        url: e.url.clone(),
+        folder: e.folder.clone(),
+        files: e.files.clone(),
+        duration: e.duration,
+        error: e.error.clone(),
+    }).collect()
+}
+
+pub fn queue_rows(jobs: &[QueuedJob]) -> Vec<ExportRow> {
+    jobs.iter().map(|j| {
+        let result = j.result.clone().unwrap_or_default();
+        ExportRow {
+            timestamp: j.added_at,
+            status: status_name(j.state),
+            title: if result.title.is_empty() { j.request.url.clone() } else { result.title },
+            url: j.request.url.clone(),
+            folder: j.request.path.clone(),
+            files: result.files,
+            duration: result.duration,
+            error: j.last_error.clone(),
+        }
+    }).collect()
+}
+
+/// 避免試算表把 = + - @ 開頭的儲存格當成公式執行
+// [2026-10-19 修正] 開頭的 tab 與 CR 也會被部分試算表略過後再判斷公式，一併加上前綴
+fn csv_cell(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) { format!("'{}", value) } else { value.to_string() }
+}
+
+fn local_time(ts: i64) -> String {
+    Local.timestamp_opt(ts, 0).single().map(|t| t.to_rfc3339()).unwrap_or_default()
+}
+
+fn write_csv(rows: &[ExportRow], dest: &Path) -> Result<usize, String> {
+    let mut writer = csv::Writer::from_path(dest).map_err(|e| e.to_string())?;
+    writer.write_record(["date", "status", "title", "url", "folder", "files", "duration", "error"]).map_err(|e| e.to_string())?;
+    for row in rows {
+        let files: Vec<String> = row.files.iter().map(|f| f.to_string_lossy().to_string()).collect();
+        writer.write_record([
+            local_time(row.timestamp),
+            row.status.clone(),
+            csv_cell(&row.title),
+            csv_cell(&row.url),
+            csv_cell(&row.folder),
+            csv_cell(&files.join(";")),
+            row.duration.map(|d| format!("{:.0}", d)).unwrap_or_default(),
+            csv_cell(row.error.as_deref().unwrap_or("")),
+        ]).map_err(|e| e.to_string())?;
+    }
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok(rows.len())
+}
+
+/// 產生 M3U 內容；只列出仍然存在的檔案。多檔輸出 (章節曲目) 以檔名作為標題
+pub fn m3u(rows: &[ExportRow], playlist_dir: &Path) -> (String, usize) {
+    let mut text = String::from("#EXTM3U\n");
+    let mut count = 0;
+    for row in rows {
+        let files: Vec<&PathBuf> = row.files.iter().filter(|f| f.exists()).collect();
+        for file in &files {
+            let (duration, title) = if row.files.len() == 1 {
+                (row.duration, row.title.clone())
+            } else {
+                (None, file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default())
+            };
+            let duration = duration.map(|d| d.round() as i64).unwrap_or(-1);
+            let title = title.replace(['\r', '\n'], " ");
+            let path = file.strip_prefix(playlist_dir).unwrap_or(file);
+            text.push_str(&format!("#EXTINF:{},{}\n{}\n", duration, title, path.to_string_lossy()));
+            count += 1;
+        }
+    }
+    (text, count)
+}
+
+/// 依格式寫入檔案，回傳匯出的項目數 (M3U 為檔案數)
+pub fn write(rows: &[ExportRow], format: ExportFormat, dest: &Path) -> Result<usize, String> {
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    match format {
+        ExportFormat::Csv => write_csv(rows, dest),
+        ExportFormat::Json => {
+            let txt = serde_json::to_string_pretty(rows).map_err(|e| e.to_string())?;
+            std::fs::write(dest, txt).map_err(|e| e.to_string())?;
+            Ok(rows.len())
+        }
+        ExportFormat::M3u => {
+            let (text, count) = m3u(rows, dest.parent().unwrap_or(Path::new("")));
+            std::fs::write(dest, text).map_err(|e| e.to_string())?;
+            Ok(count)
+        }
+    }
+}
+
+pub fn export(env: &AppEnv, queue: &DownloadQueue, source: ExportSource, format: ExportFormat, filter: &ExportFilter, dest: &Path) -> Result<usize, String> {
+    let rows = match source {
+        ExportSource::History => history_rows(&history::load(&env.history_path())?),
+        ExportSource::Queue => queue_rows(&queue.list()),
+    };
+    let rows: Vec<ExportRow> = rows.into_iter().filter(|r| filter.matches(r)).collect();
+    write(&rows, format, dest)
+}
@@ -0,0 +1,166 @@
+// [2026-10-19 新增] RSS / Atom 訂閱來源：podcast 與一般網站的 RSS、YouTube 頻道的 Atom feed (videos.xml)。
+// 以 GUID 判斷新項目，有 enclosure (podcast 音檔) 時下載 enclosure，否則下載項目連結，一樣交給 yt-dlp 與下載佇列處理。
+use crate::env::AppEnv;
+use crate::network;
+use crate::sections::parse_timestamp;
+use crate::subscriptions::PlaylistEntry;
+use chrono::DateTime;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::time::Duration;
+
+#[derive(Default)]
+struct ItemBuilder {
+    guid: String,
+    title: String,
+    link: String,
+    enclosure: Option<String>,
+    date: String,
+    duration: String,
+    video_id: String,
+}
+
+fn qualified_name(e: &BytesStart) -> String {
+    String::from_utf8_lossy(e.name().as_ref()).to_string()
+}
+
+fn attr(e: &BytesStart, key: &str) -> Option<String> {
+    e.attributes().flatten()
+        .find(|a| a.key.as_ref() == key.as_bytes())
+        .and_then(|a| a.unescape_value().ok().map(|v| v.trim().to_string()))
+}
+
+/// RSS 為 RFC 2822、Atom 為 RFC 3339，統一轉成 YYYYMMDD
+fn parse_date(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    DateTime::parse_from_rfc2822(raw)
+        .or_else(|_| DateTime::parse_from_rfc3339(raw))
+        .ok()
+        .map(|d| d.format("%Y%m%d").to_string())
+}
+
+impl ItemBuilder {
+    /// 屬性中帶網址的元素：Atom <link href>、RSS <enclosure url>
+    fn start(&mut self, name: &str, e: &BytesStart) {
+        match name {
+            "link" => {
+                let Some(href) = attr(e, "href") else { return };
+                match attr(e, "rel").as_deref() {
+                    Some("enclosure") => {
+                        self.enclosure.get_or_insert(href);
+                    }
+                    None | Some("alternate") if self.link.is_empty() => self.link = href,
+                    _ => {}
+                }
+            }
+            "enclosure" => {
+                if let Some(url) = attr(e, "url") {
+                    self.enclosure.get_or_insert(url);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, name: &str, text: &str) {
+        let field = match name {
+            "guid" | "id" => &mut self.guid,
+            "title" => &mut self.title,
+            "link" => &mut self.link,
+            "pubDate" | "published" | "dc:date" => &mut self.date,
+            "updated" if self.date.is_empty() => &mut self.date,
+            "itunes:duration" => &mut self.duration,
+            "yt:videoId" => &mut self.video_id,
+            _ => return,
+        };
+        field.push_str(text);
+    }
+
+    fn build(self) -> Option<PlaylistEntry> {
+        let link = Some(self.link.trim().to_string()).filter(|l| !l.is_empty());
+        let url = self.enclosure.filter(|u| !u.is_empty()).or(link.clone())?;
+        let guid = Some(self.guid.trim().to_string()).filter(|g| !g.is_empty()).or(link).unwrap_or_else(|| url.clone());
+        let video_id = self.video_id.trim();
+        // YouTube 的項目使用與 download archive 相同的鍵，手動下載過的影片不會重複下載
+        let archive_key = if video_id.is_empty() { guid } else { format!("youtube {}", video_id) };
+        let title = self.title.trim();
+        Some(PlaylistEntry {
+            archive_key,
+            title: if title.is_empty() { url.clone() } else { title.to_string() },
+            url,
+            duration: parse_timestamp(&self.duration).ok(),
+            upload_date: parse_date(&self.date),
+            live_status: None,
+        })
+    }
+}
+
+/// 解析 RSS 2.0 / RSS 1.0 (RDF) / Atom，依原本的順序回傳項目
+pub fn parse_feed(xml: &str) -> Result<Vec<PlaylistEntry>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut items = Vec::new();
+    let mut is_feed = false;
+    let mut current: Option<ItemBuilder> = None;
+    let mut field: Option<String> = None;
+
+    loop {
+        match reader.read_event().map_err(|e| format!("Invalid feed: {}", e))? {
+            Event::Start(e) => {
+                let name = qualified_name(&e);
+                match name.as_str() {
+                    "rss" | "feed" | "rdf:RDF" => is_feed = true,
+                    "item" | "entry" => current = Some(ItemBuilder::default()),
+                    _ => {
+                        if let Some(item) = current.as_mut() {
+                            item.start(&name, &e);
+                        }
+                    }
+                }
+                field = Some(name);
+            }
+            Event::Empty(e) => {
+                if let Some(item) = current.as_mut() {
+                    item.start(&qualified_name(&e), &e);
+                }
+            }
+            Event::Text(t) => {
+                if let (Some(item), Some(name)) = (current.as_mut(), field.as_deref()) {
+                    let text = t.unescape().map_err(|e| format!("Invalid feed: {}", e))?;
+                    item.text(name, &text);
+                }
+            }
+            Event::CData(t) => {
+                if let (Some(item), Some(name)) = (current.as_mut(), field.as_deref()) {
+                    item.text(name, &String::from_utf8_lossy(&t));
+                }
+            }
+            Event::End(e) => {
+                if matches!(e.name().as_ref(), b"item" | b"entry") {
+                    items.extend(current.take().and_then(ItemBuilder::build));
+                }
+                field = None;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !is_feed {
+        return Err("Not an RSS or Atom feed".into());
+    }
+    Ok(items)
+}
+
+/// 下載並解析 feed (走網路設定中的代理)
+pub async fn fetch(env: &AppEnv, url: &str) -> Result<Vec<PlaylistEntry>, String> {
+    let client = network::client_builder(&env.settings.network, url)?
+        .timeout(Duration::from_secs(env.settings.timeouts.metadata_secs))
+        .build()
+        .map_err(|e: reqwest::Error| e.to_string())?;
+    let resp = client.get(url).send().await.map_err(|e: reqwest::Error| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("Feed HTTP {}", resp.status()));
+    }
+    let text = resp.text().await.map_err(|e: reqwest::Error| e.to_string())?;
+    parse_feed(&text)
+}
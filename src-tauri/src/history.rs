@@ -0,0 +1,164 @@
+// [2026-10-19 新增] 下載紀錄：每個結束的下載 (手動、佇列、訂閱) 都寫入使用者資料目錄的 history.json，
+// 供前端顯示與匯出 (export.rs)。只保留最近的 MAX_ENTRIES 筆。
+use crate::env::AppEnv;
+use crate::hooks::HookOutcome;
+use crate::settings::{back_up_corrupt, write_json_atomic};
+use crate::ytdlp::{DownloadRequest, DownloadResult};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+const MAX_ENTRIES: usize = 10_000;
+
+// [2026-10-19 新增] 下載結束、還原中繼資料與清除紀錄可能同時發生，讀取—修改—寫回都在這個鎖內完成
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryStatus {
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// [2026-10-19 新增] 穩定的識別碼 (前端以此指定紀錄，舊紀錄在讀取時補上)
+    #[serde(default)]
+    pub id: String,
+    pub url: String,
+    /// 失敗時可能沒有標題，以網址代替
+    pub title: String,
+    pub mode: String,
+    pub quality: String,
+    pub folder: String,
+    pub status: HistoryStatus,
+    pub files: Vec<PathBuf>,
+    pub duration: Option<f64>,
+    pub error: Option<String>,
+    /// 結束時間 (Unix 秒)
+    pub finished_at: i64,
+    /// [2026-10-19 新增] 整理規則試跑用的來源資訊 (舊紀錄沒有)
+    #[serde(default)]
+    pub uploader: Option<String>,
+    #[serde(default)]
+    pub playlist: Option<String>,
+    #[serde(default)]
+    pub upload_date: Option<String>,
+    /// [2026-10-19 新增] 典藏用的 .info.json
+    #[serde(default)]
+    pub info_file: Option<PathBuf>,
+    /// [2026-10-19 新增] 下載結束後執行的掛鉤結果
+    #[serde(default)]
+    pub hooks: Vec<HookOutcome>,
+}
+
+fn lock() -> MutexGuard<'static, ()> {
+    HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn new_id() -> String {
+    format!("{:x}-{}", Local::now().timestamp_millis(), ID_COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// 讀取紀錄並替沒有 id 的舊紀錄補上；回傳是否有補上
+// [2026-10-19 修正] 檔案不存在才視為空紀錄；無法解析時回傳錯誤，不再當成空的
+fn read(path: &Path) -> Result<(Vec<HistoryEntry>, bool), String> {
+    let mut entries: Vec<HistoryEntry> = match std::fs::read_to_string(path) {
+        Ok(txt) => serde_json::from_str(&txt).map_err(|e| format!("Cannot read download history: {}", e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(format!("Cannot read download history: {}", e)),
+    };
+    let mut migrated = false;
+    for entry in entries.iter_mut().filter(|e| e.id.is_empty()) {
+        entry.id = new_id();
+        migrated = true;
+    }
+    Ok((entries, migrated))
+}
+
+// [2026-10-19 修改] 補上的 id 立即寫回，之後每次讀取都得到相同的 id
+pub fn load(path: &Path) -> Result<Vec<HistoryEntry>, String> {
+    let _guard = lock();
+    let (entries, migrated) = read(path)?;
+    if migrated {
+        let _ = write_json_atomic(path, &entries);
+    }
+    Ok(entries)
+}
+
+/// [2026-10-19 新增] 在鎖內讀取、修改並整份寫回；f 回傳錯誤時不寫入。
+/// 既有的檔案無法解析時先改名備份，再從空紀錄開始
+pub fn update<T>(path: &Path, f: impl FnOnce(&mut Vec<HistoryEntry>) -> Result<T, String>) -> Result<T, String> {
+    let _guard = lock();
+    let mut entries = match read(path) {
+        Ok((entries, _)) => entries,
+        Err(e) if path.is_file() => {
+            back_up_corrupt(path).map_err(|backup_err| format!("{} ({})", e, backup_err))?;
+            Vec::new()
+        }
+        Err(e) => return Err(e),
+    };
+    let value = f(&mut entries)?;
+    write_json_atomic(path, &entries)?;
+    Ok(value)
+}
+
+/// 加入一筆紀錄
+pub fn append(path: &Path, entry: HistoryEntry) -> Result<(), String> {
+    update(path, |entries| {
+        entries.push(entry);
+        if entries.len() > MAX_ENTRIES {
+            let excess = entries.len() - MAX_ENTRIES;
+            entries.drain(..excess);
+        }
+        Ok(())
+    })
+}
+
+pub fn clear(path: &Path) -> Result<(), String> {
+    let _guard = lock();
+    write_json_atomic(path, &Vec::<HistoryEntry>::new())
+}
+
+/// [2026-10-19 新增] 掛鉤在下載紀錄寫入後才執行，完成時把結果寫回該筆紀錄
+pub fn set_hooks(path: &Path, id: &str, hooks: Vec<HookOutcome>) -> Result<(), String> {
+    update(path, |entries| {
+        let entry = entries.iter_mut().find(|e| e.id == id).ok_or_else(|| format!("History entry not found: {}", id))?;
+        entry.hooks = hooks;
+        Ok(())
+    })
+}
+
+/// 由下載結果建立紀錄並回傳其 id；寫入失敗不影響下載本身 (回傳 None)
+// [2026-10-19 修改] 掛鉤結果改由 set_hooks 在掛鉤執行完後補上
+pub fn record(env: &AppEnv, req: &DownloadRequest, result: &Result<DownloadResult, String>) -> Option<String> {
+    let done = result.as_ref().ok().cloned().unwrap_or_default();
+    let (status, error) = match result {
+        Ok(_) => (HistoryStatus::Completed, None),
+        Err(e) => (HistoryStatus::Failed, Some(e.clone())),
+    };
+    let title = done.title;
+    let id = new_id();
+    let entry = HistoryEntry {
+        id: id.clone(),
+        url: req.url.clone(),
+        title: if title.is_empty() { req.url.clone() } else { title },
+        mode: req.mode.clone(),
+        quality: req.quality.clone(),
+        folder: req.path.clone(),
+        status,
+        files: done.files,
+        duration: done.duration,
+        error,
+        finished_at: Local::now().timestamp(),
+        uploader: done.uploader,
+        playlist: done.playlist,
+        upload_date: done.upload_date,
+        info_file: done.info_file,
+        hooks: Vec::new(),
+    };
+    append(&env.history_path(), entry).ok().map(|_| id)
+}
@@ -0,0 +1,322 @@
+// [2026-10-19 新增] 下載結束後的掛鉤：依下載設定檔執行外部指令，或以 POST 把 JSON 送到 Webhook
+// (通知聊天室、通知媒體伺服器重新掃描、執行轉檔腳本等)。指令不經過 shell，參數中的 {path}、{title} 等欄位逐一代入，
+// 標題含有引號或分號也不會被當成指令。每個掛鉤有逾時與重試次數，輸出即時寫入記錄，結果 (HookOutcome) 存入下載紀錄；掛鉤失敗不影響下載結果。
+// [2026-10-19 修改] 掛鉤在釋放下載鎖之後才執行 (PendingHooks)，不占用下載的時間。
+use crate::env::AppEnv;
+use crate::events::{self, get_msg, EventSink};
+use crate::history;
+use crate::network;
+use crate::process::{self, StreamLine};
+use crate::ytdlp::{DownloadRequest, DownloadResult};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// 指令參數可用的欄位；參數剛好是 {files} 時展開成每個檔案一個參數
+const PLACEHOLDERS: [&str; 7] = ["path", "files", "folder", "title", "url", "id", "status"];
+
+/// 每個掛鉤的 stdout 與 stderr 各自保存在紀錄中的最後幾行
+const MAX_OUTPUT_LINES: usize = 20;
+
+const MAX_TIMEOUT_SECS: u64 = 3600;
+const MAX_RETRIES: u32 = 10;
+
+/// 重試前的等待時間 (依嘗試次數遞增)
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookTrigger {
+    #[default]
+    Completed,
+    Failed,
+    Always,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookAction {
+    /// 直接執行程式 (不經過 shell)
+    Command { program: String, args: Vec<String> },
+    /// 以 POST 送出 JSON (HookPayload)
+    Webhook { url: String },
+}
+
+impl Default for HookAction {
+    fn default() -> Self {
+        Self::Command { program: String::new(), args: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hook {
+    /// 記錄中顯示的名稱；空白時使用程式名稱或網址
+    pub name: String,
+    pub action: HookAction,
+    pub when: HookTrigger,
+    pub timeout_secs: u64,
+    /// 失敗後重試的次數
+    pub retries: u32,
+}
+
+impl Default for Hook {
+    fn default() -> Self {
+        Self { name: String::new(), action: HookAction::default(), when: HookTrigger::default(), timeout_secs: 30, retries: 0 }
+    }
+}
+
+/// [2026-10-19 新增] 掛鉤的執行結果 (存入下載紀錄)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HookOutcome {
+    pub name: String,
+    pub success: bool,
+    /// 含重試的執行次數
+    pub attempts: u32,
+    /// 最後一次執行的輸出 (stdout 與 stderr 各自最後 MAX_OUTPUT_LINES 行)
+    pub output: Vec<String>,
+    /// 最後一次失敗的原因
+    pub error: Option<String>,
+}
+
+/// 送給 Webhook 的內容
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HookPayload {
+    /// download.completed / download.failed
+    pub event: String,
+    pub status: String,
+    pub url: String,
+    pub id: Option<String>,
+    pub title: String,
+    /// 第一個輸出檔 (失敗時為空白)
+    pub path: String,
+    pub files: Vec<String>,
+    pub folder: String,
+    pub duration: Option<f64>,
+    pub error: Option<String>,
+    pub profile: Option<String>,
+}
+
+impl HookPayload {
+    pub fn new(req: &DownloadRequest, result: &Result<DownloadResult, String>) -> Self {
+        let done = result.as_ref().ok().cloned().unwrap_or_default();
+        let status = if result.is_ok() { "completed" } else { "failed" };
+        let files: Vec<String> = done.files.iter().map(|f| f.to_string_lossy().to_string()).collect();
+        Self {
+            event: format!("download.{}", status),
+            status: status.into(),
+            url: req.url.clone(),
+            id: done.video_id,
+            title: if done.title.is_empty() { req.url.clone() } else { done.title },
+            path: files.first().cloned().unwrap_or_default(),
+            files,
+            folder: req.path.clone(),
+            duration: done.duration,
+            error: result.as_ref().err().cloned(),
+            profile: req.profile.clone(),
+        }
+    }
+
+    fn field(&self, name: &str) -> String {
+        match name {
+            "path" => self.path.clone(),
+            "files" => self.files.join(" "),
+            "folder" => self.folder.clone(),
+            "title" => self.title.clone(),
+            "url" => self.url.clone(),
+            "id" => self.id.clone().unwrap_or_default(),
+            "status" => self.status.clone(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// 依欄位代入指令參數 (每個參數獨立，值中的空白與引號原樣保留)
+pub fn render_args(args: &[String], payload: &HookPayload) -> Vec<String> {
+    let mut out = Vec::new();
+    for arg in args {
+        if arg == "{files}" {
+            out.extend(payload.files.iter().cloned());
+            continue;
+        }
+        let mut rendered = arg.clone();
+        for name in PLACEHOLDERS {
+            rendered = rendered.replace(&format!("{{{}}}", name), &payload.field(name));
+        }
+        out.push(rendered);
+    }
+    out
+}
+
+impl Hook {
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.action {
+            HookAction::Command { program, args } => {
+                if program.trim().is_empty() {
+                    return Err("Hook command cannot be empty".into());
+                }
+                for arg in args {
+                    let mut rest = arg.as_str();
+                    while let Some(start) = rest.find('{') {
+                        let Some(len) = rest[start..].find('}') else { break };
+                        let name = &rest[start + 1..start + len];
+                        if !PLACEHOLDERS.contains(&name) {
+                            return Err(format!("Unknown placeholder {{{}}} (available: {})", name, PLACEHOLDERS.join(", ")));
+                        }
+                        rest = &rest[start + len + 1..];
+                    }
+                }
+            }
+            HookAction::Webhook { url } => {
+                let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid webhook URL: {}", e))?;
+                if !matches!(parsed.scheme(), "http" | "https") {
+                    return Err(format!("Webhook URL must use http or https: {}", url));
+                }
+            }
+        }
+        if self.timeout_secs == 0 || self.timeout_secs > MAX_TIMEOUT_SECS {
+            return Err(format!("Hook timeout must be between 1 and {} seconds", MAX_TIMEOUT_SECS));
+        }
+        if self.retries > MAX_RETRIES {
+            return Err(format!("Hook retries cannot exceed {}", MAX_RETRIES));
+        }
+        Ok(())
+    }
+
+    pub fn label(&self) -> String {
+        if !self.name.trim().is_empty() {
+            return self.name.trim().to_string();
+        }
+        match &self.action {
+            HookAction::Command { program, .. } => program.clone(),
+            HookAction::Webhook { url } => url.clone(),
+        }
+    }
+
+    fn applies(&self, success: bool) -> bool {
+        match self.when {
+            HookTrigger::Completed => success,
+            HookTrigger::Failed => !success,
+            HookTrigger::Always => true,
+        }
+    }
+}
+
+/// 執行一次；輸出逐行即時寫入記錄，回傳 stdout 與 stderr 各自的最後幾行
+// [2026-10-19 修正] 改為串流讀取：長時間執行的掛鉤不必等結束才看到輸出，逾時時結束整個程序樹
+async fn run_once(env: &AppEnv, sink: &dyn EventSink, label: &str, hook: &Hook, payload: &HookPayload) -> Result<Vec<String>, (String, Vec<String>)> {
+    let timeout = Duration::from_secs(hook.timeout_secs);
+    match &hook.action {
+        HookAction::Command { program, args } => {
+            let mut cmd = process::command(program);
+            cmd.args(render_args(args, payload));
+            let (mut out_tail, mut err_tail) = (VecDeque::new(), VecDeque::new());
+            let outcome = process::run_streaming_timeout(cmd, timeout, |line| {
+                let (tail, line) = match line {
+                    StreamLine::Stdout(line) => (&mut out_tail, line),
+                    StreamLine::Stderr(line) => (&mut err_tail, line),
+                };
+                if line.trim().is_empty() {
+                    return;
+                }
+                events::log(sink, format!("[{}] {}", label, line));
+                if tail.len() == MAX_OUTPUT_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(line);
+            }).await.map_err(|e| (e, Vec::new()))?;
+            let lines: Vec<String> = out_tail.into_iter().chain(err_tail).collect();
+            if outcome.stopped {
+                Err((format!("Timed out after {}s", timeout.as_secs()), lines))
+            } else if outcome.status.success() {
+                Ok(lines)
+            } else {
+                Err((format!("Exited with {}", outcome.status), lines))
+            }
+        }
+        HookAction::Webhook { url } => {
+            let client = network::client(&env.settings.network, url).map_err(|e| (e, Vec::new()))?;
+            let resp = client.post(url).json(payload).timeout(timeout).send().await.map_err(|e| (e.to_string(), Vec::new()))?;
+            let status = resp.status();
+            if status.is_success() {
+                Ok(Vec::new())
+            } else {
+                Err((format!("HTTP {}", status), Vec::new()))
+            }
+        }
+    }
+}
+
+/// 執行單一掛鉤 (含重試)，輸出寫入記錄並回傳執行結果
+pub async fn run(env: &AppEnv, sink: &dyn EventSink, lang: &str, hook: &Hook, payload: &HookPayload) -> HookOutcome {
+    let label = hook.label();
+    let mut outcome = HookOutcome { name: label.clone(), ..Default::default() };
+    for attempt in 0..=hook.retries {
+        if attempt > 0 {
+            tokio::time::sleep(RETRY_DELAY * attempt).await;
+            events::log(sink, get_msg(
+                lang,
+                &format!("🔁 [{}] 重試掛鉤 ({}/{})", label, attempt, hook.retries),
+                &format!("🔁 [{}] Retrying hook ({}/{})", label, attempt, hook.retries),
+            ));
+        }
+        outcome.attempts = attempt + 1;
+        let (result, lines) = match run_once(env, sink, &label, hook, payload).await {
+            Ok(lines) => (Ok(()), lines),
+            Err((e, lines)) => (Err(e), lines),
+        };
+        outcome.output = lines;
+        match result {
+            Ok(()) => {
+                events::log(sink, get_msg(lang, &format!("✅ [{}] 掛鉤已完成", label), &format!("✅ [{}] Hook finished", label)));
+                outcome.success = true;
+                outcome.error = None;
+                return outcome;
+            }
+            Err(e) => {
+                events::log(sink, get_msg(lang, &format!("⚠️ [{}] 掛鉤失敗：{}", label, e), &format!("⚠️ [{}] Hook failed: {}", label, e)));
+                outcome.error = Some(e);
+            }
+        }
+    }
+    outcome
+}
+
+/// [2026-10-19 新增] 下載結束後待執行的掛鉤 (已依結果篩選)。
+/// 呼叫端在釋放下載鎖之後才執行，掛鉤再久也不會擋住下一個下載；結果寫回對應的下載紀錄
+#[derive(Debug, Default)]
+pub struct PendingHooks {
+    hooks: Vec<Hook>,
+    payload: HookPayload,
+    lang: String,
+    history_id: Option<String>,
+}
+
+impl PendingHooks {
+    /// 依請求的下載設定檔挑出符合結果的掛鉤；history_id 為這次下載的紀錄
+    pub fn new(env: &AppEnv, req: &DownloadRequest, result: &Result<DownloadResult, String>, history_id: Option<String>) -> Self {
+        // 不存在的設定檔在下載開始前就已回報錯誤
+        let profile = env.settings.profile(req.profile.as_deref()).unwrap_or_default();
+        let hooks: Vec<Hook> = profile.hooks.iter().filter(|h| h.applies(result.is_ok())).cloned().collect();
+        if hooks.is_empty() {
+            return Self::default();
+        }
+        Self { hooks, payload: HookPayload::new(req, result), lang: req.lang.clone(), history_id }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// 依序執行，回傳各掛鉤的結果
+    pub async fn run(self, env: &AppEnv, sink: &dyn EventSink) -> Vec<HookOutcome> {
+        let mut outcomes = Vec::new();
+        for hook in &self.hooks {
+            outcomes.push(run(env, sink, &self.lang, hook, &self.payload).await);
+        }
+        if let (Some(id), false) = (&self.history_id, outcomes.is_empty()) {
+            let _ = history::set_hooks(&env.history_path(), id, outcomes.clone());
+        }
+        outcomes
+    }
+}
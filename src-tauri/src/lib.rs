@@ -1,121 +1,74 @@
-use serde::{Deserialize, Serialize};
-use std::io::{BufRead, BufReader, Write, Read}; // [修正] 加入 Read 用於讀取錯誤訊息
-use std::process::{Command, Stdio};
-use tauri::Emitter; 
-use std::path::{Path, PathBuf};
-use regex::Regex;
-use futures_util::StreamExt; // 用於串流下載
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tauri::Emitter;
 use tauri::Manager; // 用於視窗管理
-use tauri::Listener; // [新增] 修正 error[E0599]，讓 app 支援 listen 方法
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
+#[cfg(target_os = "windows")]
+use std::process::Command;
+
+// [2026-10-19 重構] 核心邏輯拆分為獨立模組，指令層只負責鎖與狀態注入
+pub mod archival;
+pub mod archive;
+pub mod auth;
+pub mod bandwidth;
+pub mod batch;
+pub mod cache;
+pub mod clipboard;
+pub mod components;
+pub mod diskspace;
+pub mod env;
+pub mod events;
+pub mod export;
+pub mod feeds;
+pub mod history;
+pub mod hooks;
+pub mod live;
+pub mod medialib;
+pub mod network;
+pub mod organizer;
+pub mod process;
+pub mod queue;
+pub mod savepath;
+pub mod sections;
+pub mod settings;
+pub mod sponsorblock;
+pub mod subscriptions;
+pub mod tracks;
+pub mod urls;
+pub mod ytdlp;
+
+pub use auth::{CredentialStore, CredentialSummary};
+pub use components::{Component, ComponentStatus};
+pub use env::{AppEnv, EnvState};
+pub use events::{get_msg, DownloadPayload, EventSink, LivePayload};
+pub use queue::{DownloadQueue, JobSchedule, QueuedJob};
+pub use settings::{DownloadProfile, Settings};
+pub use subscriptions::{Subscription, SubscriptionConfig, SubscriptionStore};
+pub use urls::{NormalizedUrl, UrlError};
+pub use ytdlp::{Chapter, DownloadOptions, DownloadRequest, DownloadResult, VideoFormat, VideoMetadata};
+use hooks::PendingHooks;
 
 // [2026-01-17 新增] 全域下載鎖，確保同時間只有一個下載任務執行，防止誤觸導致的邏輯打架
 lazy_static::lazy_static! {
     static ref DOWNLOAD_LOCK: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
+    // [2026-10-19 新增] 目前下載任務的停止訊號 (下載期間 DOWNLOAD_LOCK 被占用，另外保存)
+    static ref CURRENT_STOP: std::sync::Mutex<Option<process::StopSignal>> = std::sync::Mutex::new(None);
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct VideoFormat {
-    pub id: String,
-    pub ext: String,
-    pub resolution: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct VideoMetadata {
-    pub title: String,
-    pub thumbnail: String,
-    pub formats: Vec<VideoFormat>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DownloadPayload {
-    pub progress: f64,
-    pub speed: String,
-    pub eta: String,
-}
-
-fn get_msg(lang: &str, zh: &str, en: &str) -> String {
-    if lang == "en" { en.to_string() } else { zh.to_string() }
-}
-
-fn get_app_dir() -> PathBuf {
-    std::env::current_exe()
-        .map(|p| p.parent().unwrap_or(Path::new("")).to_path_buf())
-        .unwrap_or_else(|_| PathBuf::from("."))
-}
-
-fn get_unique_path(base_path: &Path, title: &str, quality: &str, ext: &str) -> PathBuf {
-    let safe_title = title.replace(['\\', '/', ':', '*', '?', '"', '<', '>', '|'], "_");
-    let mut counter = 0;
-    loop {
-        let filename = if counter == 0 {
-            format!("{}_{}.{}", safe_title, quality, ext)
-        } else {
-            format!("{}_{}_{}.{}", safe_title, quality, counter, ext)
-        };
-        let full_path = base_path.join(filename);
-        if !full_path.exists() {
-            return full_path;
-        }
-        counter += 1;
+// [2026-10-19 新增] 讓核心模組透過 Window 廣播事件 (使用 app_handle 確保所有視窗收到)
+impl<R: tauri::Runtime> EventSink for tauri::Window<R> {
+    fn emit_json(&self, event: &str, payload: serde_json::Value) {
+        let _ = self.app_handle().emit(event, payload);
     }
 }
 
-// 內部輔助函數：執行實際的帶網速下載
-async fn perform_download(
-    window: &tauri::Window, 
-    url: &str, 
-    save_path: &PathBuf, 
-    base_prog: f64, 
-    max_prog: f64
-) -> Result<(), String> {
-    let client = reqwest::Client::new();
-    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
-    let total_size = response.content_length().unwrap_or(0);
-    
-    let mut file = std::fs::File::create(save_path).map_err(|e| e.to_string())?;
-    let mut downloaded: u64 = 0;
-    let start_time = std::time::Instant::now();
-    let mut stream = response.bytes_stream();
-
-    while let Some(item) = stream.next().await {
-        let chunk = item.map_err(|e| e.to_string())?;
-        file.write_all(&chunk).map_err(|e| e.to_string())?;
-        downloaded += chunk.len() as u64;
-
-        let elapsed = start_time.elapsed().as_secs_f64();
-        if elapsed > 0.5 { // 每 0.5 秒更新一次數據
-            let speed_bps = downloaded as f64 / elapsed;
-            let progress_ratio = if total_size > 0 { downloaded as f64 / total_size as f64 } else { 0.0 };
-            let current_progress = base_prog + (progress_ratio * (max_prog - base_prog));
-            
-            let speed_text = if speed_bps > 1024.0 * 1024.0 {
-                format!("{:.2} MB/s", speed_bps / (1024.0 * 1024.0))
-            } else {
-                format!("{:.2} KB/s", speed_bps / 1024.0)
-            };
-
-            let eta_text = if total_size > 0 && speed_bps > 0.0 {
-                let remaining_secs = (total_size - downloaded) as f64 / speed_bps;
-                format!("{:02}:{:02}", (remaining_secs / 60.0) as i32, (remaining_secs % 60.0) as i32)
-            } else {
-                "--:--".into()
-            };
-
-            // [2026-01-19 修正] 使用 app_handle().emit 確保所有視窗收到進度
-            let _ = window.app_handle().emit("download-progress", DownloadPayload {
-                progress: current_progress,
-                speed: speed_text,
-                eta: eta_text,
-            });
-        }
+// [2026-10-19 新增] 背景佇列沒有對應的視窗，直接透過 AppHandle 廣播
+impl<R: tauri::Runtime> EventSink for tauri::AppHandle<R> {
+    fn emit_json(&self, event: &str, payload: serde_json::Value) {
+        let _ = self.emit(event, payload);
     }
-    Ok(())
 }
 
 // [2026-01-17 修正] 強化版強制退出：確保殺掉所有可能殘留的 yt-dlp 子進程，避免背景佔用
@@ -132,375 +85,376 @@ fn exit_app() {
     std::process::exit(0);
 }
 
+// [2026-10-19 修正] 先驗證網址 (僅允許 http/https)，失敗時的備援改用參數陣列呼叫，
+// 不再把網址拼進 PowerShell 指令字串，避免含引號的網址執行任意指令
 #[tauri::command]
 async fn open_link(app: tauri::AppHandle, url: String) -> Result<(), String> {
     use tauri_plugin_opener::OpenerExt;
-    if let Err(_) = app.opener().open_url(&url, None::<&str>) {
+    let url = urls::validate_external_url(&url)?;
+    if app.opener().open_url(url.as_str(), None::<&str>).is_err() {
         #[cfg(target_os = "windows")]
         {
-            let mut cmd = Command::new("powershell");
-            cmd.args(["-Command", &format!("Start-Process '{}'", url)]);
-            cmd.creation_flags(0x08000000); 
+            let mut cmd = Command::new("rundll32");
+            cmd.args(["url.dll,FileProtocolHandler", url.as_str()]);
+            cmd.creation_flags(0x08000000);
             cmd.spawn().map_err(|e| format!("無法開啟網頁: {}", e))?;
         }
     }
     Ok(())
 }
 
-// [2026-01-18 修改] 偵測邏輯新增 deno.exe，確保環境完整
-#[tauri::command]
-fn check_core_components(window: tauri::Window, lang: String) -> Result<bool, String> {
-    let app_dir = get_app_dir();
-    let yt_exists = app_dir.join("yt-dlp.exe").exists();
-    let ff_exists = app_dir.join("ffmpeg.exe").exists();
-    let de_exists = app_dir.join("deno.exe").exists(); // [2026-01-18 新增]
-    
-    let is_ok = yt_exists && ff_exists && de_exists;
-    let _ = window.emit("core-status-update", is_ok);
-    
-    if is_ok {
-        Ok(true)
-    } else {
-        let mut missing = Vec::new();
-        if !yt_exists { missing.push("yt-dlp.exe"); }
-        if !ff_exists { missing.push("ffmpeg.exe"); }
-        if !de_exists { missing.push("deno.exe"); } // [2026-01-18 新增]
-        let log_txt = get_msg(&lang, 
-            &format!("⚠️ 核心組件不完整，缺失: {}", missing.join(", ")),
-            &format!("⚠️ Core components incomplete, missing: {}", missing.join(", "))
-        );
-        let _ = window.emit("backend-log", log_txt);
-        Ok(false) 
-    }
+#[tauri::command]
+fn check_core_components(window: tauri::Window, state: tauri::State<'_, EnvState>, lang: String) -> Result<bool, String> {
+    Ok(components::check(&state.snapshot(), &window, &lang))
+}
+
+// [2026-10-19 新增] 回報每個組件的實際位置與來源 (設定路徑 / 使用者資料夾 / 程式目錄 / PATH)
+#[tauri::command]
+fn locate_components(state: tauri::State<'_, EnvState>) -> Vec<ComponentStatus> {
+    state.snapshot().locator().status()
 }
 
-// [2026-01-18 修改] 修復程序新增 Deno 下載邏輯
 #[tauri::command]
-async fn download_components(window: tauri::Window, lang: String) -> Result<String, String> {
+fn get_settings(state: tauri::State<'_, EnvState>) -> Result<Settings, String> {
+    state.settings()
+}
+
+#[tauri::command]
+fn save_settings(state: tauri::State<'_, EnvState>, settings: Settings) -> Result<(), String> {
+    state.update_settings(settings)
+}
+
+#[tauri::command]
+async fn download_components(window: tauri::Window, state: tauri::State<'_, EnvState>, lang: String) -> Result<String, String> {
     let mut lock = DOWNLOAD_LOCK.lock().await;
     if *lock { return Err("BUSY".into()); }
     *lock = true;
 
-    let app_dir = get_app_dir();
-    let yt_path = app_dir.join("yt-dlp.exe");
-    let ff_path = app_dir.join("ffmpeg.exe");
-    let de_path = app_dir.join("deno.exe"); // [2026-01-18 新增]
-
-    let yt_missing = !yt_path.exists();
-    let ff_missing = !ff_path.exists();
-    let de_missing = !de_path.exists(); // [2026-01-18 新增]
-
-    let msg_start = get_msg(&lang, "🚀 啟動修復程序：正在下載缺失組件...", "🚀 Starting repair: Downloading missing components...");
-    let _ = window.emit("backend-log", msg_start);
-
-    // 下載 yt-dlp
-    if yt_missing {
-        let log_msg = get_msg(&lang, "⬇️ 正在獲取 yt-dlp.exe...", "⬇️ Downloading yt-dlp.exe...");
-        let _ = window.emit("backend-log", log_msg);
-        if let Err(e) = perform_download(&window, "https://github.com/yt-dlp/yt-dlp/releases/latest/download/yt-dlp.exe", &yt_path, 0.0, 30.0).await {
-            *lock = false;
-            return Err(e);
-        }
-    }
-
-    // 下載 FFmpeg
-    if ff_missing {
-        let log_msg = get_msg(&lang, "⬇️ 正在獲取 ffmpeg.exe (此檔案較大)...", "⬇️ Downloading ffmpeg.exe (Large file)...");
-        let _ = window.emit("backend-log", log_msg);
-        
-        let zip_path = app_dir.join("ffmpeg.zip");
-        if let Err(e) = perform_download(&window, "https://github.com/BtbN/FFmpeg-Builds/releases/download/latest/ffmpeg-master-latest-win64-gpl.zip", &zip_path, 30.0, 80.0).await {
-            *lock = false;
-            return Err(e);
-        }
+    let env = state.snapshot();
+    let result = components::repair(&env, &window, &lang).await;
 
-        let _ = window.emit("backend-log", get_msg(&lang, "📦 正在解壓並部署 FFmpeg...", "📦 Extracting and deploying FFmpeg..."));
-        let mut cmd = Command::new("powershell");
-        cmd.args(["-Command", &format!(
-            "Expand-Archive -Path '{}' -DestinationPath './ff_tmp' -Force; \
-             Move-Item './ff_tmp/*/bin/ffmpeg.exe' '{}' -Force; \
-             Remove-Item '{}'; Remove-Item './ff_tmp' -Recurse", 
-             zip_path.display(), ff_path.display(), zip_path.display())]);
-        
-        #[cfg(target_os = "windows")]
-        cmd.creation_flags(0x08000000); 
-        let _ = cmd.output();
-    }
+    *lock = false;
+    result
+}
 
-    // [2026-01-18 新增] 下載 Deno 引擎 (YouTube SABR 解碼必需)
-    if de_missing {
-        let log_msg = get_msg(&lang, "⬇️ 正在獲取解碼引擎 (Deno)...", "⬇️ Downloading Decode Engine (Deno)...");
-        let _ = window.emit("backend-log", log_msg);
+#[tauri::command]
+async fn get_local_yt_dlp_version(state: tauri::State<'_, EnvState>) -> Result<String, String> {
+    let yt_exe = state.snapshot().yt_dlp();
+    components::local_yt_dlp_version(&yt_exe).await
+}
 
-        let de_zip_path = app_dir.join("deno.zip");
-        // 下載進度分配在 80% 到 95%
-        if let Err(e) = perform_download(&window, "https://github.com/denoland/deno/releases/latest/download/deno-x86_64-pc-windows-msvc.zip", &de_zip_path, 80.0, 95.0).await {
-            *lock = false;
-            return Err(e);
-        }
+#[tauri::command]
+async fn check_remote_yt_dlp_version(state: tauri::State<'_, EnvState>) -> Result<String, String> {
+    let env = state.snapshot();
+    components::remote_yt_dlp_version(&env).await
+}
 
-        let _ = window.emit("backend-log", get_msg(&lang, "📦 正在部署解碼引擎...", "📦 Deploying Decode Engine..."));
-        let mut cmd = Command::new("powershell");
-        
-        // [修正邏輯] 使用更安全的路徑指定方式，確保 deno.exe 被解壓到正確的 app_dir
-        let dest_dir = app_dir.to_string_lossy();
-        cmd.args(["-Command", &format!(
-            "Expand-Archive -Path '{}' -DestinationPath '{}' -Force; \
-             Remove-Item '{}' -Force", 
-             de_zip_path.display(), dest_dir, de_zip_path.display())]);
-        
-        #[cfg(target_os = "windows")]
-        cmd.creation_flags(0x08000000); 
-        let _ = cmd.output();
-    }
-    let is_ready = yt_path.exists() && ff_path.exists() && de_path.exists();
-    let _ = window.emit("core-status-update", is_ready);
+// [2026-10-19 新增] 在呼叫 yt-dlp 前先正規化並檢查網址，回傳具型別的結果 / 錯誤
+#[tauri::command]
+async fn normalize_url(state: tauri::State<'_, EnvState>, url: String, check_extractors: Option<bool>) -> Result<NormalizedUrl, UrlError> {
+    let env = state.snapshot();
+    urls::prevalidate(&env, &url, check_extractors.unwrap_or(false)).await
+}
 
-    *lock = false;
+#[tauri::command]
+async fn analyze_video(window: tauri::Window, state: tauri::State<'_, EnvState>, url: String, lang: String) -> Result<VideoMetadata, String> {
+    let env = state.snapshot();
+    ytdlp::analyze(&env, &window, &url, &lang).await
+}
 
-    if is_ready {
-        let _ = window.emit("download-progress", DownloadPayload { progress: 100.0, speed: "Done".into(), eta: "00:00".into() });
-        let _ = window.emit("backend-log", get_msg(&lang, "✅ 核心組件修復完成！", "✅ Core components repair completed!"));
-        Ok("OK".into())
-    } else {
-        let _ = window.emit("download-progress", DownloadPayload { progress: 0.0, speed: "".into(), eta: "".into() });
-        let _ = window.emit("backend-log", get_msg(&lang, "❌ 修復失敗，請檢查網路。", "❌ Repair failed."));
-        Err("Fail".into())
-    }
+// [2026-10-19 新增] 開始下載前預估檔案大小並檢查磁碟空間
+#[tauri::command]
+async fn estimate_download(
+    state: tauri::State<'_, EnvState>,
+    url: String,
+    mode: String,
+    quality: String,
+    path: String,
+    options: Option<DownloadOptions>,
+) -> Result<diskspace::SizeEstimate, String> {
+    let req = DownloadRequest { url, mode, quality, path, lang: String::new(), options: options.unwrap_or_default(), profile: None };
+    ytdlp::estimate_size(&state.snapshot(), &req).await
 }
 
-// [2026-01-18 新增] 獲取本地 yt-dlp 版本號
+// [2026-10-19 新增] 檢查儲存資料夾是否可用 (create 為 true 時建立不存在的資料夾)，無法使用時回報改用的預設資料夾
 #[tauri::command]
-async fn get_local_yt_dlp_version() -> Result<String, String> {
-    let app_dir = get_app_dir();
-    let yt_exe = app_dir.join("yt-dlp.exe");
+fn validate_save_path(state: tauri::State<'_, EnvState>, path: String, create: bool) -> Result<savepath::SavePath, String> {
+    savepath::resolve(&state.snapshot().settings.downloads, &path, create)
+}
 
-    if !yt_exe.exists() {
-        return Ok("none".into());
+/// 手動下載 (下載頁)；與佇列共用下載鎖，並可由「停止下載」中斷
+async fn run_manual_download(window: &tauri::Window, state: &EnvState, req: DownloadRequest) -> Result<DownloadResult, String> {
+    // [2026-10-19 修改] 佇列任務執行時也會占用鎖，改用 try_lock 立即回報忙碌，而不是等待
+    let Ok(mut lock) = DOWNLOAD_LOCK.try_lock() else {
+        return Err(get_msg(&req.lang, "⚠️ 已有任務正在下載中", "⚠️ Task is already in progress"));
+    };
+    if *lock {
+        return Err(get_msg(&req.lang, "⚠️ 已有任務正在下載中", "⚠️ Task is already in progress"));
     }
+    *lock = true;
 
-    let mut cmd = Command::new(&yt_exe);
-    cmd.args(["--version"]);
-    #[cfg(target_os = "windows")]
-    cmd.creation_flags(0x08000000);
-
-    let output = cmd.output().map_err(|e| e.to_string())?;
-    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    Ok(version)
-}
-
-// [2026-01-18 新增] 獲取遠端 GitHub 最新 yt-dlp 版本號 (方案 B)
-#[tauri::command]
-async fn check_remote_yt_dlp_version() -> Result<String, String> {
-    let client = reqwest::Client::builder()
-        .user_agent("Tauri-Video-Downloader") // GitHub API 要求必須有 User-Agent
-        .build()
-        .map_err(|e: reqwest::Error| e.to_string())?;
-
-    let resp = client.get("https://api.github.com/repos/yt-dlp/yt-dlp/releases/latest")
-        .send()
-        .await
-        .map_err(|e: reqwest::Error| e.to_string())?;
-        
-    // [修正] 明確標註反序列化的類型為 serde_json::Value
-    let json = resp.json::<serde_json::Value>()
-        .await
-        .map_err(|e: reqwest::Error| e.to_string())?;
-    
-    // GitHub 的 tag_name 通常是日期格式，如 2025.01.15
-    let latest_version = json["tag_name"].as_str().unwrap_or("").to_string();
-    
-    Ok(latest_version)
-}
-
-#[tauri::command]
-async fn analyze_video(window: tauri::Window, url: String, lang: String) -> Result<VideoMetadata, String> {
-    let app_dir = get_app_dir();
-    let yt_exe = app_dir.join("yt-dlp.exe");
-
-    if !yt_exe.exists() { 
-        let _ = window.emit("core-status-update", false);
-        let _ = window.emit("backend-log", get_msg(&lang, "❌ 找不到 yt-dlp.exe", "❌ yt-dlp.exe not found"));
-        return Err("Missing Core".into()); 
-    }
+    let env = state.snapshot();
+    let stop = process::StopSignal::default();
+    *CURRENT_STOP.lock().unwrap_or_else(|e| e.into_inner()) = Some(stop.clone());
+    let (result, hooks) = ytdlp::download_deferring_hooks(&env, window, &req, &stop).await;
+    *CURRENT_STOP.lock().unwrap_or_else(|e| e.into_inner()) = None;
 
-    let _ = window.emit("backend-log", get_msg(&lang, "🔍 正在解析影片...", "🔍 Analyzing..."));
+    *lock = false;
+    drop(lock);
+    spawn_hooks(env, window.clone(), hooks);
+    result
+}
 
-    let mut cmd = Command::new(&yt_exe);
-    // [2026-01-18 修正] 加入 --no-config 確保穩定性
-    cmd.args(["--no-config", "--quiet", "--no-warnings", "--skip-download", "--dump-json", &url]);
-    #[cfg(target_os = "windows")]
-    cmd.creation_flags(0x08000000); 
-
-    let output = cmd.output().map_err(|e| e.to_string())?;
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    if stdout.trim().is_empty() { return Err("Empty Output".into()); }
-
-    let json: serde_json::Value = serde_json::from_str(&stdout).map_err(|e| e.to_string())?;
-    
-    let mut video_formats = std::collections::HashMap::new();
-    let mut audio_formats = std::collections::HashMap::new();
-
-    if let Some(fmts) = json["formats"].as_array() {
-        for f in fmts {
-            let vcodec = f["vcodec"].as_str().unwrap_or("none");
-            let acodec = f["acodec"].as_str().unwrap_or("none");
-
-            if vcodec != "none" {
-                let res = f["resolution"].as_str().or(f["format_note"].as_str()).unwrap_or("unknown");
-                video_formats.entry(res.to_string()).or_insert(f["format_id"].as_str().unwrap_or("").to_string());
-            } else if acodec != "none" && vcodec == "none" {
-                let abr = f["abr"].as_f64().or(f["tbr"].as_f64()).unwrap_or(0.0);
-                let bitrate = format!("{}k", abr as i32);
-                audio_formats.entry(bitrate).or_insert(f["format_id"].as_str().unwrap_or("").to_string());
-            }
-        }
+/// [2026-10-19 新增] 下載鎖釋放後，在背景執行下載結束的掛鉤
+fn spawn_hooks<S: EventSink + 'static>(env: AppEnv, sink: S, hooks: PendingHooks) {
+    if !hooks.is_empty() {
+        tauri::async_runtime::spawn(async move {
+            hooks.run(&env, &sink).await;
+        });
     }
+}
 
-    let mut v_list: Vec<VideoFormat> = video_formats.into_iter()
-        .map(|(res, id)| VideoFormat { id, ext: "mp4".into(), resolution: res })
-        .collect();
+// [2026-10-19 修正] 維持回傳字串 (前端依此判斷成功)；完整結果改由 download_video_detailed 取得
+#[tauri::command]
+async fn download_video(
+    window: tauri::Window,
+    state: tauri::State<'_, EnvState>,
+    url: String,
+    mode: String,
+    quality: String,
+    path: String,
+    lang: String, 
+    options: Option<DownloadOptions>,
+    profile: Option<String>,
+) -> Result<String, String> {
+    let req = DownloadRequest { url, mode, quality, path, lang, options: options.unwrap_or_default(), profile };
+    run_manual_download(&window, &state, req).await.map(|_| "Success".to_string())
+}
 
-    v_list.sort_by(|a, b| {
-        let get_num = |s: &str| s.chars().filter(|c| c.is_digit(10)).collect::<String>().parse::<i32>().unwrap_or(0);
-        get_num(&b.resolution).cmp(&get_num(&a.resolution))
-    });
+// [2026-10-19 新增] 與 download_video 相同，但回傳完整的下載結果 (輸出檔、剪掉的片段等)
+#[tauri::command]
+async fn download_video_detailed(
+    window: tauri::Window,
+    state: tauri::State<'_, EnvState>,
+    url: String,
+    mode: String,
+    quality: String,
+    path: String,
+    lang: String,
+    options: Option<DownloadOptions>,
+    profile: Option<String>,
+) -> Result<DownloadResult, String> {
+    let req = DownloadRequest { url, mode, quality, path, lang, options: options.unwrap_or_default(), profile };
+    run_manual_download(&window, &state, req).await
+}
 
-    let mut a_list: Vec<VideoFormat> = audio_formats.into_iter()
-        .map(|(bit, id)| VideoFormat { id, ext: "mp3".into(), resolution: bit })
-        .collect();
+// [2026-10-19 新增] 各網站登入資訊管理 (列表不含密碼)
+#[tauri::command]
+fn list_credentials(state: tauri::State<'_, EnvState>) -> Result<Vec<CredentialSummary>, String> {
+    CredentialStore::load(&state.snapshot()).map(|store| store.list())
+}
 
-    a_list.sort_by(|a, b| {
-        let get_num = |s: &str| s.chars().filter(|c| c.is_digit(10)).collect::<String>().parse::<i32>().unwrap_or(0);
-        get_num(&b.resolution).cmp(&get_num(&a.resolution))
-    });
+#[tauri::command]
+fn import_cookies(state: tauri::State<'_, EnvState>, site: String, path: String) -> Result<(), String> {
+    CredentialStore::import_cookies(&state.snapshot(), &site, std::path::Path::new(&path))
+}
 
-    let mut final_formats = v_list;
-    final_formats.extend(a_list);
+#[tauri::command]
+fn use_browser_cookies(state: tauri::State<'_, EnvState>, site: String, browser: String, profile: Option<String>) -> Result<(), String> {
+    CredentialStore::use_browser(&state.snapshot(), &site, &browser, profile)
+}
 
-    let _ = window.emit("backend-log", get_msg(&lang, "✅ 解析完成", "✅ Analysis complete"));
+#[tauri::command]
+fn set_site_login(state: tauri::State<'_, EnvState>, site: String, username: String, password: String) -> Result<(), String> {
+    CredentialStore::set_login(&state.snapshot(), &site, &username, &password)
+}
 
-    Ok(VideoMetadata {
-        title: json["title"].as_str().unwrap_or("未知標題").into(),
-        thumbnail: json["thumbnail"].as_str().unwrap_or("").into(),
-        formats: final_formats,
-    })
+#[tauri::command]
+fn remove_credential(state: tauri::State<'_, EnvState>, site: String) -> Result<(), String> {
+    CredentialStore::remove(&state.snapshot(), &site)
 }
 
+// [2026-10-19 新增] 排程下載佇列
 #[tauri::command]
-async fn download_video(
-    window: tauri::Window,
+fn enqueue_download(
+    queue: tauri::State<'_, Arc<DownloadQueue>>,
     url: String,
     mode: String,
     quality: String,
     path: String,
-    lang: String, 
-) -> Result<String, String> {
-    let mut lock = DOWNLOAD_LOCK.lock().await;
-    if *lock {
-        return Err(get_msg(&lang, "⚠️ 已有任務正在下載中", "⚠️ Task is already in progress"));
-    }
-    *lock = true;
+    lang: String,
+    options: Option<DownloadOptions>,
+    schedule: Option<JobSchedule>,
+    profile: Option<String>,
+) -> Result<QueuedJob, String> {
+    let req = DownloadRequest { url, mode, quality, path, lang, options: options.unwrap_or_default(), profile };
+    queue.enqueue(req, schedule.unwrap_or_default())
+}
+
+#[tauri::command]
+fn list_queue(queue: tauri::State<'_, Arc<DownloadQueue>>) -> Vec<QueuedJob> {
+    queue.list()
+}
+
+#[tauri::command]
+fn pause_job(queue: tauri::State<'_, Arc<DownloadQueue>>, id: String) -> Result<(), String> {
+    queue.pause(&id)
+}
+
+#[tauri::command]
+fn resume_job(queue: tauri::State<'_, Arc<DownloadQueue>>, id: String) -> Result<(), String> {
+    queue.resume(&id)
+}
+
+#[tauri::command]
+fn remove_job(queue: tauri::State<'_, Arc<DownloadQueue>>, id: String) -> Result<(), String> {
+    queue.remove(&id)
+}
 
-    // [2026-01-18 防呆修正] 檢查 quality 是否為空，避免因為前端 reset 導致的邏輯錯誤
-    if quality.is_empty() {
-        *lock = false;
-        return Err(get_msg(&lang, "❌ 錯誤：未選擇下載品質或格式", "❌ Error: Quality or format not selected"));
+/// 背景執行佇列：有到期任務且沒有其他下載時執行
+async fn run_queue_worker(app: tauri::AppHandle, queue: Arc<DownloadQueue>) {
+    loop {
+        if queue.has_due() {
+            if let Ok(mut lock) = DOWNLOAD_LOCK.try_lock() {
+                *lock = true;
+                let env = app.state::<EnvState>().snapshot();
+                // [2026-10-19 修正] 與 download_video 相同，讓「停止下載」也能停止佇列中的任務
+                loop {
+                    let stop = process::StopSignal::default();
+                    *CURRENT_STOP.lock().unwrap_or_else(|e| e.into_inner()) = Some(stop.clone());
+                    let ran = queue.run_next_until(&env, &app, stop).await;
+                    *CURRENT_STOP.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                    // 掛鉤不占用下載鎖，與下一個任務同時進行
+                    let Some(hooks) = ran else { break };
+                    spawn_hooks(env.clone(), app.clone(), hooks);
+                }
+                *lock = false;
+            }
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
     }
+}
+
+// [2026-10-19 新增] 批次匯入網址清單 (txt / csv / json)
+#[tauri::command]
+fn import_batch(
+    state: tauri::State<'_, EnvState>,
+    queue: tauri::State<'_, Arc<DownloadQueue>>,
+    path: String,
+    defaults: batch::BatchDefaults,
+) -> Result<batch::BatchReport, String> {
+    batch::import_file(&state.snapshot(), &queue, std::path::Path::new(&path), &defaults)
+}
 
-    let app_dir = get_app_dir();
-    let yt_exe = app_dir.join("yt-dlp.exe");
-    let ff_exe = app_dir.join("ffmpeg.exe");
+// [2026-10-19 新增] 下載紀錄與匯出
+#[tauri::command]
+fn list_history(state: tauri::State<'_, EnvState>) -> Result<Vec<history::HistoryEntry>, String> {
+    history::load(&state.snapshot().history_path())
+}
 
-    let _ = window.app_handle().emit("backend-log", get_msg(&lang, "⚙️ 準備下載...", "⚙️ Preparing..."));
+#[tauri::command]
+fn clear_history(state: tauri::State<'_, EnvState>) -> Result<(), String> {
+    history::clear(&state.snapshot().history_path())
+}
 
-    let mut info_cmd = Command::new(&yt_exe);
-    // [2026-01-18 修正] 加入 --no-config
-    info_cmd.args(["--no-config", "--quiet", "--skip-download", "--dump-json", &url]);
-    #[cfg(target_os = "windows")]
-    info_cmd.creation_flags(0x08000000);
-
-    let info_output = info_cmd.output().map_err(|e| { *lock = false; e.to_string() })?;
-    let info_json: serde_json::Value = serde_json::from_str(&String::from_utf8_lossy(&info_output.stdout)).map_err(|e| { *lock = false; e.to_string() })?;
-    let title = info_json["title"].as_str().unwrap_or("unknown");
-    
-    let ext = if mode == "video" { "mp4" } else { "mp3" };
-    let final_path = get_unique_path(Path::new(&path), title, &quality, ext);
-    let final_path_str = final_path.to_string_lossy().to_string();
-
-    let _ = window.app_handle().emit("backend-log", get_msg(&lang, "📥 開始下載...", "📥 Downloading..."));
-
-    let fmt_val = if mode == "video" {
-        if quality == "best" { "bestvideo+bestaudio/best".to_string() } else { format!("{}+bestaudio/best", quality) }
-    } else {
-        if quality == "bestaudio" { "bestaudio/best".to_string() } else { quality.clone() }
-    };
+// [2026-10-19 新增] 從下載檔旁的 .info.json / .description 還原紀錄的中繼資料與留言
+#[tauri::command]
+fn rehydrate_history(state: tauri::State<'_, EnvState>, id: String) -> Result<archival::Rehydrated, String> {
+    archival::rehydrate(&state.snapshot(), &id)
+}
+
+// [2026-10-19 新增] 對既有的下載紀錄試跑整理規則 (不搬移檔案)；rules 可傳入尚未儲存的規則
+#[tauri::command]
+fn preview_organize(state: tauri::State<'_, EnvState>, id: String, rules: Option<Vec<organizer::OrganizeRule>>) -> Result<organizer::OrganizePlan, String> {
+    organizer::preview(&state.snapshot(), &id, rules.as_deref())
+}
+
+#[tauri::command]
+fn export_downloads(
+    state: tauri::State<'_, EnvState>,
+    queue: tauri::State<'_, Arc<DownloadQueue>>,
+    source: export::ExportSource,
+    format: export::ExportFormat,
+    dest: String,
+    filter: Option<export::ExportFilter>,
+) -> Result<usize, String> {
+    let filter = filter.unwrap_or_default();
+    export::export(&state.snapshot(), &queue, source, format, &filter, std::path::Path::new(&dest))
+}
+
+// [2026-10-19 新增] 頻道 / 播放清單訂閱
+#[tauri::command]
+fn list_subscriptions(subs: tauri::State<'_, Arc<SubscriptionStore>>) -> Vec<Subscription> {
+    subs.list()
+}
+
+#[tauri::command]
+fn add_subscription(subs: tauri::State<'_, Arc<SubscriptionStore>>, config: SubscriptionConfig) -> Result<Subscription, String> {
+    subs.add(config)
+}
 
-    // [修正邏輯錯誤 E0716] 將 to_string_lossy() 產生的暫時字串綁定到變數，以延長生命週期
-    let ff_path_lossy = ff_exe.to_string_lossy();
-    let ff_path_str = ff_path_lossy.as_ref();
-    let mut args = vec![
-        "--no-config", // [2026-01-18 修正] 加入 --no-config 確保調用 deno.exe
-        "--progress", "--newline",
-        "--ffmpeg-location", ff_path_str,
-        "-o", &final_path_str,
-    ];
-
-    if mode == "video" {
-        args.extend(["-f", &fmt_val, "--merge-output-format", "mp4"]);
-    } else {
-        args.extend(["-f", &fmt_val, "--extract-audio", "--audio-format", "mp3", "--audio-quality", "256K"]);
+#[tauri::command]
+fn edit_subscription(subs: tauri::State<'_, Arc<SubscriptionStore>>, id: String, config: SubscriptionConfig) -> Result<(), String> {
+    subs.edit(&id, config)
+}
+
+#[tauri::command]
+fn set_subscription_enabled(subs: tauri::State<'_, Arc<SubscriptionStore>>, id: String, enabled: bool) -> Result<(), String> {
+    subs.set_enabled(&id, enabled)
+}
+
+#[tauri::command]
+fn remove_subscription(subs: tauri::State<'_, Arc<SubscriptionStore>>, id: String) -> Result<(), String> {
+    subs.remove(&id)
+}
+
+/// 立即檢查訂閱 (不等待檢查間隔)
+#[tauri::command]
+async fn check_subscription(
+    state: tauri::State<'_, EnvState>,
+    subs: tauri::State<'_, Arc<SubscriptionStore>>,
+    queue: tauri::State<'_, Arc<DownloadQueue>>,
+    id: String,
+) -> Result<Vec<QueuedJob>, String> {
+    subscriptions::check(&state.snapshot(), &subs, &queue, &id).await
+}
+
+/// 背景檢查到期的訂閱，新項目交給佇列執行
+async fn run_subscription_poller(app: tauri::AppHandle, subs: Arc<SubscriptionStore>, queue: Arc<DownloadQueue>) {
+    loop {
+        let env = app.state::<EnvState>().snapshot();
+        subscriptions::check_due(&env, &app, &subs, &queue).await;
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
     }
-    args.push(&url);
+}
 
-    let mut child_cmd = Command::new(&yt_exe);
-    child_cmd.args(args);
-    child_cmd.stdout(Stdio::piped());
-    child_cmd.stderr(Stdio::piped());
-    #[cfg(target_os = "windows")]
-    child_cmd.creation_flags(0x08000000);
-
-    let mut child = child_cmd.spawn().map_err(|e| { *lock = false; e.to_string() })?;
-    
-    // [2026-01-18 優化] 獨立獲取管道，避免緩衝區堵塞
-    let stdout = child.stdout.take().ok_or("No Stdout")?;
-    let stderr = child.stderr.take().ok_or("No Stderr")?;
-    let reader = BufReader::new(stdout);
-    let mut error_reader = BufReader::new(stderr);
-
-    let re = Regex::new(r"\[download\]\s+(\d+\.?\d*)%\s+of\s+.*\s+at\s+(.*)\s+ETA\s+(.*)").unwrap();
-
-    // [2026-01-18 修改] 強化日誌讀取：確保所有日誌都傳回前端，用於偵測轉檔狀態
-    for line in reader.lines() {
-        if let Ok(content) = line {
-            // 1. 進度正則判斷
-            if let Some(caps) = re.captures(&content) {
-                let progress = caps[1].parse::<f64>().unwrap_or(0.0);
-                let speed = caps[2].trim().to_string();
-                let eta = caps[3].trim().to_string();
-                // [2026-01-19 修正] 改用 app_handle().emit
-                let _ = window.app_handle().emit("download-progress", DownloadPayload { progress, speed, eta });
-            }
-            
-            // 2. 將原始日誌行廣播發送給所有視窗
-            let _ = window.app_handle().emit("backend-log", content.clone());
+/// [2026-10-19 新增] 背景監看剪貼簿 (設定中開啟時)，偵測到的影片網址依設定通知 / 解析 / 加入佇列
+async fn run_clipboard_watcher(app: tauri::AppHandle, queue: Arc<DownloadQueue>) {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+    let mut watcher = clipboard::ClipboardWatcher::default();
+    loop {
+        tokio::time::sleep(clipboard::POLL_INTERVAL).await;
+        let env = app.state::<EnvState>().snapshot();
+        if !env.settings.clipboard.enabled {
+            watcher.reset();
+            continue;
+        }
+        let Ok(text) = app.clipboard().read_text() else { continue };
+        for url in watcher.observe(&text, &env.settings.clipboard) {
+            clipboard::handle(&env, &app, &queue, url).await;
         }
     }
+}
 
-    let result = child.wait().map_err(|e| { *lock = false; e.to_string() })?;
-    *lock = false; 
-
-    if result.success() {
-        let _ = window.app_handle().emit("backend-log", get_msg(&lang, "🎉 下載完成！", "🎉 Finished!"));
-        Ok("Success".to_string())
-    } else {
-        // [2026-01-18 優化] 失敗時才讀取具體原因
-        let mut err_msg = String::new();
-        let _ = error_reader.read_to_string(&mut err_msg);
-        if err_msg.is_empty() {
-            err_msg = "Download process failed. Possibly network or format issues.".into();
+// [2026-10-19 新增] 停止目前的下載；直播錄製會保留並封裝已錄到的內容
+#[tauri::command]
+fn stop_download() -> bool {
+    match CURRENT_STOP.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        Some(stop) => {
+            stop.stop();
+            true
         }
-        Err(err_msg)
+        None => false,
     }
 }
 
@@ -512,9 +466,23 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_dialog::init())
-        .setup(|_app| {
+        .setup(|app| {
             // [2026-01-19 修正] 依照要求徹底移除 Mini 懸浮窗邏輯
             // 以免刪除 mini.html 後程式因找不到視窗源檔案而報錯
+            // [2026-10-19 新增] 注入執行環境，指令不再自行推導組件路徑
+            // 使用者資料目錄一定可寫入，修復下載與設定檔都放在這裡
+            let data_dir = app.path().app_local_data_dir().unwrap_or_else(|_| env::get_app_dir());
+            let env = AppEnv::from_app_dir(data_dir);
+            // [2026-10-19 新增] 載入排程佇列並啟動背景執行
+            let queue = Arc::new(DownloadQueue::load(env.queue_path()));
+            // [2026-10-19 新增] 訂閱定期檢查，新影片加入同一個佇列
+            let subs = Arc::new(SubscriptionStore::load(env.subscriptions_path()));
+            app.manage(EnvState::new(env));
+            app.manage(queue.clone());
+            app.manage(subs.clone());
+            tauri::async_runtime::spawn(run_subscription_poller(app.handle().clone(), subs, queue.clone()));
+            tauri::async_runtime::spawn(run_clipboard_watcher(app.handle().clone(), queue.clone()));
+            tauri::async_runtime::spawn(run_queue_worker(app.handle().clone(), queue));
             Ok(())
         })
         .on_window_event(|window, event| {
@@ -527,9 +495,39 @@ pub fn run() {
             }
         })
         .invoke_handler(tauri::generate_handler![
+            normalize_url,
             analyze_video,
+            estimate_download,
+            validate_save_path,
             download_video,
+            download_video_detailed,
+            stop_download,
+            enqueue_download,
+            list_queue,
+            pause_job,
+            resume_job,
+            remove_job,
+            import_batch,
+            list_history,
+            clear_history,
+            preview_organize,
+            rehydrate_history,
+            export_downloads,
+            list_subscriptions,
+            add_subscription,
+            edit_subscription,
+            set_subscription_enabled,
+            remove_subscription,
+            check_subscription,
+            list_credentials,
+            import_cookies,
+            use_browser_cookies,
+            set_site_login,
+            remove_credential,
             check_core_components,
+            locate_components,
+            get_settings,
+            save_settings,
             download_components,
             get_local_yt_dlp_version,
             check_remote_yt_dlp_version,
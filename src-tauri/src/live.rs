@@ -0,0 +1,137 @@
+// [2026-10-19 新增] 直播錄製：判斷直播 / 預定直播、等待開播、從頭或從現在開始錄、
+// 以已錄製時間與大小回報進度，使用者停止時結束 yt-dlp 並把未完成的檔案封裝成可播放的成品。
+// 直播一律使用 MPEG-TS 暫存檔 (--hls-use-mpegts)，即使中途被終止內容也完整可讀。
+use crate::env::AppEnv;
+use crate::events::{self, get_msg, EventSink};
+use crate::process;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 預定直播尚未開始時，yt-dlp 重新檢查的間隔秒數
+pub const WAIT_RETRY_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveStatus {
+    #[default]
+    NotLive,
+    IsLive,
+    IsUpcoming,
+    WasLive,
+    PostLive,
+}
+
+impl LiveStatus {
+    /// 依 info JSON 的 live_status (較新的 yt-dlp) 或 is_live 判斷
+    pub fn from_info(json: &serde_json::Value) -> Self {
+        match json["live_status"].as_str() {
+            Some("is_live") => LiveStatus::IsLive,
+            Some("is_upcoming") => LiveStatus::IsUpcoming,
+            Some("was_live") => LiveStatus::WasLive,
+            Some("post_live") => LiveStatus::PostLive,
+            Some(_) => LiveStatus::NotLive,
+            None if json["is_live"].as_bool() == Some(true) => LiveStatus::IsLive,
+            None => LiveStatus::NotLive,
+        }
+    }
+
+    /// 需要以錄製模式處理 (進行中或尚未開始)
+    pub fn is_recording(self) -> bool {
+        matches!(self, LiveStatus::IsLive | LiveStatus::IsUpcoming)
+    }
+}
+
+/// 預定開播時間 (Unix 秒)
+pub fn scheduled_start(json: &serde_json::Value) -> Option<i64> {
+    json["release_timestamp"].as_i64()
+}
+
+/// 直播錄製用的 yt-dlp 參數
+pub fn args(status: LiveStatus, from_start: bool) -> Vec<String> {
+    let mut args = vec!["--hls-use-mpegts".to_string()];
+    args.push(if from_start { "--live-from-start" } else { "--no-live-from-start" }.into());
+    if status == LiveStatus::IsUpcoming {
+        args.extend(["--wait-for-video".to_string(), WAIT_RETRY_SECS.to_string()]);
+    }
+    args
+}
+
+/// 與輸出檔同名開頭的所有檔案 (成品、.part、分段暫存檔)
+fn related_files(final_path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (final_path.parent(), final_path.file_name()) else { return Vec::new() };
+    // 以 "stem." 比對，避免算到同名加序號的其他檔案 (Title_best_1.mp4)
+    let stem = format!("{}.", final_path.file_stem().unwrap_or(name).to_string_lossy());
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir).map(|entries| {
+        entries.filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with(&stem))
+            .map(|e| e.path())
+            .collect()
+    }).unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// 目前已寫入磁碟的大小
+pub fn recorded_bytes(final_path: &Path) -> u64 {
+    related_files(final_path).iter()
+        .filter_map(|p| std::fs::metadata(p).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// 未完成的暫存檔 (影像與聲音可能分開，例如 --live-from-start 的 .f299.mp4.part)
+pub fn partial_files(final_path: &Path) -> Vec<PathBuf> {
+    related_files(final_path).into_iter()
+        .filter(|p| p.extension().is_some_and(|e| e == "part"))
+        .filter(|p| std::fs::metadata(p).is_ok_and(|m| m.len() > 0))
+        .collect()
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 { format!("{}{}", bytes, UNITS[0]) } else { format!("{:.2}{}", value, UNITS[unit]) }
+}
+
+/// 使用者停止錄製後，把暫存檔封裝成最終檔案；yt-dlp 已自行完成時直接回傳。
+/// 必須在 yt-dlp 與其啟動的 ffmpeg 都結束後才呼叫 (process::run_streaming_until 回傳時已確保)，否則 .part 檔仍在寫入
+pub async fn finalize(env: &AppEnv, sink: &dyn EventSink, lang: &str, final_path: &Path, audio_only: bool) -> Result<(), String> {
+    if final_path.exists() {
+        return Ok(());
+    }
+    let parts = partial_files(final_path);
+    if parts.is_empty() {
+        return Err(get_msg(lang, "❌ 直播尚未開始，沒有錄到任何內容", "❌ Nothing was recorded before stopping"));
+    }
+
+    events::log(sink, get_msg(lang, "🎞️ 正在封裝已錄製的內容...", "🎞️ Finalizing the recording..."));
+    let mut args: Vec<String> = vec!["-hide_banner".into(), "-nostdin".into(), "-loglevel".into(), "error".into(), "-y".into()];
+    for part in &parts {
+        args.extend(["-i".into(), part.to_string_lossy().to_string()]);
+    }
+    for i in 0..parts.len() {
+        args.extend(["-map".into(), i.to_string()]);
+    }
+    if audio_only {
+        args.extend(["-vn", "-c:a", "libmp3lame", "-b:a", "256k"].map(String::from));
+    } else {
+        args.extend(["-c", "copy"].map(String::from));
+    }
+    args.push(final_path.to_string_lossy().to_string());
+
+    let mut cmd = process::command(env.ffmpeg());
+    cmd.args(&args);
+    let outcome = process::run_streaming(cmd, |_| {}).await?;
+    if !outcome.status.success() {
+        return Err(format!("ffmpeg failed to finalize the recording: {}", outcome.error_message()));
+    }
+    for part in parts {
+        let _ = std::fs::remove_file(part);
+    }
+    Ok(())
+}
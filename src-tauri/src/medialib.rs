@@ -0,0 +1,155 @@
+// [2026-10-19 新增] 媒體庫輸出 (Jellyfin / Kodi)：把頻道當成節目、上傳年份當成季，
+// 檔案放到 Show/Season YYYY/Show - SYYYYEmmdd - Title.ext，並寫入 episodedetails .nfo (標題、簡介、播出日期、
+// 頻道、長度)。縮圖存成與檔案同名的 -thumb 圖片；節目資料夾另外寫入 tvshow.nfo 與 poster / fanart (已存在時不覆寫)。
+use crate::env::AppEnv;
+use crate::events::{self, get_msg, EventSink};
+use crate::network;
+use crate::ytdlp::{get_unique_named_path, sanitize_file_name};
+use chrono::Local;
+use quick_xml::escape::escape;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const IMAGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn text<'a>(info: &'a serde_json::Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter().find_map(|k| info[*k].as_str().map(str::trim).filter(|v| !v.is_empty()))
+}
+
+/// 節目名稱 (頻道 / 上傳者)
+pub fn show_name(info: &serde_json::Value) -> String {
+    text(info, &["channel", "uploader", "uploader_id"]).unwrap_or("Unknown").to_string()
+}
+
+/// 播出日期 (YYYYMMDD)；沒有上傳日期時使用下載當天
+pub fn aired_date(info: &serde_json::Value) -> String {
+    text(info, &["upload_date", "release_date"])
+        .filter(|d| d.len() == 8 && d.bytes().all(|b| b.is_ascii_digit()))
+        .map(str::to_string)
+        .unwrap_or_else(|| Local::now().format("%Y%m%d").to_string())
+}
+
+/// 資料夾與檔名中使用的名稱 (去掉結尾的點與空白，Windows 不允許)
+fn safe_name(name: &str) -> String {
+    let name = sanitize_file_name(name);
+    let name = name.trim().trim_end_matches('.').trim();
+    if name.is_empty() { "_".into() } else { name.to_string() }
+}
+
+/// Show/Season YYYY/Show - SYYYYEmmdd - Title.ext；同名時加上序號
+pub fn episode_path(base: &Path, info: &serde_json::Value, ext: &str) -> PathBuf {
+    let show = safe_name(&show_name(info));
+    let date = aired_date(info);
+    let title = safe_name(text(info, &["title"]).unwrap_or("unknown"));
+    let dir = base.join(&show).join(format!("Season {}", &date[..4]));
+    get_unique_named_path(&dir, &format!("{} - S{}E{} - {}", show, &date[..4], &date[4..], title), ext, 1)
+}
+
+fn tag(xml: &mut String, name: &str, value: &str) {
+    xml.push_str(&format!("  <{0}>{1}</{0}>\n", name, escape(value)));
+}
+
+/// 單集的 episodedetails .nfo
+pub fn episode_nfo(info: &serde_json::Value) -> String {
+    let date = aired_date(info);
+    let aired = format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..]);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<episodedetails>\n");
+    tag(&mut xml, "title", text(info, &["title"]).unwrap_or("unknown"));
+    tag(&mut xml, "showtitle", &show_name(info));
+    tag(&mut xml, "season", &date[..4]);
+    tag(&mut xml, "episode", &date[4..]);
+    if let Some(plot) = text(info, &["description"]) {
+        tag(&mut xml, "plot", plot);
+    }
+    tag(&mut xml, "aired", &aired);
+    tag(&mut xml, "premiered", &aired);
+    tag(&mut xml, "studio", &show_name(info));
+    if let Some(secs) = info["duration"].as_f64() {
+        // Kodi / Jellyfin 的 runtime 以分鐘為單位
+        tag(&mut xml, "runtime", &((secs / 60.0).round() as u64).to_string());
+    }
+    if let Some(id) = text(info, &["id"]) {
+        let source = text(info, &["extractor_key", "extractor"]).unwrap_or("ytdlp").to_ascii_lowercase();
+        xml.push_str(&format!("  <uniqueid type=\"{}\" default=\"true\">{}</uniqueid>\n", escape(&source), escape(id)));
+    }
+    xml.push_str("</episodedetails>\n");
+    xml
+}
+
+/// 節目資料夾的 tvshow.nfo
+pub fn show_nfo(info: &serde_json::Value) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<tvshow>\n");
+    tag(&mut xml, "title", &show_name(info));
+    tag(&mut xml, "studio", &show_name(info));
+    xml.push_str("</tvshow>\n");
+    xml
+}
+
+/// 下載圖片；副檔名依網址判斷 (jpg / png / webp)
+async fn fetch_image(env: &AppEnv, url: &str) -> Result<(Vec<u8>, &'static str), String> {
+    let client = network::client(&env.settings.network, url)?;
+    let resp = client.get(url).timeout(IMAGE_TIMEOUT).send().await.map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_ascii_lowercase();
+    let ext = if path.ends_with(".png") { "png" } else if path.ends_with(".webp") { "webp" } else { "jpg" };
+    let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
+    Ok((bytes.to_vec(), ext))
+}
+
+fn write_new(path: &Path, contents: &[u8]) -> Result<bool, String> {
+    if path.exists() {
+        return Ok(false);
+    }
+    std::fs::write(path, contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(true)
+}
+
+/// 寫入 .nfo 與圖片，回傳建立的檔案；任何失敗只提出警告
+pub async fn write_sidecars(env: &AppEnv, sink: &dyn EventSink, lang: &str, media: &Path, info: &serde_json::Value) -> Vec<PathBuf> {
+    let mut written = Vec::new();
+    let mut errors = Vec::new();
+    // Show/Season YYYY/檔案 → 節目資料夾為上兩層
+    let show_dir = media.parent().and_then(Path::parent).map(Path::to_path_buf);
+
+    let nfo = media.with_extension("nfo");
+    match std::fs::write(&nfo, episode_nfo(info)) {
+        Ok(()) => written.push(nfo),
+        Err(e) => errors.push(e.to_string()),
+    }
+    if let Some(dir) = &show_dir {
+        let tvshow = dir.join("tvshow.nfo");
+        match write_new(&tvshow, show_nfo(info).as_bytes()) {
+            Ok(true) => written.push(tvshow),
+            Ok(false) => {}
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if let Some(url) = text(info, &["thumbnail"]) {
+        match fetch_image(env, url).await {
+            Ok((bytes, ext)) => {
+                let stem = media.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                let mut targets = vec![media.with_file_name(format!("{}-thumb.{}", stem, ext))];
+                if let Some(dir) = &show_dir {
+                    targets.push(dir.join(format!("poster.{}", ext)));
+                    targets.push(dir.join(format!("fanart.{}", ext)));
+                }
+                for target in targets {
+                    match write_new(&target, &bytes) {
+                        Ok(true) => written.push(target),
+                        Ok(false) => {}
+                        Err(e) => errors.push(e),
+                    }
+                }
+            }
+            Err(e) => errors.push(format!("{}: {}", url, e)),
+        }
+    }
+
+    for e in errors {
+        events::log(sink, get_msg(lang, &format!("⚠️ 媒體庫資訊寫入失敗：{}", e), &format!("⚠️ Could not write media library files: {}", e)));
+    }
+    written
+}
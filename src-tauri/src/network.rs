@@ -0,0 +1,130 @@
+// [2026-10-19 新增] 網路設定：代理伺服器 (HTTP / SOCKS)、各網站的代理覆寫、來源位址、強制 IPv4 / IPv6、地區繞過。
+// 同一份設定同時轉成 yt-dlp 參數與 reqwest client，組件下載、版本檢查與 SponsorBlock 查詢都走相同的路徑。
+use crate::settings::NetworkSettings;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// 各網站覆寫時表示「不使用代理」的值
+pub const DIRECT: &str = "direct";
+
+const PROXY_SCHEMES: [&str; 6] = ["http", "https", "socks4", "socks4a", "socks5", "socks5h"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IpVersion {
+    #[default]
+    Any,
+    V4,
+    V6,
+}
+
+fn host_of(url: &str) -> String {
+    url::Url::parse(url).ok()
+        .and_then(|u| u.host_str().map(|h| h.to_ascii_lowercase()))
+        .unwrap_or_default()
+}
+
+fn validate_proxy(proxy: &str) -> Result<(), String> {
+    if proxy == DIRECT {
+        return Ok(());
+    }
+    let url = url::Url::parse(proxy).map_err(|e| format!("Invalid proxy {}: {}", proxy, e))?;
+    if !PROXY_SCHEMES.contains(&url.scheme()) || url.host_str().is_none() {
+        return Err(format!("Unsupported proxy: {}", proxy));
+    }
+    Ok(())
+}
+
+impl NetworkSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(proxy) = &self.proxy {
+            validate_proxy(proxy)?;
+        }
+        for (site, proxy) in &self.site_proxies {
+            if site.trim().is_empty() {
+                return Err("Empty site in proxy overrides".into());
+            }
+            validate_proxy(proxy)?;
+        }
+        if let Some(addr) = &self.source_address {
+            let ip: IpAddr = addr.parse().map_err(|_| format!("Invalid source address: {}", addr))?;
+            let conflict = matches!((self.ip_version, ip), (IpVersion::V4, IpAddr::V6(_)) | (IpVersion::V6, IpAddr::V4(_)));
+            if conflict {
+                return Err("Source address does not match the forced IP version".into());
+            }
+        }
+        if let Some(country) = &self.geo_bypass_country {
+            if country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(format!("Invalid country code: {}", country));
+            }
+        }
+        Ok(())
+    }
+
+    /// 依網址主機決定使用的代理；Some("direct") 表示該網站不使用代理
+    pub fn proxy_for(&self, url: &str) -> Option<&str> {
+        let host = host_of(url);
+        let host = host.trim_start_matches("www.");
+        let site_override = self.site_proxies.iter()
+            .filter(|(site, _)| host == site.as_str() || host.ends_with(&format!(".{}", site)))
+            .max_by_key(|(site, _)| site.len())
+            .map(|(_, proxy)| proxy.as_str());
+        site_override.or(self.proxy.as_deref()).filter(|p| !p.is_empty())
+    }
+
+    /// 綁定的本機位址；只強制 IP 版本時以未指定位址達成
+    fn local_address(&self) -> Option<IpAddr> {
+        if let Some(ip) = self.source_address.as_deref().and_then(|a| a.parse().ok()) {
+            return Some(ip);
+        }
+        match self.ip_version {
+            IpVersion::Any => None,
+            IpVersion::V4 => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            IpVersion::V6 => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        }
+    }
+}
+
+/// 轉成 yt-dlp 參數 (url 用於選擇各網站的代理)
+pub fn yt_dlp_args(settings: &NetworkSettings, url: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    match settings.proxy_for(url) {
+        // yt-dlp 以空字串表示直接連線
+        Some(DIRECT) => args.extend(["--proxy".to_string(), String::new()]),
+        Some(proxy) => args.extend(["--proxy".to_string(), proxy.to_string()]),
+        None => {}
+    }
+    if let Some(addr) = &settings.source_address {
+        args.extend(["--source-address".to_string(), addr.clone()]);
+    }
+    match settings.ip_version {
+        IpVersion::Any => {}
+        IpVersion::V4 => args.push("--force-ipv4".into()),
+        IpVersion::V6 => args.push("--force-ipv6".into()),
+    }
+    if let Some(country) = &settings.geo_bypass_country {
+        args.extend(["--xff".to_string(), country.to_ascii_uppercase()]);
+    }
+    args
+}
+
+/// 依網路設定建立 reqwest client builder (呼叫端可再加上 user agent / timeout)
+pub fn client_builder(settings: &NetworkSettings, url: &str) -> Result<reqwest::ClientBuilder, String> {
+    let mut builder = reqwest::Client::builder();
+    match settings.proxy_for(url) {
+        Some(DIRECT) => builder = builder.no_proxy(),
+        Some(proxy) => {
+            let proxy = reqwest::Proxy::all(proxy).map_err(|e: reqwest::Error| e.to_string())?;
+            builder = builder.proxy(proxy);
+        }
+        None => {}
+    }
+    if let Some(ip) = settings.local_address() {
+        builder = builder.local_address(ip);
+    }
+    Ok(builder)
+}
+
+pub fn client(settings: &NetworkSettings, url: &str) -> Result<reqwest::Client, String> {
+    client_builder(settings, url)?.build().map_err(|e: reqwest::Error| e.to_string())
+}
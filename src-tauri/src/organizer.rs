@@ -0,0 +1,439 @@
+// [2026-10-19 新增] 下載後的整理規則：依上傳者、網站、長度、模式、播放清單或標題比對，
+// 把完成的檔案搬移 / 重新命名到以範本組成的位置 (例如 Music/{uploader}/{year})。規則依順序比對，第一個符合的規則生效。
+// 目的地已有同名檔案時依規則處理 (加序號、略過或覆寫)。plan 只計算結果不動檔案，供前端對既有紀錄試跑。
+use crate::archival::{sidecar_path, SIDECAR_SUFFIXES};
+use crate::auth::site_key;
+use crate::env::AppEnv;
+use crate::events::{self, get_msg, EventSink};
+use crate::history::{self, HistoryEntry};
+use crate::urls;
+use crate::ytdlp::{sanitize_file_name, DownloadRequest, DownloadResult};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+/// 範本可用的欄位
+const PLACEHOLDERS: [&str; 10] = ["title", "uploader", "playlist", "site", "mode", "quality", "date", "year", "month", "day"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// 加上 _1、_2 等序號
+    #[default]
+    Rename,
+    /// 保留在原位置
+    Skip,
+    Overwrite,
+}
+
+/// 比對條件；未設定的條件不限制，全部符合才算符合
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleConditions {
+    /// 上傳者 (正規表示式)
+    pub uploader: Option<String>,
+    /// 網站 (子網域也符合)
+    pub site: Option<String>,
+    /// video / audio
+    pub mode: Option<String>,
+    /// 長度下限 / 上限 (秒)
+    pub min_duration: Option<f64>,
+    pub max_duration: Option<f64>,
+    /// 播放清單名稱 (正規表示式)
+    pub playlist: Option<String>,
+    pub title_regex: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrganizeRule {
+    pub name: String,
+    pub enabled: bool,
+    pub conditions: RuleConditions,
+    /// 目的地資料夾範本；相對路徑以原下載資料夾為基準，空白表示留在原資料夾 (只重新命名)
+    pub folder: String,
+    /// 檔名範本 (不含副檔名)；未設定時保留原檔名。多檔輸出 (章節曲目) 一律保留原檔名
+    pub file_name: Option<String>,
+    pub on_conflict: ConflictPolicy,
+}
+
+impl Default for OrganizeRule {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            enabled: true,
+            conditions: RuleConditions::default(),
+            folder: String::new(),
+            file_name: None,
+            on_conflict: ConflictPolicy::default(),
+        }
+    }
+}
+
+/// 規則比對與範本使用的下載資訊 (來自剛完成的下載或下載紀錄)
+#[derive(Debug, Clone, Default)]
+pub struct MediaFacts {
+    pub url: String,
+    pub site: String,
+    pub title: String,
+    pub uploader: Option<String>,
+    pub playlist: Option<String>,
+    /// YYYYMMDD
+    pub upload_date: Option<String>,
+    pub mode: String,
+    pub quality: String,
+    pub duration: Option<f64>,
+    pub folder: String,
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveOutcome {
+    Move,
+    /// 目的地已有同名檔案，改用加上序號的名稱
+    Renamed,
+    Overwrite,
+    /// 目的地已有同名檔案，依規則略過
+    Skip,
+    /// 原檔已不存在
+    Missing,
+    /// 已在目的地
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub outcome: MoveOutcome,
+    /// [2026-10-19 新增] 跟著下載檔一起搬移的附屬檔 (.info.json / .description)
+    pub sidecar: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OrganizePlan {
+    /// 符合的規則；None 表示沒有規則符合
+    pub rule: Option<String>,
+    pub moves: Vec<PlannedMove>,
+}
+
+impl MediaFacts {
+    pub fn from_download(req: &DownloadRequest, result: &DownloadResult) -> Self {
+        Self {
+            url: req.url.clone(),
+            site: site_of(&req.url),
+            title: result.title.clone(),
+            uploader: result.uploader.clone(),
+            playlist: result.playlist.clone(),
+            upload_date: result.upload_date.clone(),
+            mode: req.mode.clone(),
+            quality: req.quality.clone(),
+            duration: result.duration,
+            folder: req.path.clone(),
+            files: result.files.clone(),
+        }
+    }
+
+    pub fn from_history(entry: &HistoryEntry) -> Self {
+        Self {
+            url: entry.url.clone(),
+            site: site_of(&entry.url),
+            title: entry.title.clone(),
+            uploader: entry.uploader.clone(),
+            playlist: entry.playlist.clone(),
+            upload_date: entry.upload_date.clone(),
+            mode: entry.mode.clone(),
+            quality: entry.quality.clone(),
+            duration: entry.duration,
+            folder: entry.folder.clone(),
+            files: entry.files.clone(),
+        }
+    }
+
+    fn field(&self, name: &str) -> Option<String> {
+        let date = self.upload_date.as_deref().filter(|d| d.len() == 8 && d.bytes().all(|b| b.is_ascii_digit()));
+        match name {
+            "title" => Some(self.title.clone()),
+            "uploader" => self.uploader.clone(),
+            "playlist" => self.playlist.clone(),
+            "site" => Some(self.site.clone()),
+            "mode" => Some(self.mode.clone()),
+            "quality" => Some(self.quality.clone()),
+            "date" => date.map(str::to_string),
+            "year" => date.map(|d| d[..4].to_string()),
+            "month" => date.map(|d| d[4..6].to_string()),
+            "day" => date.map(|d| d[6..].to_string()),
+            _ => None,
+        }
+    }
+}
+
+fn site_of(url: &str) -> String {
+    urls::normalize(url).map(|n| n.site).unwrap_or_default()
+}
+
+fn regex_matches(pattern: &Option<String>, value: Option<&str>) -> bool {
+    match pattern.as_deref().map(Regex::new) {
+        None => true,
+        Some(Ok(re)) => value.is_some_and(|v| re.is_match(v)),
+        Some(Err(_)) => false,
+    }
+}
+
+impl RuleConditions {
+    pub fn validate(&self) -> Result<(), String> {
+        for (label, pattern) in [("uploader", &self.uploader), ("playlist", &self.playlist), ("title", &self.title_regex)] {
+            if let Some(pattern) = pattern {
+                Regex::new(pattern).map_err(|e| format!("Invalid {} pattern: {}", label, e))?;
+            }
+        }
+        if let Some(site) = &self.site {
+            site_key(site)?;
+        }
+        if let Some(mode) = self.mode.as_deref().filter(|m| !matches!(*m, "video" | "audio")) {
+            return Err(format!("Invalid mode: {}", mode));
+        }
+        if let (Some(min), Some(max)) = (self.min_duration, self.max_duration) {
+            if min > max {
+                return Err("Minimum duration is longer than the maximum".into());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, facts: &MediaFacts) -> bool {
+        if let Some(site) = self.site.as_deref().and_then(|s| site_key(s).ok()) {
+            if facts.site != site && !facts.site.ends_with(&format!(".{}", site)) {
+                return false;
+            }
+        }
+        if self.mode.as_deref().is_some_and(|m| !m.eq_ignore_ascii_case(&facts.mode)) {
+            return false;
+        }
+        if self.min_duration.is_some_and(|min| facts.duration.is_none_or(|d| d < min))
+            || self.max_duration.is_some_and(|max| facts.duration.is_none_or(|d| d > max))
+        {
+            return false;
+        }
+        regex_matches(&self.uploader, facts.uploader.as_deref())
+            && regex_matches(&self.playlist, facts.playlist.as_deref())
+            && regex_matches(&self.title_regex, Some(&facts.title))
+    }
+}
+
+/// 檢查範本的欄位名稱與括號
+fn check_template(template: &str) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            return Err(format!("Unclosed placeholder in template: {}", template));
+        };
+        let name = &rest[start + 1..start + len];
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(format!("Unknown placeholder {{{}}} (available: {})", name, PLACEHOLDERS.join(", ")));
+        }
+        rest = &rest[start + len + 1..];
+    }
+    Ok(())
+}
+
+/// 代入欄位；每個值都清除路徑字元，沒有值時使用 "Unknown"
+pub fn render(template: &str, facts: &MediaFacts) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else { break };
+        let name = &rest[start + 1..start + len];
+        let value = facts.field(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).unwrap_or_else(|| "Unknown".into());
+        let value = sanitize_file_name(&value.replace(['\r', '\n'], " "));
+        // 只有點的值 (例如 "..") 會變成上層目錄
+        out.push_str(if value.chars().all(|c| c == '.') { "_" } else { &value });
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+impl OrganizeRule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Rule name cannot be empty".into());
+        }
+        self.conditions.validate()?;
+        check_template(&self.folder)?;
+        if Path::new(&self.folder).components().any(|c| c == Component::ParentDir) {
+            return Err(format!("Folder template cannot contain '..': {}", self.folder));
+        }
+        if let Some(name) = &self.file_name {
+            check_template(name)?;
+            if name.trim().is_empty() || name.contains(['/', '\\']) {
+                return Err(format!("Invalid file name template: {}", name));
+            }
+        }
+        if self.folder.trim().is_empty() && self.file_name.is_none() {
+            return Err(format!("Rule {} has neither a folder nor a file name", self.name));
+        }
+        Ok(())
+    }
+}
+
+/// 加上序號直到不與既有檔案或同批計畫的目的地衝突
+fn unique_target(target: &Path, taken: &HashSet<PathBuf>) -> PathBuf {
+    let stem = target.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = target.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    let mut counter = 1;
+    loop {
+        let candidate = target.with_file_name(format!("{}_{}{}", stem, counter, ext));
+        if !candidate.exists() && !taken.contains(&candidate) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// 計算整理結果 (不動檔案)
+pub fn plan(rules: &[OrganizeRule], facts: &MediaFacts) -> OrganizePlan {
+    let Some(rule) = rules.iter().find(|r| r.enabled && r.conditions.matches(facts)) else {
+        return OrganizePlan::default();
+    };
+    let folder = render(rule.folder.trim(), facts);
+    let folder = Path::new(&facts.folder).join(folder);
+    let mut taken = HashSet::new();
+    let mut moves = Vec::new();
+    for from in &facts.files {
+        let file_name = match (&rule.file_name, facts.files.len()) {
+            (Some(template), 1) => {
+                let ext = from.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+                format!("{}{}", render(template, facts), ext)
+            }
+            _ => from.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        };
+        let target = folder.join(file_name);
+        let outcome = if !from.exists() {
+            MoveOutcome::Missing
+        } else if target == *from {
+            MoveOutcome::Unchanged
+        } else if target.exists() || taken.contains(&target) {
+            match rule.on_conflict {
+                ConflictPolicy::Rename => MoveOutcome::Renamed,
+                ConflictPolicy::Skip => MoveOutcome::Skip,
+                ConflictPolicy::Overwrite => MoveOutcome::Overwrite,
+            }
+        } else {
+            MoveOutcome::Move
+        };
+        let to = if outcome == MoveOutcome::Renamed { unique_target(&target, &taken) } else { target };
+        taken.insert(to.clone());
+        moves.push(PlannedMove { from: from.clone(), to: to.clone(), outcome, sidecar: false });
+        if matches!(outcome, MoveOutcome::Move | MoveOutcome::Renamed | MoveOutcome::Overwrite) {
+            // 附屬檔改用與下載檔相同的新檔名
+            for suffix in SIDECAR_SUFFIXES {
+                let side = sidecar_path(from, suffix);
+                if side.is_file() {
+                    let side_to = sidecar_path(&to, suffix);
+                    let side_outcome = if side_to.exists() { MoveOutcome::Overwrite } else { MoveOutcome::Move };
+                    moves.push(PlannedMove { from: side, to: side_to, outcome: side_outcome, sidecar: true });
+                }
+            }
+        }
+    }
+    OrganizePlan { rule: Some(rule.name.clone()), moves }
+}
+
+/// 對下載紀錄中的一筆 (以紀錄的 id 指定) 試跑整理規則；rules 為 None 時使用已儲存的規則
+pub fn preview(env: &AppEnv, id: &str, rules: Option<&[OrganizeRule]>) -> Result<OrganizePlan, String> {
+    let entries = history::load(&env.history_path())?;
+    let entry = entries.iter().find(|e| e.id == id).ok_or_else(|| format!("History entry not found: {}", id))?;
+    let rules = match rules {
+        Some(rules) => {
+            rules.iter().try_for_each(OrganizeRule::validate)?;
+            rules
+        }
+        None => &env.settings.organizer_rules,
+    };
+    Ok(plan(rules, &MediaFacts::from_history(entry)))
+}
+
+/// 搬移單一檔案；跨磁碟時改為複製後刪除
+fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to).map_err(|e| format!("Cannot move {}: {}", from.display(), e))?;
+    std::fs::remove_file(from).map_err(|e| e.to_string())
+}
+
+/// 依計畫搬移，回傳整理後的檔案清單 (不含附屬檔；未搬移或搬移失敗的檔案保留原路徑) 與錯誤
+pub fn execute(plan: &OrganizePlan) -> (Vec<PathBuf>, Vec<String>) {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    // 附屬檔排在所屬的下載檔之後，下載檔搬移成功才搬移
+    let mut moved = false;
+    for step in &plan.moves {
+        if step.sidecar {
+            if moved {
+                if let Err(e) = move_file(&step.from, &step.to) {
+                    errors.push(e);
+                }
+            }
+            continue;
+        }
+        moved = false;
+        match step.outcome {
+            MoveOutcome::Move | MoveOutcome::Renamed | MoveOutcome::Overwrite => match move_file(&step.from, &step.to) {
+                Ok(()) => {
+                    moved = true;
+                    files.push(step.to.clone());
+                }
+                Err(e) => {
+                    errors.push(e);
+                    files.push(step.from.clone());
+                }
+            },
+            MoveOutcome::Unchanged => files.push(step.to.clone()),
+            MoveOutcome::Skip | MoveOutcome::Missing => files.push(step.from.clone()),
+        }
+    }
+    (files, errors)
+}
+
+/// 下載完成後套用整理規則並更新結果中的檔案路徑；失敗只提出警告，不影響下載結果 (媒體庫輸出不套用)
+pub fn apply(rules: &[OrganizeRule], sink: &dyn EventSink, req: &DownloadRequest, result: &mut DownloadResult) {
+    // 媒體庫輸出的位置由 medialib 決定，且 .nfo / 縮圖需與檔案在同一個資料夾
+    if req.options.media_library {
+        return;
+    }
+    let lang = req.lang.as_str();
+    let plan = plan(rules, &MediaFacts::from_download(req, result));
+    let Some(rule) = plan.rule.as_deref() else { return };
+    let (files, errors) = execute(&plan);
+    for step in plan.moves.iter().filter(|s| s.sidecar && s.to.exists()) {
+        if result.info_file.as_ref() == Some(&step.from) {
+            result.info_file = Some(step.to.clone());
+        }
+    }
+    for step in plan.moves.iter().filter(|s| s.to != s.from && files.contains(&s.to)) {
+        events::log(sink, get_msg(
+            lang,
+            &format!("📂 [{}] 已移動到：{}", rule, step.to.display()),
+            &format!("📂 [{}] Moved to: {}", rule, step.to.display()),
+        ));
+    }
+    for step in plan.moves.iter().filter(|s| s.outcome == MoveOutcome::Skip) {
+        events::log(sink, get_msg(
+            lang,
+            &format!("⚠️ [{}] 目的地已有同名檔案，保留原位置：{}", rule, step.to.display()),
+            &format!("⚠️ [{}] A file already exists at the destination, left in place: {}", rule, step.to.display()),
+        ));
+    }
+    for e in errors {
+        events::log(sink, get_msg(lang, &format!("⚠️ 整理檔案失敗：{}", e), &format!("⚠️ Could not organize files: {}", e)));
+    }
+    result.files = files;
+}
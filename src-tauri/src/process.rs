@@ -0,0 +1,267 @@
+// [2026-10-19 新增] 非阻塞的外部程式管理 (tokio::process)
+// 過去在 async 指令中直接呼叫 std::process::Command::output() 與阻塞式的 lines()，會卡住執行緒；
+// 而且 stderr 要等 stdout 關閉後才讀，子程序輸出大量錯誤時會塞滿管道造成死結。
+// 這裡統一建立指令、同時讀取 stdout / stderr，並為解析類的呼叫加上逾時。
+use std::ffi::OsStr;
+use std::future::Future;
+use std::process::{ExitStatus, Output, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// 失敗時保留的 stderr 行數上限
+const STDERR_TAIL: usize = 50;
+
+/// [2026-10-19 新增] 要求停止後，等待子程序 (含 yt-dlp 啟動的 ffmpeg) 自行收尾的時間；逾時才強制終止
+const STOP_GRACE: Duration = Duration::from_secs(20);
+
+/// [2026-10-19 新增] 能否要求子程序自行收尾後結束 (SIGINT)；Windows 的 CTRL_BREAK 會讓 yt-dlp 立即結束，不算在內
+pub const SOFT_STOP: bool = cfg!(unix);
+
+/// 建立不會彈出主控台視窗、且被丟棄時會結束子程序的指令
+pub fn command(program: impl AsRef<OsStr>) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.kill_on_drop(true);
+    cmd.stdin(Stdio::null());
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000);
+    cmd
+}
+
+/// 執行並收集全部輸出；超過 timeout 時終止子程序並回傳錯誤
+pub async fn run_capture(mut cmd: Command, timeout: Duration) -> Result<Output, String> {
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    let child = cmd.spawn().map_err(|e| e.to_string())?;
+    // wait_with_output 會同時讀取兩個管道；逾時時 future 被丟棄，kill_on_drop 會結束子程序
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => output.map_err(|e| e.to_string()),
+        Err(_) => Err(format!("Timed out after {}s", timeout.as_secs())),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamLine {
+    Stdout(String),
+    Stderr(String),
+}
+
+#[derive(Debug)]
+pub struct ProcessOutcome {
+    pub status: ExitStatus,
+    /// 最後幾行 stderr，用於組成錯誤訊息
+    pub stderr_tail: Vec<String>,
+    /// 是否因為 StopSignal 而被終止
+    pub stopped: bool,
+}
+
+/// [2026-10-19 新增] 使用者要求停止任務的訊號 (可複製，任一份呼叫 stop 即生效)
+#[derive(Debug, Clone)]
+pub struct StopSignal(Arc<watch::Sender<bool>>);
+
+impl Default for StopSignal {
+    fn default() -> Self {
+        Self(Arc::new(watch::channel(false).0))
+    }
+}
+
+impl StopSignal {
+    pub fn stop(&self) {
+        self.0.send_replace(true);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// 等到 stop 被呼叫為止
+    pub fn stopped(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut rx = self.0.subscribe();
+        async move {
+            let _ = rx.wait_for(|stopped| *stopped).await;
+        }
+    }
+}
+
+impl ProcessOutcome {
+    pub fn error_message(&self) -> String {
+        self.stderr_tail.join("\n")
+    }
+}
+
+/// 啟動子程序並逐行回呼 stdout / stderr (兩者同時讀取，不會互相阻塞)
+pub async fn run_streaming<F>(cmd: Command, on_line: F) -> Result<ProcessOutcome, String>
+where
+    F: FnMut(StreamLine),
+{
+    run_streaming_until(cmd, std::future::pending(), on_line).await
+}
+
+/// 同 run_streaming，但 stop 完成時結束子程序 (ProcessOutcome.stopped 為 true)。
+/// [2026-10-19 修改] 子程序放在獨立的程序群組：停止時對整個群組送出 SIGINT (Windows 為 CTRL_BREAK)，
+/// 讓 yt-dlp 與 ffmpeg 寫完各自的檔案，逾時才強制終止；回傳前會等到群組內的程序都已結束，呼叫端可以安全地處理 .part 檔。
+pub async fn run_streaming_until<S, F>(cmd: Command, stop: S, on_line: F) -> Result<ProcessOutcome, String>
+where
+    S: Future<Output = ()>,
+    F: FnMut(StreamLine),
+{
+    run_streaming_with_grace(cmd, stop, STOP_GRACE, on_line).await
+}
+
+/// [2026-10-19 新增] 同 run_streaming，但超過 timeout 時立即強制終止整個程序樹 (ProcessOutcome.stopped 為 true)
+pub async fn run_streaming_timeout<F>(cmd: Command, timeout: Duration, on_line: F) -> Result<ProcessOutcome, String>
+where
+    F: FnMut(StreamLine),
+{
+    run_streaming_with_grace(cmd, tokio::time::sleep(timeout), Duration::ZERO, on_line).await
+}
+
+/// grace 為要求停止後等待子程序自行結束的時間；為 0 時直接強制終止
+async fn run_streaming_with_grace<S, F>(mut cmd: Command, stop: S, grace_period: Duration, mut on_line: F) -> Result<ProcessOutcome, String>
+where
+    S: Future<Output = ()>,
+    F: FnMut(StreamLine),
+{
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    #[cfg(unix)]
+    cmd.process_group(0);
+    // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP：群組 id 即為子程序的 pid，CTRL_BREAK 只送到這個群組
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000 | 0x00000200);
+    let mut child = cmd.spawn().map_err(|e| e.to_string())?;
+    let pid = child.id();
+
+    let stdout = child.stdout.take().ok_or("No Stdout")?;
+    let stderr = child.stderr.take().ok_or("No Stderr")?;
+    let mut out_lines = BufReader::new(stdout).lines();
+    let mut err_lines = BufReader::new(stderr).lines();
+    let mut out_open = true;
+    let mut err_open = true;
+    let mut stderr_tail = Vec::new();
+    // 要求停止後的強制終止期限
+    let mut deadline = None;
+    let mut killed = false;
+    let grace = tokio::time::sleep(grace_period);
+    tokio::pin!(stop, grace);
+
+    while out_open || err_open {
+        tokio::select! {
+            _ = &mut stop, if deadline.is_none() => {
+                // 子程序結束後管道會關閉，迴圈自然結束；無法送出中斷時直接強制終止
+                if grace_period.is_zero() || !interrupt_tree(pid) {
+                    kill_tree(&mut child, pid).await;
+                    killed = true;
+                }
+                let at = Instant::now() + grace_period;
+                grace.as_mut().reset(at);
+                deadline = Some(at);
+            }
+            _ = &mut grace, if deadline.is_some() && !killed => {
+                kill_tree(&mut child, pid).await;
+                killed = true;
+            }
+            line = out_lines.next_line(), if out_open => match line {
+                Ok(Some(line)) => on_line(StreamLine::Stdout(line)),
+                _ => out_open = false,
+            },
+            line = err_lines.next_line(), if err_open => match line {
+                Ok(Some(line)) => {
+                    if stderr_tail.len() == STDERR_TAIL { stderr_tail.remove(0); }
+                    stderr_tail.push(line.clone());
+                    on_line(StreamLine::Stderr(line));
+                }
+                _ => err_open = false,
+            },
+        }
+    }
+
+    let status = match deadline {
+        Some(at) => {
+            let status = match tokio::time::timeout_at(at, child.wait()).await {
+                Ok(status) => status,
+                Err(_) => {
+                    kill_tree(&mut child, pid).await;
+                    child.wait().await
+                }
+            };
+            // yt-dlp 結束後，它啟動的 ffmpeg 可能還在寫檔
+            wait_tree(pid, at).await;
+            status
+        }
+        None => child.wait().await,
+    };
+    let status = status.map_err(|e| e.to_string())?;
+    Ok(ProcessOutcome { status, stderr_tail, stopped: deadline.is_some() })
+}
+
+/// 對整個程序群組送出訊號 (群組 id 即為子程序的 pid)
+#[cfg(unix)]
+fn signal_group(pid: u32, signal: libc::c_int) -> bool {
+    // SAFETY: kill 只讀取參數；負的 pid 代表程序群組
+    unsafe { libc::kill(-(pid as libc::pid_t), signal) == 0 }
+}
+
+/// 要求子程序與其子孫自行結束；回傳是否成功送出
+#[cfg(unix)]
+fn interrupt_tree(pid: Option<u32>) -> bool {
+    pid.is_some_and(|pid| signal_group(pid, libc::SIGINT))
+}
+
+#[cfg(unix)]
+async fn kill_tree(child: &mut tokio::process::Child, pid: Option<u32>) {
+    if let Some(pid) = pid {
+        signal_group(pid, libc::SIGKILL);
+    }
+    let _ = child.start_kill();
+}
+
+/// 等到群組內的程序都結束；超過期限時強制終止剩下的程序
+#[cfg(unix)]
+async fn wait_tree(pid: Option<u32>, deadline: Instant) {
+    let Some(pid) = pid else { return };
+    // 訊號 0 只檢查群組是否還有程序
+    while signal_group(pid, 0) {
+        if Instant::now() >= deadline {
+            signal_group(pid, libc::SIGKILL);
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+/// [2026-10-19 修改] 對子程序的群組送出 CTRL_BREAK，ffmpeg 收到後會寫完檔尾再結束。
+/// GUI 程式本身沒有主控台，先附加到子程序 (CREATE_NO_WINDOW 建立的隱藏主控台) 才能送出，送出後立即卸離；
+/// 本程式不在該群組中，不會收到這個事件。
+#[cfg(windows)]
+fn interrupt_tree(pid: Option<u32>) -> bool {
+    use windows_sys::Win32::System::Console::{AttachConsole, FreeConsole, GenerateConsoleCtrlEvent, CTRL_BREAK_EVENT};
+    let Some(pid) = pid else { return false };
+    // SAFETY: 只傳入數值參數
+    unsafe {
+        let attached = AttachConsole(pid) != 0;
+        let sent = GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid) != 0;
+        if attached {
+            FreeConsole();
+        }
+        sent
+    }
+}
+
+// [2026-10-19 修正] 以 tokio 的 Command 等待 taskkill，不阻塞執行緒
+#[cfg(not(unix))]
+async fn kill_tree(child: &mut tokio::process::Child, pid: Option<u32>) {
+    if let Some(pid) = pid {
+        let mut taskkill = command("taskkill");
+        taskkill.args(["/T", "/F", "/PID", &pid.to_string()]).stdout(Stdio::null()).stderr(Stdio::null());
+        let _ = taskkill.status().await;
+    }
+    let _ = child.start_kill();
+}
+
+/// CTRL_BREAK 送到整個群組；逾時後的 taskkill /T 會一併結束子孫程序並等待其結束
+#[cfg(not(unix))]
+async fn wait_tree(_pid: Option<u32>, _deadline: Instant) {}
@@ -0,0 +1,377 @@
+// [2026-10-19 新增] 排程下載佇列：任務可指定「在某時間之後開始」或「只在 01:00–06:00 之間下載」。
+// 佇列存放於使用者資料目錄的 queue.json，重新啟動後繼續；一次只執行一個任務 (與手動下載共用 DOWNLOAD_LOCK)。
+// 時段結束時執行中的任務會被停止 (保留 .part)，狀態標為「因排程暫停」，與使用者手動暫停分開，時段再次開始時自動續傳。
+use crate::bandwidth::{parse_clock, window_contains};
+use crate::env::AppEnv;
+use crate::events::{self, EventSink};
+use crate::hooks::PendingHooks;
+use crate::process::StopSignal;
+use crate::settings::write_json_atomic;
+use crate::urls;
+use crate::ytdlp::{self, DownloadRequest, DownloadResult};
+use chrono::{DateTime, Days, Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const EVT_QUEUE: &str = "queue-updated";
+
+/// 執行中的任務多久檢查一次時段是否結束
+const WINDOW_CHECK: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeWindow {
+    /// 本地時間 "HH:MM"
+    pub start: String,
+    /// 早於 start 表示跨午夜
+    pub end: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobSchedule {
+    /// 最早開始時間 (Unix 秒)
+    pub start_at: Option<i64>,
+    /// 只在此時段內下載
+    pub window: Option<TimeWindow>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// 等待開始時間 / 時段
+    Scheduled,
+    Running,
+    /// 下載途中時段結束，等下一個時段自動續傳
+    PausedBySchedule,
+    /// 使用者暫停，需手動繼續
+    PausedByUser,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedJob {
+    pub id: String,
+    pub request: DownloadRequest,
+    pub schedule: JobSchedule,
+    pub state: JobState,
+    pub added_at: i64,
+    /// 下次可執行的時間 (Unix 秒)；暫停、完成或失敗時為 None
+    pub next_run: Option<i64>,
+    pub last_error: Option<String>,
+    pub result: Option<DownloadResult>,
+}
+
+impl TimeWindow {
+    fn validate(&self) -> Result<(), String> {
+        parse_clock(&self.start)?;
+        parse_clock(&self.end)?;
+        Ok(())
+    }
+
+    fn contains(&self, t: &DateTime<Local>) -> bool {
+        match (parse_clock(&self.start), parse_clock(&self.end)) {
+            (Ok(start), Ok(end)) => window_contains(start, end, t.time()),
+            _ => true,
+        }
+    }
+
+    /// t 之後 (含) 第一個在時段內的時間
+    fn next_open(&self, t: DateTime<Local>) -> DateTime<Local> {
+        if self.contains(&t) {
+            return t;
+        }
+        let Ok(start) = parse_clock(&self.start) else { return t };
+        let mut date = t.date_naive();
+        for _ in 0..2 {
+            let candidate = to_local(date.and_time(start));
+            if candidate > t {
+                return candidate;
+            }
+            date = date.checked_add_days(Days::new(1)).unwrap_or(date);
+        }
+        t
+    }
+}
+
+/// 夏令時間切換造成的不存在時間，往後一小時
+fn to_local(naive: NaiveDateTime) -> DateTime<Local> {
+    Local.from_local_datetime(&naive).earliest()
+        .or_else(|| Local.from_local_datetime(&(naive + chrono::Duration::hours(1))).earliest())
+        .unwrap_or_else(Local::now)
+}
+
+impl QueuedJob {
+    /// 依目前時間計算下次執行時間
+    pub fn compute_next_run(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        if !matches!(self.state, JobState::Scheduled | JobState::PausedBySchedule) {
+            return None;
+        }
+        let mut t = now;
+        if let Some(start_at) = self.schedule.start_at.and_then(|s| Local.timestamp_opt(s, 0).single()) {
+            t = t.max(start_at);
+        }
+        Some(match &self.schedule.window {
+            Some(window) => window.next_open(t),
+            None => t,
+        })
+    }
+
+    fn is_due(&self, now: DateTime<Local>) -> bool {
+        self.compute_next_run(now).is_some_and(|t| t <= now)
+    }
+}
+
+struct ActiveJob {
+    id: String,
+    stop: StopSignal,
+}
+
+pub struct DownloadQueue {
+    path: PathBuf,
+    jobs: Mutex<Vec<QueuedJob>>,
+    active: Mutex<Option<ActiveJob>>,
+    counter: AtomicU64,
+}
+
+impl DownloadQueue {
+    /// 讀取佇列；上次關閉時仍在執行的任務改回排程中，啟動後自動續傳
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut jobs: Vec<QueuedJob> = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|txt| serde_json::from_str(&txt).ok())
+            .unwrap_or_default();
+        for job in &mut jobs {
+            if job.state == JobState::Running {
+                job.state = JobState::Scheduled;
+            }
+        }
+        Self { path, jobs: Mutex::new(jobs), active: Mutex::new(None), counter: AtomicU64::new(0) }
+    }
+
+    fn lock_jobs(&self) -> std::sync::MutexGuard<'_, Vec<QueuedJob>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_active(&self) -> std::sync::MutexGuard<'_, Option<ActiveJob>> {
+        self.active.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, jobs: &mut [QueuedJob]) -> Result<(), String> {
+        let now = Local::now();
+        for job in jobs.iter_mut() {
+            job.next_run = job.compute_next_run(now).map(|t| t.timestamp());
+        }
+        write_json_atomic(&self.path, jobs)
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut Vec<QueuedJob>) -> Result<T, String>) -> Result<T, String> {
+        let mut jobs = self.lock_jobs();
+        let value = f(&mut jobs)?;
+        self.save(&mut jobs)?;
+        Ok(value)
+    }
+
+    fn new_id(&self) -> String {
+        let millis = Local::now().timestamp_millis();
+        format!("{:x}-{}", millis, self.counter.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn new_job(&self, request: DownloadRequest, schedule: JobSchedule) -> Result<QueuedJob, String> {
+        urls::normalize(&request.url)?;
+        if request.quality.is_empty() {
+            return Err("Quality or format not selected".into());
+        }
+        if let Some(window) = &schedule.window {
+            window.validate()?;
+        }
+        Ok(QueuedJob {
+            id: self.new_id(),
+            request,
+            schedule,
+            state: JobState::Scheduled,
+            added_at: Local::now().timestamp(),
+            next_run: None,
+            last_error: None,
+            result: None,
+        })
+    }
+
+    pub fn enqueue(&self, request: DownloadRequest, schedule: JobSchedule) -> Result<QueuedJob, String> {
+        let job = self.new_job(request, schedule)?;
+        let id = job.id.clone();
+        self.update(|jobs| {
+            jobs.push(job);
+            Ok(())
+        })?;
+        self.get(&id).ok_or_else(|| "Job not found".to_string())
+    }
+
+    /// [2026-10-19 新增] 一次加入多個任務 (只寫入一次檔案)；任一個不合法時全部不加入
+    pub fn enqueue_all(&self, requests: Vec<DownloadRequest>, schedule: JobSchedule) -> Result<Vec<QueuedJob>, String> {
+        let new_jobs = requests.into_iter()
+            .map(|request| self.new_job(request, schedule.clone()))
+            .collect::<Result<Vec<_>, String>>()?;
+        let ids: Vec<String> = new_jobs.iter().map(|j| j.id.clone()).collect();
+        self.update(|jobs| {
+            jobs.extend(new_jobs);
+            Ok(())
+        })?;
+        let jobs = self.list();
+        Ok(ids.iter().filter_map(|id| jobs.iter().find(|j| &j.id == id).cloned()).collect())
+    }
+
+    /// [2026-10-19 新增] 尚未完成 (排程中 / 執行中 / 暫停) 的任務是否已有此網址 (正規化後比對)
+    pub fn has_pending_url(&self, url: &str) -> bool {
+        let Ok(target) = urls::normalize(url) else { return false };
+        self.lock_jobs().iter()
+            .filter(|j| !matches!(j.state, JobState::Completed | JobState::Failed))
+            .any(|j| urls::normalize(&j.request.url).is_ok_and(|n| n.url == target.url))
+    }
+
+    /// 目前的佇列 (含重新計算的下次執行時間)
+    pub fn list(&self) -> Vec<QueuedJob> {
+        let now = Local::now();
+        let mut jobs = self.lock_jobs().clone();
+        for job in &mut jobs {
+            job.next_run = job.compute_next_run(now).map(|t| t.timestamp());
+        }
+        jobs
+    }
+
+    pub fn get(&self, id: &str) -> Option<QueuedJob> {
+        self.list().into_iter().find(|j| j.id == id)
+    }
+
+    fn stop_if_active(&self, id: &str) {
+        if let Some(active) = self.lock_active().as_ref().filter(|a| a.id == id) {
+            active.stop.stop();
+        }
+    }
+
+    /// 使用者暫停 (執行中的任務會被停止，保留 .part 供續傳)
+    pub fn pause(&self, id: &str) -> Result<(), String> {
+        self.update(|jobs| {
+            let job = jobs.iter_mut().find(|j| j.id == id).ok_or("Job not found")?;
+            if matches!(job.state, JobState::Completed | JobState::Failed) {
+                return Err("Job already finished".into());
+            }
+            job.state = JobState::PausedByUser;
+            Ok(())
+        })?;
+        self.stop_if_active(id);
+        Ok(())
+    }
+
+    /// 繼續使用者暫停或失敗的任務
+    pub fn resume(&self, id: &str) -> Result<(), String> {
+        self.update(|jobs| {
+            let job = jobs.iter_mut().find(|j| j.id == id).ok_or("Job not found")?;
+            if matches!(job.state, JobState::PausedByUser | JobState::Failed) {
+                job.state = JobState::Scheduled;
+                job.last_error = None;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), String> {
+        self.stop_if_active(id);
+        self.update(|jobs| {
+            jobs.retain(|j| j.id != id);
+            Ok(())
+        })
+    }
+
+    pub fn has_due(&self) -> bool {
+        let now = Local::now();
+        self.lock_jobs().iter().any(|j| j.is_due(now))
+    }
+
+    /// 取出最早到期的任務並標為執行中
+    fn take_due(&self) -> Option<QueuedJob> {
+        let now = Local::now();
+        let mut jobs = self.lock_jobs();
+        let job = jobs.iter_mut()
+            .filter(|j| j.is_due(now))
+            .min_by_key(|j| (j.compute_next_run(now), j.added_at))?;
+        job.state = JobState::Running;
+        let job = job.clone();
+        let _ = self.save(&mut jobs);
+        Some(job)
+    }
+
+    fn finish(&self, id: &str, f: impl FnOnce(&mut QueuedJob)) {
+        let _ = self.update(|jobs| {
+            if let Some(job) = jobs.iter_mut().find(|j| j.id == id) {
+                f(job);
+            }
+            Ok(())
+        });
+    }
+
+    /// 執行一個到期的任務直到結束 / 暫停 (含掛鉤)；沒有到期的任務時回傳 false。
+    /// 呼叫端負責取得下載鎖，確保同時只有一個下載。
+    pub async fn run_next(&self, env: &AppEnv, sink: &dyn EventSink) -> bool {
+        match self.run_next_until(env, sink, StopSignal::default()).await {
+            Some(hooks) => {
+                hooks.run(env, sink).await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 同 run_next，但由呼叫端提供停止訊號 (例如「停止下載」按鈕)；
+    /// [2026-10-19 修改] 任務的掛鉤不在這裡執行，回傳給呼叫端在釋放下載鎖之後執行。沒有到期的任務時回傳 None
+    pub async fn run_next_until(&self, env: &AppEnv, sink: &dyn EventSink, stop: StopSignal) -> Option<PendingHooks> {
+        let job = self.take_due()?;
+        *self.lock_active() = Some(ActiveJob { id: job.id.clone(), stop: stop.clone() });
+        events::emit(sink, EVT_QUEUE, self.list());
+
+        let window = job.schedule.window.clone();
+        let download = ytdlp::download_deferring_hooks(env, sink, &job.request, &stop);
+        tokio::pin!(download);
+        let mut ticker = tokio::time::interval(WINDOW_CHECK);
+        let mut window_closed = false;
+        let (result, hooks) = loop {
+            tokio::select! {
+                done = &mut download => break done,
+                _ = ticker.tick(), if !window_closed => {
+                    if window.as_ref().is_some_and(|w| !w.contains(&Local::now())) {
+                        window_closed = true;
+                        stop.stop();
+                    }
+                }
+            }
+        };
+        *self.lock_active() = None;
+
+        self.finish(&job.id, |j| match result {
+            // [2026-10-19 修正] 直播被暫停或時段結束時會封裝已錄到的內容並回傳成功，任務仍應維持暫停
+            Ok(result) if result.stopped && j.state == JobState::PausedByUser => j.result = Some(result),
+            Ok(result) if result.stopped && window_closed => {
+                j.state = JobState::PausedBySchedule;
+                j.result = Some(result);
+            }
+            Ok(result) => {
+                j.state = JobState::Completed;
+                j.result = Some(result);
+            }
+            // 使用者暫停時狀態已在 pause() 設定
+            Err(_) if j.state == JobState::PausedByUser => {}
+            Err(_) if window_closed => j.state = JobState::PausedBySchedule,
+            Err(e) => {
+                j.state = JobState::Failed;
+                j.last_error = Some(e);
+            }
+        });
+        events::emit(sink, EVT_QUEUE, self.list());
+        Some(hooks)
+    }
+}
+
@@ -0,0 +1,122 @@
+// [2026-10-19 新增] 儲存資料夾檢查：過去直接相信前端傳入的路徑，不存在、唯讀或已中斷的網路 / 外接磁碟要到 yt-dlp 裡才失敗。
+// 下載前先確認資料夾存在 (設定允許時自動建立)，並以探測檔實際寫入一次；外接磁碟或網路磁碟不在時不建立資料夾
+// (避免寫到系統磁碟上的空掛載點)。目標無法使用時改用設定中的預設資料夾，並回報原因。
+use crate::settings::DownloadSettings;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// 外接 / 網路磁碟常見的掛載位置 (其下一層或兩層為磁碟本身)
+const MOUNT_PARENTS: [&str; 4] = ["/media", "/run/media", "/mnt", "/Volumes"];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SavePath {
+    /// 實際使用的資料夾
+    pub path: String,
+    /// 前端要求的資料夾
+    pub requested: String,
+    /// 資料夾是這次建立的
+    pub created: bool,
+    /// 改用了預設資料夾
+    pub fallback: bool,
+    /// 要求的資料夾無法使用的原因
+    pub reason: Option<String>,
+}
+
+/// 路徑所在的外接 / 網路磁碟根目錄 (Windows 磁碟機代號或 UNC 共用、Unix 的掛載位置)；一般路徑回傳 None
+pub fn volume_root(path: &Path) -> Option<PathBuf> {
+    if let Some(Component::Prefix(prefix)) = path.components().next() {
+        let mut root = PathBuf::from(prefix.as_os_str());
+        root.push(Component::RootDir.as_os_str());
+        return Some(root);
+    }
+    for parent in MOUNT_PARENTS {
+        let Ok(rest) = path.strip_prefix(parent) else { continue };
+        let mut parts = rest.components();
+        // /media/<使用者>/<磁碟> 與 /run/media/<使用者>/<磁碟>；/mnt/<磁碟> 與 /Volumes/<磁碟>
+        let depth = if matches!(parent, "/media" | "/run/media") { 2 } else { 1 };
+        let mut root = PathBuf::from(parent);
+        for _ in 0..depth {
+            root.push(parts.next()?.as_os_str());
+        }
+        return Some(root);
+    }
+    None
+}
+
+/// 掛載點仍然存在。Unix 上與上層位於同一個檔案系統、且是空資料夾時視為未掛載；
+/// [2026-10-19 修正] 有內容的一般資料夾 (例如根檔案系統上的 /mnt/data) 照常使用
+pub fn volume_available(root: &Path) -> bool {
+    if !root.is_dir() {
+        return false;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let parent = root.parent().unwrap_or(Path::new("/"));
+        let mounted = match (std::fs::metadata(root), std::fs::metadata(parent)) {
+            (Ok(r), Ok(p)) => r.dev() != p.dev(),
+            _ => false,
+        };
+        mounted || std::fs::read_dir(root).is_ok_and(|mut entries| entries.next().is_some())
+    }
+    #[cfg(not(unix))]
+    true
+}
+
+/// 實際寫入並刪除一個探測檔
+fn probe_writable(dir: &Path) -> Result<(), String> {
+    let probe = dir.join(format!(".cyber-ytdl-probe-{}", std::process::id()));
+    let result = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .and_then(|mut f| f.write_all(b"ok"));
+    let _ = std::fs::remove_file(&probe);
+    result.map_err(|e| format!("Folder is not writable: {}", e))
+}
+
+/// 檢查單一資料夾，回傳是否為這次建立
+pub fn check_folder(path: &str, create: bool) -> Result<bool, String> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err("No download folder selected".into());
+    }
+    let dir = Path::new(trimmed);
+    if !dir.is_absolute() {
+        return Err(format!("Download folder must be an absolute path: {}", trimmed));
+    }
+    // 既有的資料夾只看能否寫入；需要建立時才確認磁碟還在，避免寫到空的掛載點
+    if let Some(root) = volume_root(dir).filter(|_| !dir.is_dir()) {
+        if !volume_available(&root) {
+            return Err(format!("Drive or network share is not available: {}", root.display()));
+        }
+    }
+    let mut created = false;
+    if !dir.exists() {
+        if !create {
+            return Err(format!("Folder does not exist: {}", trimmed));
+        }
+        std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create folder: {}", e))?;
+        created = true;
+    } else if !dir.is_dir() {
+        return Err(format!("Not a folder: {}", trimmed));
+    }
+    probe_writable(dir)?;
+    Ok(created)
+}
+
+/// 檢查要求的資料夾，無法使用時改用預設資料夾 (預設資料夾不存在時一律建立)
+pub fn resolve(settings: &DownloadSettings, requested: &str, create: bool) -> Result<SavePath, String> {
+    let reason = match check_folder(requested, create) {
+        Ok(created) => {
+            return Ok(SavePath { path: requested.trim().to_string(), requested: requested.to_string(), created, fallback: false, reason: None });
+        }
+        Err(e) => e,
+    };
+    let Some(default) = settings.default_folder.as_deref().filter(|d| !d.trim().is_empty() && d.trim() != requested.trim()) else {
+        return Err(reason);
+    };
+    let created = check_folder(default, true).map_err(|e| format!("{} (default folder: {})", reason, e))?;
+    Ok(SavePath { path: default.trim().to_string(), requested: requested.to_string(), created, fallback: true, reason: Some(reason) })
+}
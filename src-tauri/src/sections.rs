@@ -0,0 +1,162 @@
+// [2026-10-19 新增] 片段下載：起訖時間與指定章節，對應 yt-dlp 的 --download-sections
+use crate::ytdlp::{Chapter, DownloadOptions};
+
+/// 解析時間字串：支援 "83"、"1:23"、"01:01:23.5"
+/// [2026-10-19 修正] 只接受數字、冒號與秒數中的一個小數點；f64::from_str 會接受 "nan"、"inf"、"1e3"、"-0"
+pub fn parse_timestamp(raw: &str) -> Result<f64, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Err("Empty timestamp".into());
+    }
+    let invalid = || format!("Invalid timestamp: {}", raw);
+    let parts: Vec<&str> = raw.split(':').collect();
+    if parts.len() > 3 {
+        return Err(invalid());
+    }
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let mut total = 0.0;
+    for (i, part) in parts.iter().enumerate() {
+        let valid = match part.split_once('.') {
+            Some((whole, frac)) => i == parts.len() - 1 && digits(whole) && digits(frac),
+            None => digits(part),
+        };
+        if !valid {
+            return Err(invalid());
+        }
+        let value: f64 = part.parse().map_err(|_| invalid())?;
+        // 除了最高位以外，分與秒不得超過 59
+        if i > 0 && value >= 60.0 {
+            return Err(invalid());
+        }
+        total = total * 60.0 + value;
+    }
+    if !total.is_finite() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+/// 檔名用的時間標籤，例如 83.5 -> "0h01m23s"
+pub fn time_label(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    format!("{}h{:02}m{:02}s", secs / 3600, (secs % 3600) / 60, secs % 60)
+}
+
+/// 整理後的片段設定
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sections {
+    Range { start: f64, end: Option<f64> },
+    /// 章節在 info JSON 中的索引 (由 0 開始)
+    Chapters(Vec<usize>),
+}
+
+impl Sections {
+    /// 依選項與影片資訊 (長度、章節) 驗證並建立片段設定；沒有設定時回傳 None
+    pub fn from_options(opts: &DownloadOptions, duration: Option<f64>, chapters: &[Chapter]) -> Result<Option<Self>, String> {
+        let has_range = opts.start_time.is_some() || opts.end_time.is_some();
+        let has_chapters = !opts.chapters.is_empty() || !opts.chapter_indices.is_empty();
+        if has_range && has_chapters {
+            return Err("Time range and chapter selection cannot be combined".into());
+        }
+
+        if has_range {
+            let start = opts.start_time.as_deref().map(parse_timestamp).transpose()?.unwrap_or(0.0);
+            let end = opts.end_time.as_deref().map(parse_timestamp).transpose()?;
+            if let Some(end) = end {
+                if end <= start {
+                    return Err("End time must be after start time".into());
+                }
+            }
+            if let Some(duration) = duration {
+                if start >= duration {
+                    return Err(format!("Start time is beyond the video length ({})", time_label(duration)));
+                }
+            }
+            // 結束時間超過影片長度時視為到結尾
+            let end = end.filter(|e| duration.is_none_or(|d| *e < d));
+            return Ok(Some(Sections::Range { start, end }));
+        }
+
+        if has_chapters {
+            let mut indices = Vec::new();
+            for wanted in &opts.chapters {
+                // [2026-10-19 修正] 標題重複時無法判斷是哪一章，要求改用索引選擇
+                let mut matches = chapters.iter().enumerate().filter(|(_, c)| c.title == *wanted).map(|(i, _)| i);
+                let idx = matches.next().ok_or_else(|| format!("Chapter not found: {}", wanted))?;
+                if matches.next().is_some() {
+                    return Err(format!("Several chapters are named \"{}\"; select it by index", wanted));
+                }
+                indices.push(idx);
+            }
+            for &idx in &opts.chapter_indices {
+                if idx >= chapters.len() {
+                    return Err(format!("Chapter not found: #{}", idx + 1));
+                }
+                indices.push(idx);
+            }
+            indices.sort_unstable();
+            indices.dedup();
+            return Ok(Some(Sections::Chapters(indices)));
+        }
+        Ok(None)
+    }
+
+    /// 轉為 yt-dlp 參數
+    pub fn args(&self, chapters: &[Chapter], precise_cuts: bool) -> Vec<String> {
+        let mut args = Vec::new();
+        match self {
+            Sections::Range { start, end } => {
+                let end = end.map(|e| e.to_string()).unwrap_or_else(|| "inf".into());
+                args.extend(["--download-sections".to_string(), format!("*{}-{}", start, end)]);
+            }
+            Sections::Chapters(indices) => {
+                // [2026-10-19 修改] 以章節的起訖時間指定，yt-dlp 以正則比對名稱時會選到所有同名章節
+                for &i in indices {
+                    args.extend(["--download-sections".to_string(), format!("*{}-{}", chapters[i].start_time, chapters[i].end_time)]);
+                }
+            }
+        }
+        if precise_cuts {
+            args.push("--force-keyframes-at-cuts".into());
+        }
+        args
+    }
+
+    /// 附加在檔名中的標籤
+    pub fn label(&self) -> String {
+        match self {
+            Sections::Range { start, end } => {
+                let end = end.map(time_label).unwrap_or_else(|| "end".into());
+                format!("{}-{}", time_label(*start), end)
+            }
+            Sections::Chapters(indices) => {
+                let nums: Vec<String> = indices.iter().map(|i| (i + 1).to_string()).collect();
+                format!("ch{}", nums.join("+"))
+            }
+        }
+    }
+
+    /// 選取的總長度 (秒)，total 為影片長度；用於依比例估計下載大小
+    pub fn selected_duration(&self, total: f64, chapters: &[Chapter]) -> f64 {
+        match self {
+            Sections::Range { start, end } => (end.unwrap_or(total).min(total) - start).max(0.0),
+            Sections::Chapters(indices) => indices.iter()
+                .filter_map(|&i| chapters.get(i))
+                .map(|c| (c.end_time - c.start_time).max(0.0))
+                .sum(),
+        }
+    }
+
+    /// [2026-10-19 新增] 輸出的檔案數 (每個選取的章節各一個)
+    pub fn file_count(&self) -> usize {
+        match self {
+            Sections::Range { .. } => 1,
+            Sections::Chapters(indices) => indices.len(),
+        }
+    }
+
+    /// 是否會產生多個檔案 (多個章節各自輸出)
+    pub fn is_multi_file(&self) -> bool {
+        self.file_count() > 1
+    }
+}
@@ -0,0 +1,240 @@
+// [2026-10-19 新增] 使用者設定：存放於使用者資料目錄下的 settings.json
+// 欄位一律有預設值，舊版設定檔缺少的欄位會自動補上
+use crate::clipboard::ClipboardAction;
+use crate::hooks::Hook;
+use crate::network::IpVersion;
+use crate::organizer::OrganizeRule;
+use crate::ytdlp::DownloadOptions;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// 使用者手動指定的組件路徑 (優先於其他搜尋位置)
+    pub components: ComponentPaths,
+    pub metadata_cache: CacheSettings,
+    pub timeouts: TimeoutSettings,
+    pub sponsorblock: SponsorBlockSettings,
+    pub network: NetworkSettings,
+    pub bandwidth: BandwidthSettings,
+    /// 具名的下載設定 (訂閱等自動任務使用)
+    pub profiles: Vec<DownloadProfile>,
+    pub clipboard: ClipboardSettings,
+    pub downloads: DownloadSettings,
+    /// 下載完成後依序比對的整理規則
+    pub organizer_rules: Vec<OrganizeRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadSettings {
+    /// 選擇的資料夾無法使用 (不存在、唯讀、磁碟已移除) 時改用的資料夾
+    pub default_folder: Option<String>,
+    /// 下載前自動建立不存在的資料夾 (批次匯入、訂閱的子資料夾需要)
+    pub create_missing: bool,
+}
+
+impl Default for DownloadSettings {
+    fn default() -> Self {
+        Self { default_folder: None, create_missing: true }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardSettings {
+    /// 是否在背景監看剪貼簿
+    pub enabled: bool,
+    pub action: ClipboardAction,
+    /// 不處理的網域 (子網域也適用)
+    pub ignore_domains: Vec<String>,
+    /// 自動加入佇列時的下載資料夾
+    pub download_dir: Option<String>,
+    /// 自動解析 / 下載時的訊息語言
+    pub lang: String,
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        Self { enabled: false, action: ClipboardAction::default(), ignore_domains: Vec::new(), download_dir: None, lang: "en".into() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadProfile {
+    pub name: String,
+    pub mode: String,
+    pub quality: String,
+    pub options: DownloadOptions,
+    /// 使用此設定檔的下載結束後執行的掛鉤
+    pub hooks: Vec<Hook>,
+}
+
+impl Default for DownloadProfile {
+    fn default() -> Self {
+        Self { name: "default".into(), mode: "video".into(), quality: "best".into(), options: DownloadOptions::default(), hooks: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BandwidthSettings {
+    /// 全域速率上限 (bytes/s)，None 表示不限速
+    pub limit: Option<u64>,
+    /// 依時段覆寫的上限，第一個符合目前時間的規則生效
+    pub schedule: Vec<ThrottleRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThrottleRule {
+    /// 開始時間 "HH:MM" (本地時間)
+    pub start: String,
+    /// 結束時間 "HH:MM"；早於開始時間表示跨午夜
+    pub end: String,
+    /// 此時段的上限 (bytes/s)，None 表示不限速
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// 代理伺服器，例如 http://proxy:8080、socks5://127.0.0.1:1080
+    pub proxy: Option<String>,
+    /// 各網站的代理覆寫 (網站 -> 代理網址，或 "direct" 表示直接連線)
+    pub site_proxies: BTreeMap<String, String>,
+    /// 綁定的本機來源位址
+    pub source_address: Option<String>,
+    pub ip_version: IpVersion,
+    /// 地區繞過：以 X-Forwarded-For 偽裝的國家代碼 (兩碼，例如 US)
+    pub geo_bypass_country: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SponsorBlockSettings {
+    /// SponsorBlock API 位址 (可改為自架的鏡像)
+    pub api_base: String,
+}
+
+impl Default for SponsorBlockSettings {
+    fn default() -> Self {
+        Self { api_base: crate::sponsorblock::DEFAULT_API_BASE.into() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeoutSettings {
+    /// yt-dlp 解析影片資訊的逾時秒數
+    pub metadata_secs: u64,
+}
+
+impl Default for TimeoutSettings {
+    fn default() -> Self {
+        Self { metadata_secs: 90 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheSettings {
+    /// 影片資訊保留秒數 (YouTube 的串流網址約數小時後失效，預設 30 分鐘)
+    pub ttl_secs: u64,
+    /// 是否同時寫入磁碟
+    pub persist: bool,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self { ttl_secs: 30 * 60, persist: false }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ComponentPaths {
+    pub yt_dlp: Option<PathBuf>,
+    pub ffmpeg: Option<PathBuf>,
+    pub deno: Option<PathBuf>,
+}
+
+impl Settings {
+    /// 儲存前檢查 (錯誤的代理格式等)
+    pub fn validate(&self) -> Result<(), String> {
+        self.network.validate()?;
+        self.bandwidth.validate()?;
+        self.clipboard.validate()?;
+        if let Some(folder) = self.downloads.default_folder.as_deref().filter(|f| !f.trim().is_empty()) {
+            if !Path::new(folder.trim()).is_absolute() {
+                return Err(format!("Default download folder must be an absolute path: {}", folder));
+            }
+        }
+        for (i, rule) in self.organizer_rules.iter().enumerate() {
+            rule.validate()?;
+            if self.organizer_rules[..i].iter().any(|r| r.name == rule.name) {
+                return Err(format!("Duplicate rule: {}", rule.name));
+            }
+        }
+        for (i, profile) in self.profiles.iter().enumerate() {
+            if profile.name.trim().is_empty() {
+                return Err("Profile name cannot be empty".into());
+            }
+            if self.profiles[..i].iter().any(|p| p.name == profile.name) {
+                return Err(format!("Duplicate profile: {}", profile.name));
+            }
+            for hook in &profile.hooks {
+                hook.validate().map_err(|e| format!("{} ({}): {}", profile.name, hook.label(), e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 讀取設定檔；檔案不存在時回傳預設值
+    // [2026-10-19 修正] 格式錯誤時回傳錯誤，不再默默改用預設值 (下次儲存會蓋掉使用者原本的設定)
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(txt) => serde_json::from_str(&txt).map_err(|e| format!("Cannot read settings ({}): {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Cannot read settings ({}): {}", path.display(), e)),
+        }
+    }
+
+    // [2026-10-19 修改] 先寫暫存檔再改名，寫到一半中斷不會留下殘缺的設定檔
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        write_json_atomic(path, self)
+    }
+
+    /// 依名稱取得下載設定檔；未指定 (或指定 "default" 但沒有建立) 時使用內建預設 (影片 / 最佳畫質)
+    // [2026-10-19 修正] 指定了不存在的設定檔時回傳錯誤，不再默默改用預設設定
+    pub fn profile(&self, name: Option<&str>) -> Result<DownloadProfile, String> {
+        let wanted = name.unwrap_or("default");
+        match self.profiles.iter().find(|p| p.name == wanted) {
+            Some(profile) => Ok(profile.clone()),
+            None if wanted == "default" => Ok(DownloadProfile::default()),
+            None => Err(format!("Unknown profile: {}", wanted)),
+        }
+    }
+}
+
+/// [2026-10-19 新增] 無法解析的資料檔改名保留 (例如 history.corrupt-<時間>.json)，之後的寫入不會覆蓋它
+pub(crate) fn back_up_corrupt(path: &Path) -> Result<PathBuf, String> {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let backup = path.with_file_name(format!("{}.corrupt-{}.json", stem, Local::now().format("%Y%m%d%H%M%S")));
+    std::fs::rename(path, &backup).map_err(|e| e.to_string())?;
+    Ok(backup)
+}
+
+/// [2026-10-19 新增] 以 JSON 寫入檔案 (先寫暫存檔再改名，避免寫到一半被中斷)
+pub(crate) fn write_json_atomic<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, txt).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
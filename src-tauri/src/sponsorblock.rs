@@ -0,0 +1,124 @@
+// [2026-10-19 新增] SponsorBlock：將選定類別的片段從輸出中剪掉 (--sponsorblock-remove) 或標記成章節 (--sponsorblock-mark)
+// API 位址可在設定中更改 (測試時指向本機伺服器)，yt-dlp 與這裡查詢被剪掉的片段都使用同一個位址
+// [2026-10-19 修正] 剪除的片段由這裡查詢一次，再以 --remove-chapters 的時間範圍交給 yt-dlp，剪掉的內容與紀錄一致
+use crate::env::AppEnv;
+use crate::network;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const DEFAULT_API_BASE: &str = "https://sponsor.ajay.app";
+
+/// 可選擇的類別 (與 yt-dlp / SponsorBlock 的名稱相同)
+pub const CATEGORIES: [&str; 5] = ["sponsor", "intro", "outro", "selfpromo", "music_offtopic"];
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 被剪掉 (或標記) 的片段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub category: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// 檢查類別名稱，回傳去除重複後的清單
+pub fn validate_categories(categories: &[String]) -> Result<Vec<String>, String> {
+    let mut result: Vec<String> = Vec::new();
+    for category in categories {
+        let category = category.trim().to_ascii_lowercase();
+        if !CATEGORIES.contains(&category.as_str()) {
+            return Err(format!("Unknown SponsorBlock category: {}", category));
+        }
+        if !result.contains(&category) {
+            result.push(category);
+        }
+    }
+    Ok(result)
+}
+
+/// yt-dlp 參數；同一類別同時出現在兩邊時以剪除為準。
+/// [2026-10-19 修正] removed 為已查詢到的剪除片段：依時間範圍剪掉；查詢失敗 (None) 時才讓 yt-dlp 自行查詢剪除的類別
+pub fn args(api_base: &str, remove: &[String], mark: &[String], removed: Option<&[Segment]>) -> Vec<String> {
+    let mark: Vec<&String> = mark.iter().filter(|c| !remove.contains(c)).collect();
+    let mut args = Vec::new();
+    match removed {
+        Some(segments) => {
+            for segment in merge(segments) {
+                args.extend(["--remove-chapters".to_string(), format!("*{}-{}", segment.start, segment.end)]);
+            }
+        }
+        None if !remove.is_empty() => args.extend(["--sponsorblock-remove".to_string(), remove.join(",")]),
+        None => {}
+    }
+    if !mark.is_empty() {
+        let mark: Vec<&str> = mark.iter().map(|c| c.as_str()).collect();
+        args.extend(["--sponsorblock-mark".to_string(), mark.join(",")]);
+    }
+    if args.iter().any(|a| a.starts_with("--sponsorblock-")) {
+        args.splice(0..0, ["--sponsorblock-api".to_string(), api_base.trim_end_matches('/').to_string()]);
+    }
+    args
+}
+
+/// [2026-10-19 新增] 合併重疊或相連的片段 (不同類別的片段常互相重疊)；回傳依時間排序的片段，類別取第一段的
+pub fn merge(segments: &[Segment]) -> Vec<Segment> {
+    let mut sorted: Vec<&Segment> = segments.iter().filter(|s| s.end > s.start).collect();
+    sorted.sort_by(|a, b| a.start.total_cmp(&b.start));
+    let mut merged: Vec<Segment> = Vec::new();
+    for segment in sorted {
+        match merged.last_mut() {
+            Some(last) if segment.start <= last.end => last.end = last.end.max(segment.end),
+            _ => merged.push(segment.clone()),
+        }
+    }
+    merged
+}
+
+/// [2026-10-19 新增] 剪掉的總長度 (秒)；重疊的部分只算一次，超出影片長度的部分不計
+pub fn removed_duration(segments: &[Segment], duration: f64) -> f64 {
+    merge(segments).iter().map(|s| (s.end.min(duration) - s.start.max(0.0)).max(0.0)).sum()
+}
+
+/// 向 SponsorBlock 查詢影片的片段；沒有任何片段時 API 回傳 404，視為空清單
+pub async fn fetch_segments(env: &AppEnv, video_id: &str, categories: &[String]) -> Result<Vec<Segment>, String> {
+    let base = env.settings.sponsorblock.api_base.trim_end_matches('/');
+    let client = network::client_builder(&env.settings.network, base)?
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e: reqwest::Error| e.to_string())?;
+
+    let categories = serde_json::to_string(categories).map_err(|e| e.to_string())?;
+    let resp = client.get(format!("{}/api/skipSegments", base))
+        .query(&[("videoID", video_id), ("categories", categories.as_str())])
+        .send()
+        .await
+        .map_err(|e: reqwest::Error| e.to_string())?;
+
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(Vec::new());
+    }
+    if !resp.status().is_success() {
+        return Err(format!("SponsorBlock HTTP {}", resp.status()));
+    }
+
+    let json = resp.json::<serde_json::Value>()
+        .await
+        .map_err(|e: reqwest::Error| e.to_string())?;
+    Ok(parse_segments(&json))
+}
+
+/// 解析 /api/skipSegments 的回應，只保留 actionType 為 skip 的片段並依時間排序
+pub fn parse_segments(json: &serde_json::Value) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = json.as_array().map(|list| {
+        list.iter()
+            .filter(|s| s["actionType"].as_str().unwrap_or("skip") == "skip")
+            .filter_map(|s| Some(Segment {
+                category: s["category"].as_str()?.to_string(),
+                start: s["segment"][0].as_f64()?,
+                end: s["segment"][1].as_f64()?,
+            }))
+            .collect()
+    }).unwrap_or_default();
+    segments.sort_by(|a, b| a.start.total_cmp(&b.start));
+    segments
+}
@@ -0,0 +1,491 @@
+// [2026-10-19 新增] 頻道 / 播放清單訂閱：定期以 --flat-playlist 讀取最新項目，與 download archive 比對後自動加入下載佇列。
+// 每個訂閱指定下載設定檔 (settings.profiles) 與目的資料夾，可依長度、標題、上傳日期過濾，並可略過 Shorts 與直播。
+// 第一次檢查只記錄既有項目，之後出現的新影片才會下載 (除非設定 download_existing)。
+// [2026-10-19 新增] 也可訂閱 RSS / Atom feed (見 feeds.rs)，項目同樣轉成 PlaylistEntry 後套用相同的比對與過濾。
+use crate::env::AppEnv;
+use crate::events::{self, get_msg, EventSink};
+use crate::queue::{DownloadQueue, JobSchedule, QueuedJob};
+use crate::settings::write_json_atomic;
+use crate::urls::UrlKind;
+use crate::ytdlp::{self, DownloadRequest};
+use crate::{auth, feeds, network, process, urls};
+use chrono::{Local, TimeZone, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+pub const EVT_SUBSCRIPTIONS: &str = "subscriptions-updated";
+
+pub const DEFAULT_INTERVAL_MINS: u64 = 60;
+const MIN_INTERVAL_MINS: u64 = 5;
+
+/// 每次只讀取最新的項目，避免大型頻道每次都列出全部影片
+const PLAYLIST_END: usize = 50;
+
+/// 記住已處理過的項目數量上限 (超過時丟棄最舊的)
+const MAX_SEEN: usize = 5000;
+
+/// [2026-10-19 新增] 一次檢查中為補上傳日期而解析影片的總時間上限；剩下的項目留到下次檢查
+const DATE_LOOKUP_BUDGET: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubscriptionFilters {
+    /// 最短長度 (秒)
+    pub min_duration: Option<u64>,
+    /// 最長長度 (秒)
+    pub max_duration: Option<u64>,
+    /// 標題需符合的正規表示式
+    pub title_regex: Option<String>,
+    /// 只下載此日期 (含) 之後上傳的影片，格式 YYYYMMDD
+    pub date_after: Option<String>,
+    pub skip_shorts: bool,
+    /// 略過直播、預定直播與直播存檔
+    pub skip_live: bool,
+}
+
+/// [2026-10-19 新增] 訂閱來源的種類
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionKind {
+    /// 頻道 / 播放清單，以 yt-dlp --flat-playlist 讀取
+    #[default]
+    Playlist,
+    /// RSS / Atom feed (podcast、YouTube 頻道 feed)
+    Feed,
+}
+
+/// 前端新增 / 修改訂閱時傳入的設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionConfig {
+    pub url: String,
+    #[serde(default)]
+    pub kind: SubscriptionKind,
+    /// 下載設定檔名稱；None 使用預設 (影片 / 最佳畫質)
+    #[serde(default)]
+    pub profile: Option<String>,
+    pub target_dir: String,
+    #[serde(default = "default_lang")]
+    pub lang: String,
+    #[serde(default = "default_interval")]
+    pub interval_mins: u64,
+    #[serde(default)]
+    pub filters: SubscriptionFilters,
+    /// 第一次檢查時也下載已存在的項目
+    #[serde(default)]
+    pub download_existing: bool,
+}
+
+fn default_lang() -> String {
+    "en".into()
+}
+
+fn default_interval() -> u64 {
+    DEFAULT_INTERVAL_MINS
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    pub id: String,
+    pub config: SubscriptionConfig,
+    pub enabled: bool,
+    pub created_at: i64,
+    /// 上次檢查時間 (Unix 秒)；None 表示尚未檢查
+    pub last_checked: Option<i64>,
+    pub last_error: Option<String>,
+    /// 已處理過的項目鍵 (見 PlaylistEntry::archive_key)，包含被過濾掉的
+    #[serde(default)]
+    pub seen: Vec<String>,
+}
+
+/// 訂閱來源中的一個項目 (--flat-playlist 或 feed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaylistEntry {
+    /// 判斷是否處理過的鍵：影片為 download archive 格式 "extractor id"，其他 feed 項目為 GUID
+    pub archive_key: String,
+    pub url: String,
+    pub title: String,
+    pub duration: Option<f64>,
+    /// YYYYMMDD
+    pub upload_date: Option<String>,
+    pub live_status: Option<String>,
+}
+
+impl PlaylistEntry {
+    pub fn is_short(&self) -> bool {
+        self.url.contains("/shorts/")
+    }
+
+    pub fn is_live(&self) -> bool {
+        matches!(self.live_status.as_deref(), Some("is_live" | "is_upcoming" | "was_live" | "post_live"))
+    }
+}
+
+impl SubscriptionFilters {
+    pub fn validate(&self) -> Result<(), String> {
+        if let (Some(min), Some(max)) = (self.min_duration, self.max_duration) {
+            if min > max {
+                return Err("Minimum duration is longer than maximum duration".into());
+            }
+        }
+        if let Some(pattern) = &self.title_regex {
+            Regex::new(pattern).map_err(|e| format!("Invalid title pattern: {}", e))?;
+        }
+        if let Some(date) = &self.date_after {
+            chrono::NaiveDate::parse_from_str(date, "%Y%m%d").map_err(|_| format!("Invalid date (YYYYMMDD): {}", date))?;
+        }
+        Ok(())
+    }
+
+    /// 項目是否符合條件；平面清單缺少的欄位 (長度、日期) 不列入判斷
+    pub fn matches(&self, entry: &PlaylistEntry) -> bool {
+        if (self.skip_shorts && entry.is_short()) || (self.skip_live && entry.is_live()) {
+            return false;
+        }
+        if let Some(duration) = entry.duration {
+            if self.min_duration.is_some_and(|min| duration < min as f64) || self.max_duration.is_some_and(|max| duration > max as f64) {
+                return false;
+            }
+        }
+        if let (Some(after), Some(date)) = (&self.date_after, &entry.upload_date) {
+            if date < after {
+                return false;
+            }
+        }
+        match self.title_regex.as_deref().map(Regex::new) {
+            Some(Ok(re)) => re.is_match(&entry.title),
+            _ => true,
+        }
+    }
+}
+
+impl SubscriptionConfig {
+    fn validate(&self) -> Result<(), String> {
+        urls::normalize(&self.url)?;
+        if self.target_dir.trim().is_empty() {
+            return Err("Target folder not selected".into());
+        }
+        if self.interval_mins < MIN_INTERVAL_MINS {
+            return Err(format!("Check interval must be at least {} minutes", MIN_INTERVAL_MINS));
+        }
+        self.filters.validate()
+    }
+}
+
+impl Subscription {
+    fn is_due(&self, now: i64) -> bool {
+        self.enabled && self.last_checked.is_none_or(|t| now - t >= self.config.interval_mins as i64 * 60)
+    }
+}
+
+/// 解析 yt-dlp --flat-playlist --dump-single-json 的輸出
+pub fn parse_entries(json: &serde_json::Value) -> Vec<PlaylistEntry> {
+    let playlist_extractor = json["extractor_key"].as_str().unwrap_or("generic");
+    let Some(entries) = json["entries"].as_array() else { return Vec::new() };
+    entries.iter().filter_map(|e| {
+        // 頻道首頁的平面清單會包含分頁 (影片 / Shorts / 直播) 本身，這些不是影片
+        if e["_type"].as_str() == Some("playlist") {
+            return None;
+        }
+        let id = e["id"].as_str()?;
+        let extractor = e["ie_key"].as_str().or(e["extractor_key"].as_str()).unwrap_or(playlist_extractor);
+        let url = [&e["url"], &e["webpage_url"]].into_iter()
+            .filter_map(|v| v.as_str())
+            .find(|u| u.starts_with("http"))?;
+        let upload_date = e["upload_date"].as_str().map(str::to_string).or_else(|| {
+            let ts = e["timestamp"].as_i64().or(e["release_timestamp"].as_i64())?;
+            Some(Utc.timestamp_opt(ts, 0).single()?.format("%Y%m%d").to_string())
+        });
+        Some(PlaylistEntry {
+            archive_key: format!("{} {}", extractor.to_ascii_lowercase(), id),
+            url: url.to_string(),
+            title: e["title"].as_str().unwrap_or(id).to_string(),
+            duration: e["duration"].as_f64(),
+            upload_date,
+            live_status: e["live_status"].as_str().map(str::to_string),
+        })
+    }).collect()
+}
+
+/// 讀取 download archive (每行 "extractor id")
+pub fn read_archive(path: &Path) -> HashSet<String> {
+    std::fs::read_to_string(path)
+        .map(|txt| txt.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// [2026-10-19 新增] YouTube 頻道首頁的平面清單只列出分頁 (影片 / Shorts / 直播)，改讀「影片」分頁
+pub fn playlist_url(url: &str) -> String {
+    let Ok(normalized) = urls::normalize(url) else { return url.to_string() };
+    if normalized.site != "youtube.com" || normalized.kind != UrlKind::Channel {
+        return url.to_string();
+    }
+    let path = normalized.url.trim_start_matches("https://www.youtube.com/");
+    let segments: Vec<&str> = path.split('/').collect();
+    // @handle 或 channel/<id>、c/<name>、user/<name>，後面沒有分頁
+    let is_root = match segments.as_slice() {
+        [handle] => handle.starts_with('@'),
+        [_, _] => !segments[0].starts_with('@'),
+        _ => false,
+    };
+    if is_root { format!("{}/videos", normalized.url) } else { url.to_string() }
+}
+
+/// [2026-10-19 新增] 判斷是否重複訂閱用的網址 (同一頻道的不同寫法視為相同)
+fn subscription_key(url: &str) -> String {
+    match urls::normalize(url) {
+        Ok(normalized) => playlist_url(&normalized.url),
+        Err(_) => url.trim().to_string(),
+    }
+}
+
+/// 以平面清單讀取頻道 / 播放清單的最新項目 (不逐一解析影片，速度快)
+pub async fn fetch_entries(env: &AppEnv, url: &str) -> Result<Vec<PlaylistEntry>, String> {
+    let url = &playlist_url(url);
+    let mut cmd = process::command(env.yt_dlp());
+    cmd.args(["--no-config", "--quiet", "--no-warnings", "--flat-playlist"]);
+    cmd.args(["--playlist-end", &PLAYLIST_END.to_string()]);
+    let auth = auth::args_for_url(env, url);
+    cmd.args(&auth.args);
+    cmd.args(network::yt_dlp_args(&env.settings.network, url));
+    cmd.args(["--dump-single-json", "--", url]);
+
+    let timeout = Duration::from_secs(env.settings.timeouts.metadata_secs);
+    let output = process::run_capture(cmd, timeout).await?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        return Err("Empty Output".into());
+    }
+    let json: serde_json::Value = serde_json::from_str(&stdout).map_err(|e| e.to_string())?;
+    Ok(parse_entries(&json))
+}
+
+pub struct SubscriptionStore {
+    path: PathBuf,
+    subs: Mutex<Vec<Subscription>>,
+    counter: AtomicU64,
+    /// [2026-10-19 新增] 每個訂閱的檢查鎖：定期檢查與手動檢查可能同時進行
+    checks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl SubscriptionStore {
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let subs = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|txt| serde_json::from_str(&txt).ok())
+            .unwrap_or_default();
+        Self { path, subs: Mutex::new(subs), counter: AtomicU64::new(0), checks: Mutex::default() }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Subscription>> {
+        self.subs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn check_lock(&self, id: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut checks = self.checks.lock().unwrap_or_else(|e| e.into_inner());
+        checks.entry(id.to_string()).or_default().clone()
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut Vec<Subscription>) -> Result<T, String>) -> Result<T, String> {
+        let mut subs = self.lock();
+        let value = f(&mut subs)?;
+        write_json_atomic(&self.path, &*subs)?;
+        Ok(value)
+    }
+
+    fn update_one(&self, id: &str, f: impl FnOnce(&mut Subscription)) -> Result<(), String> {
+        self.update(|subs| {
+            let sub = subs.iter_mut().find(|s| s.id == id).ok_or("Subscription not found")?;
+            f(sub);
+            Ok(())
+        })
+    }
+
+    pub fn list(&self) -> Vec<Subscription> {
+        self.lock().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Subscription> {
+        self.lock().iter().find(|s| s.id == id).cloned()
+    }
+
+    pub fn add(&self, config: SubscriptionConfig) -> Result<Subscription, String> {
+        config.validate()?;
+        let now = Local::now();
+        let sub = Subscription {
+            id: format!("{:x}-{}", now.timestamp_millis(), self.counter.fetch_add(1, Ordering::Relaxed)),
+            config,
+            enabled: true,
+            created_at: now.timestamp(),
+            last_checked: None,
+            last_error: None,
+            seen: Vec::new(),
+        };
+        let key = subscription_key(&sub.config.url);
+        self.update(|subs| {
+            if subs.iter().any(|s| subscription_key(&s.config.url) == key) {
+                return Err("Already subscribed".into());
+            }
+            subs.push(sub.clone());
+            Ok(sub)
+        })
+    }
+
+    /// 修改設定；已處理過的項目保留，不會因為放寬條件而重新下載舊影片
+    pub fn edit(&self, id: &str, config: SubscriptionConfig) -> Result<(), String> {
+        config.validate()?;
+        let key = subscription_key(&config.url);
+        self.update(|subs| {
+            if subs.iter().any(|s| s.id != id && subscription_key(&s.config.url) == key) {
+                return Err("Already subscribed".into());
+            }
+            let sub = subs.iter_mut().find(|s| s.id == id).ok_or("Subscription not found")?;
+            sub.config = config;
+            Ok(())
+        })
+    }
+
+    pub fn set_enabled(&self, id: &str, enabled: bool) -> Result<(), String> {
+        self.update_one(id, |sub| sub.enabled = enabled)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), String> {
+        self.update(|subs| {
+            subs.retain(|s| s.id != id);
+            Ok(())
+        })?;
+        self.checks.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+        Ok(())
+    }
+
+    /// 已到檢查時間的訂閱
+    pub fn due(&self) -> Vec<String> {
+        let now = Local::now().timestamp();
+        self.lock().iter().filter(|s| s.is_due(now)).map(|s| s.id.clone()).collect()
+    }
+}
+
+/// 立即檢查一個訂閱，將新的項目加入佇列並回傳新增的任務
+pub async fn check(env: &AppEnv, store: &SubscriptionStore, queue: &DownloadQueue, id: &str) -> Result<Vec<QueuedJob>, String> {
+    // [2026-10-19 修正] 同一訂閱的檢查依序進行；取得鎖後才讀取 seen，前一次檢查加入的項目不會重複加入
+    let lock = store.check_lock(id);
+    let _guard = lock.lock().await;
+    let sub = store.get(id).ok_or("Subscription not found")?;
+    // [2026-10-19 修正] 設定檔被刪除時回報錯誤，而不是默默改用預設設定下載
+    let profile = env.settings.profile(sub.config.profile.as_deref());
+    let entries = match &profile {
+        Err(e) => Err(e.clone()),
+        Ok(_) => match sub.config.kind {
+            SubscriptionKind::Playlist => fetch_entries(env, &sub.config.url).await,
+            SubscriptionKind::Feed => feeds::fetch(env, &sub.config.url).await,
+        },
+    };
+    let entries = match entries {
+        Ok(entries) => entries,
+        Err(e) => {
+            store.update_one(id, |s| {
+                s.last_checked = Some(Local::now().timestamp());
+                s.last_error = Some(e.clone());
+            })?;
+            return Err(e);
+        }
+    };
+
+    let archive = read_archive(&env.archive_path());
+    let mut seen: HashSet<String> = sub.seen.iter().cloned().collect();
+    let first_check = sub.last_checked.is_none();
+    let profile = profile?;
+    let mut new_keys = Vec::new();
+    let mut jobs = Vec::new();
+    let mut error = None;
+    let lookup_deadline = Instant::now() + DATE_LOOKUP_BUDGET;
+
+    // 清單由新到舊，反過來加入佇列讓舊的影片先下載
+    for entry in entries.iter().rev() {
+        if archive.contains(&entry.archive_key) || !seen.insert(entry.archive_key.clone()) {
+            continue;
+        }
+        new_keys.push(entry.archive_key.clone());
+        if (first_check && !sub.config.download_existing) || !sub.config.filters.matches(entry) {
+            continue;
+        }
+        // [2026-10-19 修正] 平面清單常缺少上傳日期，有日期條件時解析一次影片取得 (結果留在快取中，下載時沿用)；
+        // 仍無法得知日期時不下載
+        if let Some(after) = &sub.config.filters.date_after {
+            let date = match &entry.upload_date {
+                Some(date) => Some(date.clone()),
+                // [2026-10-19 修正] 所有解析共用一個時間上限，超過後剩下的項目下次再試
+                None => match tokio::time::timeout_at(lookup_deadline, ytdlp::fetch_info(env, &entry.url)).await {
+                    Ok(Ok(info)) => info["upload_date"].as_str().map(str::to_string),
+                    Ok(Err(e)) => {
+                        // 下次再試
+                        new_keys.pop();
+                        error = Some(e);
+                        continue;
+                    }
+                    Err(_) => {
+                        new_keys.pop();
+                        continue;
+                    }
+                },
+            };
+            if date.is_none_or(|d| d < *after) {
+                continue;
+            }
+        }
+        let mut request = DownloadRequest::new(&entry.url, &profile.mode, &profile.quality, &sub.config.target_dir, &sub.config.lang);
+        request.options = profile.options.clone();
+        request.profile = Some(profile.name.clone());
+        request.options.use_archive = true;
+        match queue.enqueue(request, JobSchedule::default()) {
+            Ok(job) => jobs.push(job),
+            Err(e) => {
+                // 加入失敗的項目下次再試
+                new_keys.pop();
+                error = Some(e);
+            }
+        }
+    }
+
+    store.update_one(id, |s| {
+        s.seen.extend(new_keys);
+        if s.seen.len() > MAX_SEEN {
+            let excess = s.seen.len() - MAX_SEEN;
+            s.seen.drain(..excess);
+        }
+        s.last_checked = Some(Local::now().timestamp());
+        s.last_error = error;
+    })?;
+    Ok(jobs)
+}
+
+/// 檢查所有到期的訂閱；錯誤只寫入紀錄，不影響其他訂閱
+pub async fn check_due(env: &AppEnv, sink: &dyn EventSink, store: &SubscriptionStore, queue: &DownloadQueue) -> usize {
+    let due = store.due();
+    if due.is_empty() {
+        return 0;
+    }
+    let mut added = 0;
+    for id in due {
+        let Some(sub) = store.get(&id) else { continue };
+        match check(env, store, queue, &id).await {
+            Ok(jobs) if !jobs.is_empty() => {
+                added += jobs.len();
+                events::log(sink, format!("{} {}: {}", get_msg(&sub.config.lang, "訂閱新增影片", "Subscription queued"), sub.config.url, jobs.len()));
+            }
+            Ok(_) => {}
+            Err(e) => events::log(sink, format!("{} {}: {}", get_msg(&sub.config.lang, "訂閱檢查失敗", "Subscription check failed"), sub.config.url, e)),
+        }
+    }
+    if added > 0 {
+        events::emit(sink, crate::queue::EVT_QUEUE, queue.list());
+    }
+    events::emit(sink, EVT_SUBSCRIPTIONS, store.list());
+    added
+}
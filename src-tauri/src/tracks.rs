@@ -0,0 +1,122 @@
+// [2026-10-19 新增] 依章節分割音訊：專輯 / DJ mix 這類整支上傳的影片，下載後以 ffmpeg 切成一首一檔，
+// 寫入曲名、曲目編號、專輯 (影片標題)、演出者與共用封面，放在以影片標題命名的資料夾中。
+// yt-dlp 內建的 --split-chapters 不會為每個分割檔寫入各自的曲名與封面，所以這裡自行處理。
+use crate::env::AppEnv;
+use crate::events::{self, get_msg, EventSink};
+use crate::process;
+use crate::ytdlp::{sanitize_file_name, Chapter};
+use std::path::{Path, PathBuf};
+
+/// 分割後的共用封面檔名 (多數播放器會自動讀取資料夾中的 cover.jpg)
+pub const COVER_FILE: &str = "cover.jpg";
+
+/// 整張專輯共用的標籤
+#[derive(Debug, Clone)]
+pub struct AlbumInfo {
+    pub album: String,
+    pub artist: Option<String>,
+}
+
+/// 在 base 下建立以影片標題命名、尚未存在的資料夾
+pub fn unique_album_dir(base: &Path, title: &str) -> PathBuf {
+    let name = sanitize_file_name(title);
+    let mut counter = 0;
+    loop {
+        let dir = if counter == 0 { base.join(&name) } else { base.join(format!("{}_{}", name, counter)) };
+        if !dir.exists() {
+            return dir;
+        }
+        counter += 1;
+    }
+}
+
+/// 單一曲目的檔名，例如 "03 - Title.mp3"
+pub fn track_file_name(number: usize, title: &str, ext: &str) -> String {
+    let title = if title.trim().is_empty() { format!("Track {}", number) } else { sanitize_file_name(title) };
+    format!("{:02} - {}.{}", number, title, ext)
+}
+
+/// 切出第 number 首 (由 1 開始) 的 ffmpeg 參數；音訊直接複製不重新編碼
+pub fn track_args(source: &Path, cover: Option<&Path>, chapter: &Chapter, number: usize, total: usize, album: &AlbumInfo, dest: &Path) -> Vec<String> {
+    let mut args: Vec<String> = vec!["-hide_banner".into(), "-nostdin".into(), "-loglevel".into(), "error".into(), "-y".into()];
+    args.extend(["-ss".into(), chapter.start_time.to_string()]);
+    // 章節缺少結束時間時切到檔案結尾
+    if chapter.end_time > chapter.start_time {
+        args.extend(["-to".into(), chapter.end_time.to_string()]);
+    }
+    args.extend(["-i".into(), source.to_string_lossy().to_string()]);
+    if let Some(cover) = cover {
+        args.extend(["-i".into(), cover.to_string_lossy().to_string()]);
+    }
+
+    args.extend(["-map".into(), "0:a".into(), "-c:a".into(), "copy".into()]);
+    if cover.is_some() {
+        args.extend([
+            "-map", "1:v", "-c:v", "copy", "-disposition:v", "attached_pic",
+            "-metadata:s:v", "title=Album cover", "-metadata:s:v", "comment=Cover (front)",
+        ].map(String::from));
+    }
+
+    // 原檔的標籤 (整支影片的標題等) 不沿用，改寫入各曲目自己的資訊
+    args.extend(["-map_metadata".into(), "-1".into(), "-id3v2_version".into(), "3".into()]);
+    let title = if chapter.title.trim().is_empty() { format!("Track {}", number) } else { chapter.title.clone() };
+    let mut tags = vec![
+        format!("title={}", title),
+        format!("track={}/{}", number, total),
+        format!("album={}", album.album),
+    ];
+    if let Some(artist) = &album.artist {
+        tags.push(format!("artist={}", artist));
+        tags.push(format!("album_artist={}", artist));
+    }
+    for tag in tags {
+        args.extend(["-metadata".into(), tag]);
+    }
+    args.push(dest.to_string_lossy().to_string());
+    args
+}
+
+/// 將 source 依章節分割到 source 所在的資料夾；成功後刪除原本的整檔，並把封面改名為 cover.jpg
+pub async fn split(
+    env: &AppEnv,
+    sink: &dyn EventSink,
+    lang: &str,
+    source: &Path,
+    chapters: &[Chapter],
+    album: &AlbumInfo,
+) -> Result<Vec<PathBuf>, String> {
+    let dir = source.parent().ok_or("Invalid output path")?;
+    let ext = source.extension().and_then(|e| e.to_str()).unwrap_or("mp3");
+
+    // yt-dlp --write-thumbnail 會把封面存成與音訊同名的 .jpg
+    let thumb = source.with_extension("jpg");
+    let cover = dir.join(COVER_FILE);
+    if thumb.exists() && std::fs::rename(&thumb, &cover).is_err() {
+        let _ = std::fs::copy(&thumb, &cover);
+    }
+    let cover = cover.exists().then_some(cover);
+
+    let total = chapters.len();
+    let mut tracks = Vec::with_capacity(total);
+    for (i, chapter) in chapters.iter().enumerate() {
+        let number = i + 1;
+        events::log(sink, get_msg(
+            lang,
+            &format!("✂️ 分割曲目 {}/{}：{}", number, total, chapter.title),
+            &format!("✂️ Splitting track {}/{}: {}", number, total, chapter.title),
+        ));
+
+        let dest = dir.join(track_file_name(number, &chapter.title, ext));
+        let mut cmd = process::command(env.ffmpeg());
+        cmd.args(track_args(source, cover.as_deref(), chapter, number, total, album, &dest));
+        let outcome = process::run_streaming(cmd, |_| {}).await?;
+        if !outcome.status.success() {
+            let detail = outcome.error_message();
+            return Err(format!("ffmpeg failed on track {}: {}", number, detail));
+        }
+        tracks.push(dest);
+    }
+
+    let _ = std::fs::remove_file(source);
+    Ok(tracks)
+}
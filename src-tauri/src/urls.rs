@@ -0,0 +1,312 @@
+// [2026-10-19 新增] 網址檢查：只允許 http / https，其餘 (javascript:, file:, 自訂協定...) 一律拒絕
+// [2026-10-19 擴充] 網址正規化：去除追蹤參數、統一 YouTube 各種變體、取出影片 / 播放清單 ID，
+// 並可選擇以 yt-dlp --list-extractors 的快取預先確認網站是否支援，在啟動 yt-dlp 之前就回傳明確的錯誤
+use crate::env::AppEnv;
+use crate::process;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use url::Url;
+
+pub const ALLOWED_SCHEMES: [&str; 2] = ["http", "https"];
+
+/// 追蹤用參數 (不影響內容，正規化時移除)
+const TRACKING_PARAMS: [&str; 10] = [
+    "fbclid", "gclid", "dclid", "msclkid", "igshid", "mc_cid", "mc_eid",
+    "si", "ref_src", "share_source",
+];
+
+/// [2026-10-19 修正] 只在 YouTube 上是追蹤用途的參數；其他網站可能用來選擇內容，保留不動
+const YOUTUBE_TRACKING_PARAMS: [&str; 4] = ["feature", "pp", "ref", "spm"];
+
+/// [2026-10-19 修改] 網域 → yt-dlp extractor 名稱 (小寫、去除子名稱)。
+/// 以完整的註冊網域比對 (子網域亦可)，不再由主機名稱的標籤猜測 extractor
+const SITE_EXTRACTORS: &[(&str, &str)] = &[
+    ("youtube.com", "youtube"), ("youtu.be", "youtube"),
+    ("vimeo.com", "vimeo"),
+    ("bilibili.com", "bilibili"), ("b23.tv", "bilibili"),
+    ("facebook.com", "facebook"), ("fb.watch", "facebook"),
+    ("instagram.com", "instagram"),
+    ("twitter.com", "twitter"), ("x.com", "twitter"),
+    ("tiktok.com", "tiktok"),
+    ("twitch.tv", "twitch"),
+    ("dailymotion.com", "dailymotion"), ("dai.ly", "dailymotion"),
+    ("soundcloud.com", "soundcloud"),
+    ("bandcamp.com", "bandcamp"),
+    ("mixcloud.com", "mixcloud"),
+    ("reddit.com", "reddit"), ("redd.it", "reddit"),
+    ("nicovideo.jp", "niconico"), ("nico.ms", "niconico"),
+    ("bbc.co.uk", "bbc"), ("bbc.com", "bbc"),
+    ("ted.com", "ted"),
+    ("streamable.com", "streamable"),
+    ("rumble.com", "rumble"),
+    ("odysee.com", "lbry"),
+    ("kick.com", "kick"),
+    ("archive.org", "archive.org"),
+    ("vk.com", "vk"),
+    ("weibo.com", "weibo"),
+    ("douyin.com", "douyin"),
+    ("loom.com", "loom"),
+];
+
+const EXTRACTORS_FILE: &str = "extractors.txt";
+const EXTRACTORS_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 3600);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum UrlError {
+    Empty,
+    Invalid(String),
+    UnsupportedScheme(String),
+    NoHost,
+    InvalidVideoId(String),
+    UnsupportedSite(String),
+}
+
+impl std::fmt::Display for UrlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UrlError::Empty => write!(f, "Empty URL"),
+            UrlError::Invalid(e) => write!(f, "Invalid URL: {}", e),
+            UrlError::UnsupportedScheme(s) => write!(f, "Unsupported URL scheme: {}", s),
+            UrlError::NoHost => write!(f, "URL has no host"),
+            UrlError::InvalidVideoId(id) => write!(f, "Invalid video id: {}", id),
+            UrlError::UnsupportedSite(site) => write!(f, "Site not supported by yt-dlp: {}", site),
+        }
+    }
+}
+
+// 讓既有以 String 為錯誤型別的函數可以直接使用 ?
+impl From<UrlError> for String {
+    fn from(e: UrlError) -> Self {
+        e.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlKind {
+    Video,
+    /// 影片網址中附帶 list= (播放清單中的某一部)
+    VideoInPlaylist,
+    Playlist,
+    Channel,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NormalizedUrl {
+    /// 正規化後的網址 (作為快取 / 去重的 key)
+    pub url: String,
+    pub original: String,
+    /// 去除 www. / m. 後的主機名稱
+    pub site: String,
+    pub kind: UrlKind,
+    pub video_id: Option<String>,
+    pub playlist_id: Option<String>,
+}
+
+/// 驗證要交給外部程式 (瀏覽器 / yt-dlp) 的網址，回傳重新序列化後的標準形式。
+/// 重新序列化會把空白等字元百分比編碼，且外部程式一律以參數陣列呼叫，不經過 shell。
+pub fn validate_external_url(raw: &str) -> Result<Url, UrlError> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err(UrlError::Empty);
+    }
+    if trimmed.chars().any(|c| c.is_control()) {
+        return Err(UrlError::Invalid("control characters".into()));
+    }
+    let url = Url::parse(trimmed).map_err(|e| UrlError::Invalid(e.to_string()))?;
+    if !ALLOWED_SCHEMES.contains(&url.scheme()) {
+        return Err(UrlError::UnsupportedScheme(url.scheme().to_string()));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(UrlError::NoHost);
+    }
+    Ok(url)
+}
+
+fn is_youtube_id(id: &str) -> bool {
+    id.len() == 11 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn strip_host_prefix(host: &str) -> &str {
+    host.trim_start_matches("www.").trim_start_matches("m.")
+}
+
+fn is_youtube_host(site: &str) -> bool {
+    matches!(site, "youtube.com" | "music.youtube.com" | "youtu.be" | "youtube-nocookie.com")
+}
+
+fn youtube_video(id: &str, list: Option<String>) -> Result<(String, UrlKind, Option<String>, Option<String>), UrlError> {
+    if !is_youtube_id(id) {
+        return Err(UrlError::InvalidVideoId(id.to_string()));
+    }
+    match list {
+        Some(list) => Ok((
+            format!("https://www.youtube.com/watch?v={}&list={}", id, list),
+            UrlKind::VideoInPlaylist,
+            Some(id.to_string()),
+            Some(list),
+        )),
+        None => Ok((format!("https://www.youtube.com/watch?v={}", id), UrlKind::Video, Some(id.to_string()), None)),
+    }
+}
+
+fn normalize_youtube(url: &Url, site: &str) -> Result<(String, UrlKind, Option<String>, Option<String>), UrlError> {
+    let query = |key: &str| url.query_pairs().find(|(k, _)| k == key).map(|(_, v)| v.to_string()).filter(|v| !v.is_empty());
+    let segments: Vec<&str> = url.path_segments().map(|s| s.filter(|p| !p.is_empty()).collect()).unwrap_or_default();
+    let list = query("list");
+
+    if site == "youtu.be" {
+        let id = segments.first().copied().unwrap_or("");
+        return youtube_video(id, list);
+    }
+
+    match segments.as_slice() {
+        ["watch"] => match query("v") {
+            Some(id) => youtube_video(&id, list),
+            None => Err(UrlError::InvalidVideoId(String::new())),
+        },
+        ["shorts" | "live" | "embed" | "v", id, ..] => youtube_video(id, list),
+        ["playlist"] => match list {
+            Some(list) => Ok((format!("https://www.youtube.com/playlist?list={}", list), UrlKind::Playlist, None, Some(list))),
+            None => Err(UrlError::Invalid("playlist without list id".into())),
+        },
+        [first, ..] if first.starts_with('@') || matches!(*first, "channel" | "c" | "user") => {
+            let path = segments.join("/");
+            Ok((format!("https://www.youtube.com/{}", path), UrlKind::Channel, None, None))
+        }
+        // [2026-10-19 修正] 其他頁面 (music.youtube.com/browse、/results?search_query= 等) 保留原本的主機與查詢參數
+        _ => {
+            let mut kept = url.clone();
+            strip_tracking_params(&mut kept, true);
+            Ok((kept.to_string(), UrlKind::Other, None, list))
+        }
+    }
+}
+
+/// 移除追蹤參數 (utm_* 與 TRACKING_PARAMS，YouTube 另加 YOUTUBE_TRACKING_PARAMS)，沒有剩下的參數時一併移除問號
+fn strip_tracking_params(url: &mut Url, youtube: bool) {
+    let kept: Vec<(String, String)> = url.query_pairs()
+        .filter(|(k, _)| !k.starts_with("utm_") && !TRACKING_PARAMS.contains(&k.as_ref()))
+        .filter(|(k, _)| !youtube || !YOUTUBE_TRACKING_PARAMS.contains(&k.as_ref()))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+}
+
+/// 正規化網址：移除追蹤參數與片段、YouTube 變體 (youtu.be / shorts / music / m.) 統一為標準形式
+pub fn normalize(raw: &str) -> Result<NormalizedUrl, UrlError> {
+    let mut url = validate_external_url(raw)?;
+    url.set_fragment(None);
+    let host = url.host_str().unwrap_or("").to_ascii_lowercase();
+    let site = strip_host_prefix(&host).to_string();
+
+    if is_youtube_host(&site) {
+        let (canonical, kind, video_id, playlist_id) = normalize_youtube(&url, &site)?;
+        return Ok(NormalizedUrl {
+            url: canonical,
+            original: raw.trim().to_string(),
+            site: "youtube.com".into(),
+            kind,
+            video_id,
+            playlist_id,
+        });
+    }
+
+    strip_tracking_params(&mut url, false);
+
+    Ok(NormalizedUrl {
+        url: url.to_string(),
+        original: raw.trim().to_string(),
+        site,
+        kind: UrlKind::Other,
+        video_id: None,
+        playlist_id: None,
+    })
+}
+
+/// 網站對應的 extractor；site 為去除 www. / m. 的主機名稱
+pub fn site_extractor(site: &str) -> Option<&'static str> {
+    let site = site.to_ascii_lowercase();
+    SITE_EXTRACTORS.iter()
+        .find(|(domain, _)| site == *domain || site.ends_with(&format!(".{}", domain)))
+        .map(|(_, name)| *name)
+}
+
+/// yt-dlp 支援的 extractor 名稱清單 (小寫，去除 ":tab" 之類的子名稱)
+#[derive(Debug, Clone, Default)]
+pub struct ExtractorList {
+    names: Vec<String>,
+}
+
+impl ExtractorList {
+    pub fn parse(text: &str) -> Self {
+        let mut names: Vec<String> = text.lines()
+            .map(|l| l.trim().split(':').next().unwrap_or("").split_whitespace().next().unwrap_or("").to_ascii_lowercase())
+            .filter(|n| !n.is_empty() && n != "generic")
+            .collect();
+        names.sort();
+        names.dedup();
+        Self { names }
+    }
+
+    /// [2026-10-19 修正] 只有對照表中的網域才能確定對應的 extractor：該 extractor 不在清單中時才視為不支援。
+    /// 其他網域可能由清單中的任何 extractor (或 generic) 處理，一律交由 yt-dlp 判斷
+    pub fn supports(&self, site: &str) -> bool {
+        site_extractor(site).is_none_or(|name| self.names.iter().any(|n| n == name))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// 讀取使用者資料目錄中的快取；不存在或超過一週則重新執行 yt-dlp --list-extractors
+    pub async fn load_or_refresh(env: &AppEnv) -> Result<Self, String> {
+        let cache = env.data_dir.join(EXTRACTORS_FILE);
+        if let Some(list) = Self::read_fresh(&cache) {
+            return Ok(list);
+        }
+
+        let mut cmd = process::command(env.yt_dlp());
+        cmd.args(["--no-config", "--list-extractors"]);
+        let timeout = Duration::from_secs(env.settings.timeouts.metadata_secs);
+        let output = process::run_capture(cmd, timeout).await?;
+        let text = String::from_utf8_lossy(&output.stdout).to_string();
+        let list = Self::parse(&text);
+        if !list.is_empty() {
+            let _ = std::fs::create_dir_all(&env.data_dir);
+            let _ = std::fs::write(&cache, text);
+        }
+        Ok(list)
+    }
+
+    fn read_fresh(cache: &Path) -> Option<Self> {
+        let modified = std::fs::metadata(cache).and_then(|m| m.modified()).ok()?;
+        let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+        if age > EXTRACTORS_MAX_AGE {
+            return None;
+        }
+        let list = Self::parse(&std::fs::read_to_string(cache).ok()?);
+        (!list.is_empty()).then_some(list)
+    }
+}
+
+/// 正規化並 (可選) 確認網站在 yt-dlp 的支援清單中
+pub async fn prevalidate(env: &AppEnv, raw: &str, check_extractors: bool) -> Result<NormalizedUrl, UrlError> {
+    let normalized = normalize(raw)?;
+    if check_extractors && normalized.site != "youtube.com" {
+        // 無法取得清單時不阻擋，交由 yt-dlp 判斷
+        if let Ok(list) = ExtractorList::load_or_refresh(env).await {
+            if !list.is_empty() && !list.supports(&normalized.site) {
+                return Err(UrlError::UnsupportedSite(normalized.site));
+            }
+        }
+    }
+    Ok(normalized)
+}
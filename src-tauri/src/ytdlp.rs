@@ -0,0 +1,747 @@
+// [2026-10-19 重構] yt-dlp 解析與下載的核心邏輯
+// 從 lib.rs 的指令中拆出，不再依賴 tauri::Window，方便以假的 yt-dlp 進行整合測試
+use crate::archival;
+use crate::auth;
+use crate::bandwidth;
+use crate::cache;
+use crate::diskspace::{self, SizeEstimate, SpaceCheck};
+use crate::env::AppEnv;
+use crate::events::{self, get_msg, DownloadPayload, EventSink, LivePayload};
+use crate::history;
+use crate::hooks::PendingHooks;
+use crate::live::{self, LiveStatus};
+use crate::medialib;
+use crate::network;
+use crate::organizer;
+use crate::process::{self, StopSignal, StreamLine};
+use crate::savepath;
+use crate::sections::Sections;
+use crate::sponsorblock::{self, Segment};
+use crate::tracks::{self, AlbumInfo};
+use crate::urls;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VideoFormat {
+    pub id: String,
+    pub ext: String,
+    pub resolution: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub title: String,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VideoMetadata {
+    pub title: String,
+    pub thumbnail: String,
+    pub formats: Vec<VideoFormat>,
+    // [2026-10-19 新增] 影片長度與章節，供前端選擇片段下載
+    pub duration: Option<f64>,
+    pub chapters: Vec<Chapter>,
+    // [2026-10-19 新增] 直播狀態與預定開播時間 (Unix 秒)
+    pub live_status: LiveStatus,
+    pub scheduled_start: Option<i64>,
+}
+
+/// [2026-10-19 新增] 下載的進階選項；前端未傳入時全部使用預設值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadOptions {
+    /// 起始時間 ("1:23" / "83")
+    pub start_time: Option<String>,
+    /// 結束時間
+    pub end_time: Option<String>,
+    /// 只下載這些章節 (章節名稱，來自 analyze_video)
+    pub chapters: Vec<String>,
+    /// [2026-10-19 新增] 以索引 (analyze_video 的 chapters 順序，由 0 開始) 選擇章節；標題重複時使用
+    pub chapter_indices: Vec<usize>,
+    /// 以 ffmpeg 在切點重新編碼關鍵影格，切割更精準但較慢
+    pub precise_cuts: bool,
+    /// 音訊模式：依章節分割成多個曲目，放在以影片標題命名的資料夾
+    pub split_chapters: bool,
+    /// SponsorBlock：從輸出中剪掉的類別
+    pub sponsorblock_remove: Vec<String>,
+    /// SponsorBlock：標記為章節的類別
+    pub sponsorblock_mark: Vec<String>,
+    /// 直播：從直播開頭錄起 (否則從現在開始)
+    pub live_from_start: bool,
+    /// 預定直播：等待開播後自動開始錄製
+    pub wait_for_start: bool,
+    /// 此任務的速率上限 (bytes/s)，與全域設定取較小者
+    pub rate_limit: Option<u64>,
+    /// 下載完成後記錄到 download archive (訂閱用來判斷是否已下載)
+    pub use_archive: bool,
+    /// 自訂檔名 (不含副檔名)，取代預設的「標題_畫質」
+    pub file_name: Option<String>,
+    /// [2026-10-19 新增] 媒體庫輸出：Show/Season YYYY/Show - SYYYYEmmdd - Title，並寫入 .nfo 與縮圖 (Jellyfin / Kodi)
+    pub media_library: bool,
+    /// [2026-10-19 新增] 典藏：在下載檔旁寫入 .info.json / .description
+    pub write_info_json: bool,
+    pub write_description: bool,
+    /// 抓取留言並存入 .info.json；max_comments 為 None 時不限數量
+    pub write_comments: bool,
+    pub max_comments: Option<u32>,
+}
+
+/// 一次下載任務所需的參數 (對應前端 download_video 的引數)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadRequest {
+    pub url: String,
+    pub mode: String,
+    pub quality: String,
+    pub path: String,
+    pub lang: String,
+    #[serde(default)]
+    pub options: DownloadOptions,
+    /// [2026-10-19 新增] 使用的下載設定檔 (決定下載後執行的掛鉤)；未指定時使用 "default"
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+/// [2026-10-19 新增] 下載任務的結果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadResult {
+    /// 影片標題 (下載紀錄 / 匯出播放清單使用)
+    pub title: String,
+    /// 輸出檔的長度 (秒)；片段或分割成多檔時為 None
+    pub duration: Option<f64>,
+    /// 產生的檔案 (分割章節時為各曲目)
+    pub files: Vec<PathBuf>,
+    /// 依 SponsorBlock 剪掉的片段 (以原影片的時間表示)
+    pub removed_segments: Vec<Segment>,
+    /// [2026-10-19 新增] 整理規則與掛鉤使用的來源資訊
+    pub video_id: Option<String>,
+    pub uploader: Option<String>,
+    pub playlist: Option<String>,
+    /// YYYYMMDD
+    pub upload_date: Option<String>,
+    /// [2026-10-19 新增] 寫入的 .info.json (供之後還原中繼資料)
+    pub info_file: Option<PathBuf>,
+    /// [2026-10-19 新增] 直播錄製被停止而提前結束 (已封裝錄到的部分)
+    pub stopped: bool,
+}
+
+impl DownloadRequest {
+    pub fn new(url: impl Into<String>, mode: impl Into<String>, quality: impl Into<String>, path: impl Into<String>, lang: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            mode: mode.into(),
+            quality: quality.into(),
+            path: path.into(),
+            lang: lang.into(),
+            options: DownloadOptions::default(),
+            profile: None,
+        }
+    }
+}
+
+/// 將 Windows 檔名不允許的字元換成底線
+pub fn sanitize_file_name(name: &str) -> String {
+    name.replace(['\\', '/', ':', '*', '?', '"', '<', '>', '|'], "_")
+}
+
+/// [2026-10-19 新增] 多段輸出時 yt-dlp 實際寫入的第 number 段 (由 1 開始)：stem_01.ext、stem_02.ext…
+pub fn section_file_path(final_path: &Path, number: usize) -> PathBuf {
+    let stem = final_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = final_path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+    final_path.with_file_name(format!("{}_{:02}.{}", stem, number, ext))
+}
+
+/// [2026-10-19 新增] sections 大於 1 時檢查的是各段的檔名，而不是不會被寫入的 final_path 本身
+fn is_free(path: &Path, sections: usize) -> bool {
+    if sections > 1 {
+        (1..=sections).all(|n| !section_file_path(path, n).exists())
+    } else {
+        !path.exists()
+    }
+}
+
+/// [2026-10-19 新增] 使用者指定的檔名；已存在時加上序號
+pub fn get_unique_named_path(base_path: &Path, name: &str, ext: &str, sections: usize) -> PathBuf {
+    let name = sanitize_file_name(name);
+    let stem = name.strip_suffix(&format!(".{}", ext)).unwrap_or(&name);
+    let mut full_path = base_path.join(format!("{}.{}", stem, ext));
+    let mut counter = 1;
+    while !is_free(&full_path, sections) {
+        full_path = base_path.join(format!("{}_{}.{}", stem, counter, ext));
+        counter += 1;
+    }
+    full_path
+}
+
+// [2026-10-19 修改] 加上 sections：多段輸出時以 stem_NN 的檔名判斷是否重複
+pub fn get_unique_path(base_path: &Path, title: &str, quality: &str, ext: &str, sections: usize) -> PathBuf {
+    let safe_title = sanitize_file_name(title);
+    let mut counter = 0;
+    loop {
+        let filename = if counter == 0 {
+            format!("{}_{}.{}", safe_title, quality, ext)
+        } else {
+            format!("{}_{}_{}.{}", safe_title, quality, counter, ext)
+        };
+        let full_path = base_path.join(filename);
+        if is_free(&full_path, sections) {
+            return full_path;
+        }
+        counter += 1;
+    }
+}
+
+/// 取得影片資訊 (yt-dlp --dump-json)；優先使用快取，url 應為正規化後的網址
+// [2026-10-19 修改] 改用非阻塞的子程序並加上逾時，避免網站無回應時指令永遠不返回
+pub async fn fetch_info(env: &AppEnv, url: &str) -> Result<Arc<serde_json::Value>, String> {
+    if let Some(info) = env.metadata_cache.get(url) {
+        return Ok(info);
+    }
+
+    let mut cmd = process::command(env.yt_dlp());
+    // [2026-01-18 修正] 加入 --no-config 確保穩定性
+    cmd.args(["--no-config", "--quiet", "--no-warnings", "--skip-download"]);
+    // [2026-10-19 新增] 會員限定 / 年齡限制的影片需要登入資訊
+    let auth = auth::args_for_url(env, url);
+    cmd.args(&auth.args);
+    cmd.args(network::yt_dlp_args(&env.settings.network, url));
+    cmd.args(["--dump-json", "--", url]);
+
+    let timeout = Duration::from_secs(env.settings.timeouts.metadata_secs);
+    let output = process::run_capture(cmd, timeout).await?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() { return Err("Empty Output".into()); }
+
+    let json: serde_json::Value = serde_json::from_str(&stdout).map_err(|e| e.to_string())?;
+    Ok(env.metadata_cache.insert(url, json))
+}
+
+/// yt-dlp 的輸出路徑其實是模板，檔名中的 % 必須跳脫
+pub fn escape_output_template(path: &str) -> String {
+    path.replace('%', "%%")
+}
+
+pub fn parse_chapters(json: &serde_json::Value) -> Vec<Chapter> {
+    json["chapters"].as_array().map(|list| {
+        list.iter().map(|c| Chapter {
+            title: c["title"].as_str().unwrap_or("").to_string(),
+            start_time: c["start_time"].as_f64().unwrap_or(0.0),
+            end_time: c["end_time"].as_f64().unwrap_or(0.0),
+        }).collect()
+    }).unwrap_or_default()
+}
+
+/// 將 yt-dlp --dump-json 的輸出整理成前端需要的格式清單
+pub fn parse_metadata(json: &serde_json::Value) -> VideoMetadata {
+    let mut video_formats = std::collections::HashMap::new();
+    let mut audio_formats = std::collections::HashMap::new();
+
+    if let Some(fmts) = json["formats"].as_array() {
+        for f in fmts {
+            let vcodec = f["vcodec"].as_str().unwrap_or("none");
+            let acodec = f["acodec"].as_str().unwrap_or("none");
+
+            if vcodec != "none" {
+                let res = f["resolution"].as_str().or(f["format_note"].as_str()).unwrap_or("unknown");
+                video_formats.entry(res.to_string()).or_insert(f["format_id"].as_str().unwrap_or("").to_string());
+            } else if acodec != "none" && vcodec == "none" {
+                let abr = f["abr"].as_f64().or(f["tbr"].as_f64()).unwrap_or(0.0);
+                let bitrate = format!("{}k", abr as i32);
+                audio_formats.entry(bitrate).or_insert(f["format_id"].as_str().unwrap_or("").to_string());
+            }
+        }
+    }
+
+    let get_num = |s: &str| s.chars().filter(|c| c.is_ascii_digit()).collect::<String>().parse::<i32>().unwrap_or(0);
+
+    let mut v_list: Vec<VideoFormat> = video_formats.into_iter()
+        .map(|(res, id)| VideoFormat { id, ext: "mp4".into(), resolution: res })
+        .collect();
+    v_list.sort_by_key(|f| std::cmp::Reverse(get_num(&f.resolution)));
+
+    let mut a_list: Vec<VideoFormat> = audio_formats.into_iter()
+        .map(|(bit, id)| VideoFormat { id, ext: "mp3".into(), resolution: bit })
+        .collect();
+    a_list.sort_by_key(|f| std::cmp::Reverse(get_num(&f.resolution)));
+
+    let mut final_formats = v_list;
+    final_formats.extend(a_list);
+
+    VideoMetadata {
+        title: json["title"].as_str().unwrap_or("未知標題").into(),
+        thumbnail: json["thumbnail"].as_str().unwrap_or("").into(),
+        formats: final_formats,
+        duration: json["duration"].as_f64(),
+        chapters: parse_chapters(json),
+        live_status: LiveStatus::from_info(json),
+        scheduled_start: live::scheduled_start(json),
+    }
+}
+
+pub async fn analyze(env: &AppEnv, sink: &dyn EventSink, url: &str, lang: &str) -> Result<VideoMetadata, String> {
+    // [2026-10-19 新增] 先正規化網址 (只接受 http/https)，避免以 "-" 開頭的字串被 yt-dlp 當成參數
+    let normalized = urls::normalize(url)?;
+    let url = normalized.url.as_str();
+    let yt_exe = env.yt_dlp();
+
+    if !yt_exe.exists() {
+        events::core_status(sink, false);
+        events::log(sink, get_msg(lang, "❌ 找不到 yt-dlp.exe", "❌ yt-dlp.exe not found"));
+        return Err("Missing Core".into());
+    }
+
+    events::log(sink, get_msg(lang, "🔍 正在解析影片...", "🔍 Analyzing..."));
+
+    // [2026-10-19 修改] 透過快取取得資訊，同一網址短時間內不重複呼叫 yt-dlp
+    let json = fetch_info(env, url).await?;
+    let metadata = parse_metadata(&json);
+
+    events::log(sink, get_msg(lang, "✅ 解析完成", "✅ Analysis complete"));
+    Ok(metadata)
+}
+
+/// [2026-10-19 新增] 下載前預估大小與目的地的可用空間 (供前端在開始前顯示)
+pub async fn estimate_size(env: &AppEnv, req: &DownloadRequest) -> Result<SizeEstimate, String> {
+    let normalized = urls::normalize(&req.url)?;
+    let info_json = fetch_info(env, &normalized.url).await?;
+    let sections = Sections::from_options(&req.options, info_json["duration"].as_f64(), &parse_chapters(&info_json))?;
+    Ok(diskspace::estimate(&info_json, req, sections.as_ref()).with_available(Path::new(&req.path)))
+}
+
+/// 多檔輸出時，讀取 yt-dlp 以 --print-to-file 記錄的本次輸出檔案
+// [2026-10-19 修正] 不再列出所有 stem_ 開頭的檔案，資料夾中既有的同名檔案不會被算進來
+fn section_files(printed: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for line in std::fs::read_to_string(printed).unwrap_or_default().lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let path = PathBuf::from(line);
+        if path.is_file() && !files.contains(&path) {
+            files.push(path);
+        }
+    }
+    files
+}
+
+pub async fn download(env: &AppEnv, sink: &dyn EventSink, req: &DownloadRequest) -> Result<DownloadResult, String> {
+    download_with_stop(env, sink, req, &StopSignal::default()).await
+}
+
+/// [2026-10-19 新增] 可由使用者中途停止的下載；直播錄製停止時會封裝已錄到的內容
+pub async fn download_with_stop(env: &AppEnv, sink: &dyn EventSink, req: &DownloadRequest, stop: &StopSignal) -> Result<DownloadResult, String> {
+    let (result, hooks) = download_deferring_hooks(env, sink, req, stop).await;
+    hooks.run(env, sink).await;
+    result
+}
+
+/// [2026-10-19 新增] 同 download_with_stop，但掛鉤交由呼叫端在釋放下載鎖之後執行
+pub async fn download_deferring_hooks(env: &AppEnv, sink: &dyn EventSink, req: &DownloadRequest, stop: &StopSignal) -> (Result<DownloadResult, String>, PendingHooks) {
+    // [2026-10-19 新增] 先確認儲存資料夾可以寫入；改用預設資料夾時紀錄與結果都以實際的資料夾為準
+    // [2026-10-19 新增] 指定了不存在的下載設定檔時不開始下載
+    let req = &match env.settings.profile(req.profile.as_deref()).and_then(|_| prepare_save_path(env, sink, req)) {
+        Ok(req) => req,
+        Err(e) => {
+            let result = Err(e);
+            history::record(env, req, &result);
+            return (result, PendingHooks::default());
+        }
+    };
+    let mut result = run_download(env, sink, req, stop).await;
+    // [2026-10-19 新增] 依整理規則搬移完成的檔案，紀錄中保存整理後的位置
+    if let Ok(done) = result.as_mut() {
+        organizer::apply(&env.settings.organizer_rules, sink, req, done);
+    }
+    // [2026-10-19 新增] 完成與失敗都寫入下載紀錄；被停止 / 暫停的任務之後可能續傳，不算結束
+    if result.is_ok() || !stop.is_stopped() {
+        // [2026-10-19 新增] 依下載設定檔執行掛鉤 (指令 / Webhook)
+        let history_id = history::record(env, req, &result);
+        let hooks = PendingHooks::new(env, req, &result, history_id);
+        return (result, hooks);
+    }
+    (result, PendingHooks::default())
+}
+
+/// [2026-10-19 新增] 檢查儲存資料夾 (依設定建立、寫入測試)，無法使用時改用設定中的預設資料夾
+fn prepare_save_path(env: &AppEnv, sink: &dyn EventSink, req: &DownloadRequest) -> Result<DownloadRequest, String> {
+    let lang = req.lang.as_str();
+    let settings = &env.settings.downloads;
+    let save = savepath::resolve(settings, &req.path, settings.create_missing).map_err(|e| {
+        get_msg(lang, &format!("❌ 無法使用儲存資料夾：{}", e), &format!("❌ Cannot use the download folder: {}", e))
+    })?;
+    if save.created {
+        events::log(sink, get_msg(lang, &format!("📁 已建立資料夾：{}", save.path), &format!("📁 Created folder: {}", save.path)));
+    }
+    if let Some(reason) = save.reason.as_deref().filter(|_| save.fallback) {
+        events::log(sink, get_msg(
+            lang,
+            &format!("⚠️ {}，改存到預設資料夾：{}", reason, save.path),
+            &format!("⚠️ {}, saving to the default folder instead: {}", reason, save.path),
+        ));
+    }
+    Ok(DownloadRequest { path: save.path, ..req.clone() })
+}
+
+async fn run_download(env: &AppEnv, sink: &dyn EventSink, req: &DownloadRequest, stop: &StopSignal) -> Result<DownloadResult, String> {
+    let lang = req.lang.as_str();
+    let normalized = urls::normalize(&req.url)?;
+    let url = normalized.url.as_str();
+    let mode = req.mode.as_str();
+    let quality = req.quality.as_str();
+
+    // [2026-01-18 防呆修正] 檢查 quality 是否為空，避免因為前端 reset 導致的邏輯錯誤
+    if quality.is_empty() {
+        return Err(get_msg(lang, "❌ 錯誤：未選擇下載品質或格式", "❌ Error: Quality or format not selected"));
+    }
+
+    let yt_exe = env.yt_dlp();
+    let ff_exe = env.ffmpeg();
+
+    events::log(sink, get_msg(lang, "⚙️ 準備下載...", "⚙️ Preparing..."));
+
+    // [2026-10-19 修改] 沿用 analyze_video 快取的資訊，不再重跑一次 --dump-json
+    let info_json = fetch_info(env, url).await?;
+    let title = info_json["title"].as_str().unwrap_or("unknown");
+
+    // [2026-10-19 新增] 片段 / 章節下載：先驗證再開始，錯誤的時間不會啟動 yt-dlp
+    let chapters = parse_chapters(&info_json);
+    let sections = Sections::from_options(&req.options, info_json["duration"].as_f64(), &chapters)?;
+
+    // [2026-10-19 新增] 依章節分割曲目：只限音訊模式，且需要影片本身有章節
+    let split = req.options.split_chapters;
+    if split {
+        if mode != "audio" {
+            return Err(get_msg(lang, "❌ 只有音訊模式可以依章節分割", "❌ Chapter splitting is only available in audio mode"));
+        }
+        if sections.is_some() {
+            return Err(get_msg(lang, "❌ 分割章節無法與片段範圍或章節選擇同時使用", "❌ Chapter splitting cannot be combined with a time range or chapter selection"));
+        }
+        if chapters.is_empty() {
+            return Err(get_msg(lang, "❌ 此影片沒有章節，無法分割", "❌ This video has no chapters to split"));
+        }
+    }
+
+    // [2026-10-19 新增] SponsorBlock 只支援 YouTube；剪掉片段會改變時間軸，不能再依原章節分割
+    let sb_remove = sponsorblock::validate_categories(&req.options.sponsorblock_remove)?;
+    let sb_mark = sponsorblock::validate_categories(&req.options.sponsorblock_mark)?;
+    if split && !sb_remove.is_empty() {
+        return Err(get_msg(lang, "❌ 分割章節無法與 SponsorBlock 剪除同時使用", "❌ Chapter splitting cannot be combined with SponsorBlock removal"));
+    }
+    let sb_video_id = normalized.video_id.clone().filter(|_| normalized.site == "youtube.com");
+    if sb_video_id.is_none() && !(sb_remove.is_empty() && sb_mark.is_empty()) {
+        events::log(sink, get_msg(lang, "⚠️ SponsorBlock 只支援 YouTube，已略過", "⚠️ SponsorBlock only supports YouTube, skipped"));
+    }
+
+    // [2026-10-19 新增] 直播錄製：片段與分割需要固定長度，直播時不適用
+    let live_status = LiveStatus::from_info(&info_json);
+    let is_live = live_status.is_recording();
+    if is_live {
+        if sections.is_some() || split {
+            return Err(get_msg(lang, "❌ 直播無法使用片段或分割章節", "❌ Sections and chapter splitting are not available for live streams"));
+        }
+        if live_status == LiveStatus::IsUpcoming && !req.options.wait_for_start {
+            let when = live::scheduled_start(&info_json).map(|t| format!(" (scheduled at {})", t)).unwrap_or_default();
+            return Err(get_msg(
+                lang,
+                &format!("❌ 直播尚未開始{}，請啟用等待開播", when),
+                &format!("❌ The stream has not started yet{}; enable waiting for the stream", when),
+            ));
+        }
+    }
+
+    // [2026-10-19 新增] 媒體庫的目錄結構與檔名固定，一集只能對應一個檔案
+    let media_library = req.options.media_library;
+    if media_library && (split || sections.as_ref().is_some_and(Sections::is_multi_file) || req.options.file_name.is_some()) {
+        return Err(get_msg(
+            lang,
+            "❌ 媒體庫輸出無法與分割章節、多段下載或自訂檔名同時使用",
+            "❌ Media library output cannot be combined with chapter splitting, multiple sections or a custom file name",
+        ));
+    }
+
+    let ext = if mode == "video" { "mp4" } else { "mp3" };
+    // 檔名反映片段範圍，例如 Title_best_0h01m00s-0h01m30s.mp4
+    let name_tag = match &sections {
+        Some(sec) => format!("{}_{}", quality, sec.label()),
+        None => quality.to_string(),
+    };
+    let section_count = sections.as_ref().map_or(1, Sections::file_count);
+    let final_path = if split {
+        // 整檔先下載到專輯資料夾，分割完成後刪除
+        tracks::unique_album_dir(Path::new(&req.path), title).join(format!("{}.{}", sanitize_file_name(title), ext))
+    } else if media_library {
+        medialib::episode_path(Path::new(&req.path), &info_json, ext)
+    } else if let Some(name) = req.options.file_name.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        get_unique_named_path(Path::new(&req.path), name, ext, section_count)
+    } else {
+        get_unique_path(Path::new(&req.path), title, &name_tag, ext, section_count)
+    };
+    let mut output_template = escape_output_template(&final_path.to_string_lossy());
+    if sections.as_ref().is_some_and(Sections::is_multi_file) {
+        // 多個章節各自成檔，加上章節序號
+        let stem = final_path.with_extension("");
+        output_template = format!("{}_%(section_number)02d.%(ext)s", escape_output_template(&stem.to_string_lossy()));
+    }
+
+    // [2026-10-19 新增] 下載前確認目的地磁碟放得下 (直播無法預估大小)
+    if !is_live {
+        let estimate = diskspace::estimate(&info_json, req, sections.as_ref()).with_available(Path::new(&req.path));
+        events::emit(sink, diskspace::EVT_SIZE_ESTIMATE, &estimate);
+        let sizes = || {
+            let size = |b: Option<u64>| b.map(live::format_bytes).unwrap_or_default();
+            (size(estimate.required_bytes), size(estimate.available_bytes))
+        };
+        match estimate.check() {
+            SpaceCheck::Insufficient => {
+                let (required, available) = sizes();
+                return Err(get_msg(
+                    lang,
+                    &format!("❌ 磁碟空間不足：約需 {}，可用 {}", required, available),
+                    &format!("❌ Not enough disk space: about {} needed, {} available", required, available),
+                ));
+            }
+            SpaceCheck::Tight => {
+                let (required, available) = sizes();
+                events::log(sink, get_msg(
+                    lang,
+                    &format!("⚠️ 磁碟空間可能不足 (約需 {}，可用 {})", required, available),
+                    &format!("⚠️ Disk space may run out (about {} needed, {} available)", required, available),
+                ));
+            }
+            SpaceCheck::Ok | SpaceCheck::Unknown => {}
+        }
+    }
+
+    // 直播的串流網址與狀態變化很快，不沿用快取的資訊
+    let info_file = if is_live { None } else { Some(cache::write_info_json(&env.cache_dir().join("jobs"), url, &info_json)?) };
+    // [2026-10-19 修正] 專輯 / 節目季資料夾在所有檢查通過後才建立，避免失敗時留下空資料夾
+    if split || media_library {
+        if let Some(dir) = final_path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+    }
+
+    events::log(sink, get_msg(lang, "📥 開始下載...", "📥 Downloading..."));
+
+    let fmt_val = if mode == "video" {
+        if quality == "best" { "bestvideo+bestaudio/best".to_string() } else { format!("{}+bestaudio/best", quality) }
+    } else if quality == "bestaudio" {
+        "bestaudio/best".to_string()
+    } else {
+        quality.to_string()
+    };
+
+    let mut args: Vec<String> = vec![
+        "--no-config".into(), // [2026-01-18 修正] 加入 --no-config 確保調用 deno.exe
+        "--progress".into(), "--newline".into(),
+        "--ffmpeg-location".into(), ff_exe.to_string_lossy().to_string(),
+        "-o".into(), output_template,
+        "-f".into(), fmt_val,
+    ];
+
+    if mode == "video" {
+        args.extend(["--merge-output-format", "mp4"].map(String::from));
+    } else {
+        args.extend(["--extract-audio", "--audio-format", "mp3", "--audio-quality", "256K"].map(String::from));
+    }
+    // 暫存 netrc 保留到下載 (含重新啟動) 結束
+    let auth = auth::args_for_url(env, url);
+    args.extend(auth.args.iter().cloned());
+    args.extend(network::yt_dlp_args(&env.settings.network, url));
+    if req.options.use_archive {
+        args.extend(["--download-archive".to_string(), env.archive_path().to_string_lossy().to_string()]);
+    }
+    if let Some(sec) = &sections {
+        args.extend(sec.args(&chapters, req.options.precise_cuts));
+    }
+    // [2026-10-19 修正] 只查詢一次要剪掉的片段，同一份結果用於剪除與紀錄；查詢失敗時交由 yt-dlp 剪除，不記錄片段
+    let mut removed_segments = None;
+    if let Some(id) = &sb_video_id {
+        if !sb_remove.is_empty() {
+            match sponsorblock::fetch_segments(env, id, &sb_remove).await {
+                Ok(segments) => removed_segments = Some(segments),
+                Err(e) => events::log(sink, format!("⚠️ SponsorBlock: {}", e)),
+            }
+        }
+        args.extend(sponsorblock::args(&env.settings.sponsorblock.api_base, &sb_remove, &sb_mark, removed_segments.as_deref()));
+    }
+    if split {
+        // 封面存成與音訊同名的 jpg，分割時嵌入每一首
+        args.extend(["--write-thumbnail", "--convert-thumbnails", "jpg"].map(String::from));
+    }
+    if is_live {
+        events::log(sink, get_msg(lang, "🔴 直播錄製模式", "🔴 Live recording mode"));
+        args.extend(live::args(live_status, req.options.live_from_start));
+    }
+    // [2026-10-19 新增] 多段輸出時由 yt-dlp 列出本次實際寫入的檔案
+    let printed_files = if sections.as_ref().is_some_and(Sections::is_multi_file) {
+        let jobs = env.cache_dir().join("jobs");
+        std::fs::create_dir_all(&jobs).map_err(|e| e.to_string())?;
+        let path = jobs.join(format!("{}.files.txt", cache::cache_key(&final_path.to_string_lossy())));
+        let _ = std::fs::remove_file(&path);
+        args.extend(["--print-to-file".to_string(), "after_move:filepath".to_string(), path.to_string_lossy().to_string()]);
+        Some(path)
+    } else {
+        None
+    };
+    match &info_file {
+        // 直接載入已取得的資訊，yt-dlp 不需要再次解析網頁
+        Some(file) => args.extend(["--load-info-json".to_string(), file.to_string_lossy().to_string()]),
+        None => args.extend(["--".to_string(), url.to_string()]),
+    }
+
+    let re = Regex::new(r"\[download\]\s+(\d+\.?\d*)%\s+of\s+.*\s+at\s+(.*)\s+ETA\s+(.*)").unwrap();
+
+    // [2026-01-18 修改] 強化日誌讀取：確保所有日誌都傳回前端，用於偵測轉檔狀態
+    // [2026-10-19 修改] stdout 與 stderr 同時讀取，stderr 也即時轉發為日誌
+    // [2026-10-19 新增] 進入合併 / 轉檔階段後不再因頻寬時段變更而重新啟動
+    let post_processing = AtomicBool::new(false);
+    let on_line = |line| match line {
+        StreamLine::Stdout(content) => {
+            if bandwidth::is_post_processing(&content) {
+                post_processing.store(true, Ordering::Relaxed);
+            }
+            // 1. 進度正則判斷
+            if let Some(caps) = re.captures(&content) {
+                let progress = caps[1].parse::<f64>().unwrap_or(0.0);
+                let speed = caps[2].trim().to_string();
+                let eta = caps[3].trim().to_string();
+                events::progress(sink, DownloadPayload { progress, speed, eta });
+            }
+
+            // 2. 將原始日誌行廣播發送給所有視窗
+            events::log(sink, content);
+        }
+        StreamLine::Stderr(content) => events::log(sink, content),
+    };
+
+    // [2026-10-19 新增] 直播沒有百分比，每秒回報已錄製時間與磁碟上的大小
+    let started = Instant::now();
+    let mut ticker = tokio::time::interval(bandwidth::CHECK_INTERVAL);
+    let mut last_bytes = 0;
+    // [2026-10-19 新增] 限速：時段切換時以新的 --limit-rate 重新啟動 yt-dlp (從 .part 續傳)；
+    // 直播重新啟動會中斷錄製、片段由 ffmpeg 直接下載無法續傳，因此維持開始時的速率
+    // [2026-10-19 修正] 只在能溫和停止 yt-dlp 的平台重新啟動；其他情況新的速率從下一個下載開始套用
+    let restartable = process::SOFT_STOP && !is_live && sections.is_none();
+    let mut deferred_notice = false;
+    let mut limit = env.settings.bandwidth.effective_limit(req.options.rate_limit);
+    let outcome = loop {
+        let mut child_cmd = process::command(&yt_exe);
+        child_cmd.args(&args);
+        child_cmd.args(bandwidth::yt_dlp_args(limit));
+
+        let restart = StopSignal::default();
+        let (user_stop, restart_stop) = (stop.stopped(), restart.stopped());
+        let either_stop = async {
+            tokio::select! {
+                _ = user_stop => {}
+                _ = restart_stop => {}
+            }
+        };
+        let run = process::run_streaming_until(child_cmd, either_stop, on_line);
+        tokio::pin!(run);
+
+        let outcome = loop {
+            tokio::select! {
+                outcome = &mut run => break outcome,
+                _ = ticker.tick() => {
+                    if is_live {
+                        let bytes = live::recorded_bytes(&final_path);
+                        let speed = format!("{}/s", live::format_bytes(bytes.saturating_sub(last_bytes)));
+                        last_bytes = bytes;
+                        events::live_progress(sink, LivePayload { elapsed_secs: started.elapsed().as_secs(), bytes, speed });
+                    } else if !restart.is_stopped() {
+                        let new_limit = env.settings.bandwidth.effective_limit(req.options.rate_limit);
+                        if new_limit != limit && restartable && !post_processing.load(Ordering::Relaxed) {
+                            limit = new_limit;
+                            events::log(sink, get_msg(lang, "🚦 頻寬時段變更，以新的速率繼續下載", "🚦 Bandwidth schedule changed, resuming with the new rate"));
+                            restart.stop();
+                        } else if new_limit != limit && !deferred_notice {
+                            deferred_notice = true;
+                            events::log(sink, get_msg(lang, "🚦 新的頻寬限制將從下一個下載開始套用", "🚦 The new bandwidth limit will apply from the next download"));
+                        }
+                    }
+                }
+            }
+        };
+        if restart.is_stopped() && !stop.is_stopped() && outcome.is_ok() {
+            continue;
+        }
+        break outcome;
+    };
+    if let Some(file) = &info_file {
+        let _ = std::fs::remove_file(file);
+    }
+    let produced = printed_files.as_deref().map(section_files);
+    if let Some(file) = &printed_files {
+        let _ = std::fs::remove_file(file);
+    }
+    let outcome = outcome?;
+    if is_live {
+        env.metadata_cache.remove(url);
+    }
+
+    if outcome.stopped && !is_live {
+        return Err(get_msg(lang, "⏹️ 下載已停止", "⏹️ Download stopped"));
+    }
+    if outcome.stopped || outcome.status.success() {
+        if is_live {
+            live::finalize(env, sink, lang, &final_path, mode != "video").await?;
+        }
+        let text = |key: &str| info_json[key].as_str().map(str::to_string);
+        let mut result = DownloadResult {
+            title: title.to_string(),
+            video_id: text("id"),
+            uploader: text("uploader").or_else(|| text("channel")),
+            playlist: text("playlist_title").or_else(|| text("playlist")),
+            upload_date: text("upload_date"),
+            stopped: outcome.stopped,
+            ..Default::default()
+        };
+        if split {
+            let album = AlbumInfo {
+                album: title.to_string(),
+                artist: info_json["artist"].as_str().or(info_json["uploader"].as_str()).map(str::to_string),
+            };
+            result.files = tracks::split(env, sink, lang, &final_path, &chapters, &album).await?;
+        } else if let Some(produced) = produced {
+            result.files = produced;
+        } else {
+            result.files.push(final_path.clone());
+        }
+
+        // 記錄被剪掉的片段 (即下載前交給 yt-dlp 剪除的片段)
+        result.removed_segments = removed_segments.unwrap_or_default();
+        if !split && sections.is_none() && !is_live {
+            result.duration = info_json["duration"].as_f64().map(|d| (d - sponsorblock::removed_duration(&result.removed_segments, d)).max(0.0));
+        }
+
+        // [2026-10-19 新增] 典藏附屬檔以整檔的名稱命名 (分割曲目 / 多段下載時為原本的檔名)
+        let opts = &req.options;
+        if opts.write_info_json || opts.write_description || opts.write_comments {
+            result.info_file = archival::write(env, sink, lang, url, opts, &final_path, &info_json).await;
+        }
+
+        if media_library {
+            if let Some(media) = result.files.first() {
+                let written = medialib::write_sidecars(env, sink, lang, media, &info_json).await;
+                events::log(sink, get_msg(lang, &format!("📝 已寫入 {} 個媒體庫檔案 (.nfo / 圖片)", written.len()), &format!("📝 Wrote {} media library files (.nfo / images)", written.len())));
+            }
+        }
+
+        events::log(sink, get_msg(lang, "🎉 下載完成！", "🎉 Finished!"));
+        Ok(result)
+    } else {
+        // 快取的串流網址可能已失效，失敗後清除，下次重新解析
+        env.metadata_cache.remove(url);
+        let mut err_msg = outcome.error_message();
+        if err_msg.is_empty() {
+            err_msg = "Download process failed. Possibly network or format issues.".into();
+        }
+        Err(err_msg)
+    }
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{recorded_calls, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component};
+
+#[tokio::test]
+async fn analyze_lists_video_then_audio_formats() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    FakeYtDlp::default().install(dir.path(), &Component::YtDlp.file_name());
+    let sink = RecordingSink::default();
+
+    let meta = ytdlp::analyze(&env, &sink, "https://www.youtube.com/watch?v=dQw4w9WgXcQ", "en").await.unwrap();
+
+    assert_eq!(meta.title, "Sample Video");
+    let res: Vec<&str> = meta.formats.iter().map(|f| f.resolution.as_str()).collect();
+    assert_eq!(res, ["1920x1080", "1280x720", "160k", "129k"]);
+    assert_eq!(meta.formats[0].id, "137");
+
+    let calls = recorded_calls(dir.path());
+    assert_eq!(calls.len(), 1);
+    assert!(calls[0].contains(&"--dump-json".to_string()));
+    assert!(sink.logs().iter().any(|l| l.contains("Analysis complete")));
+}
+
+#[tokio::test]
+async fn analyze_without_yt_dlp_reports_missing_core() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    let sink = RecordingSink::default();
+
+    let err = ytdlp::analyze(&env, &sink, "https://example.com/v", "en").await.unwrap_err();
+
+    assert_eq!(err, "Missing Core");
+    assert_eq!(sink.events("core-status-update"), [serde_json::json!(false)]);
+}
+
+#[tokio::test]
+async fn analyze_rejects_empty_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    let mut fake = FakeYtDlp::default().dump_exit(1);
+    fake.dump_json = String::new();
+    fake.install(dir.path(), &Component::YtDlp.file_name());
+    let sink = RecordingSink::default();
+
+    let err = ytdlp::analyze(&env, &sink, "https://example.com/v", "en").await.unwrap_err();
+    assert_eq!(err, "Empty Output");
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::{recorded_calls, sample_info_json, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::archival;
+use cyber_ytdl_lib::history;
+use cyber_ytdl_lib::organizer::OrganizeRule;
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, DownloadRequest};
+use serde_json::json;
+
+fn archived_info() -> serde_json::Value {
+    let mut info = sample_info_json();
+    info["description"] = "Line one\nLine two".into();
+    info["upload_date"] = "20240315".into();
+    info["comments"] = json!([{ "text": "first" }, { "text": "second" }, { "text": "third" }]);
+    info["formats"][0]["cookies"] = "SID=secret".into();
+    info
+}
+
+fn request(out: &std::path::Path) -> DownloadRequest {
+    let mut req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.to_string_lossy(), "en");
+    req.options.write_info_json = true;
+    req.options.write_description = true;
+    req
+}
+
+#[tokio::test]
+async fn info_json_description_and_comments_are_saved_next_to_the_file() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    let sink = RecordingSink::default();
+    FakeYtDlp::default().dump_json(archived_info()).writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+
+    let mut req = request(out.path());
+    req.options.write_comments = true;
+    req.options.max_comments = Some(2);
+    let result = ytdlp::download(&env, &sink, &req).await.unwrap();
+
+    let info_file = out.path().join("Sample Video_best.info.json");
+    assert_eq!(result.info_file.as_ref(), Some(&info_file));
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&info_file).unwrap()).unwrap();
+    assert_eq!(saved["comment_count"], 2);
+    assert_eq!(saved["comments"][1]["text"], "second");
+    assert!(saved["formats"][0].get("cookies").is_none());
+    assert_eq!(std::fs::read_to_string(out.path().join("Sample Video_best.description")).unwrap(), "Line one\nLine two");
+
+    let comment_call = recorded_calls(bin.path()).into_iter().find(|c| c.iter().any(|a| a == "--write-comments")).unwrap();
+    assert!(comment_call.iter().any(|a| a == "youtube:max_comments=2"), "{:?}", comment_call);
+    assert_eq!(history::load(&env.history_path()).unwrap()[0].info_file.as_ref(), Some(&info_file));
+}
+
+#[tokio::test]
+async fn history_entries_are_rehydrated_from_saved_files() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    let sink = RecordingSink::default();
+    FakeYtDlp::default().dump_json(archived_info()).writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+    ytdlp::download(&env, &sink, &request(out.path())).await.unwrap();
+    ytdlp::download(&env, &sink, &DownloadRequest::new("https://vimeo.com/2", "video", "best", out.path().to_string_lossy(), "en")).await.unwrap();
+
+    // 模擬舊版紀錄：沒有 info_file 與來源資訊
+    let ids = history::update(&env.history_path(), |entries| {
+        entries[0].info_file = None;
+        entries[0].uploader = None;
+        entries[0].title = entries[0].url.clone();
+        Ok(entries.iter().map(|e| e.id.clone()).collect::<Vec<_>>())
+    }).unwrap();
+
+    let restored = archival::rehydrate(&env, &ids[0]).unwrap();
+    assert_eq!((restored.entry.title.as_str(), restored.entry.uploader.as_deref()), ("Sample Video", Some("Sample Channel")));
+    assert_eq!(restored.metadata.duration, Some(212.0));
+    assert_eq!(restored.description.as_deref(), Some("Line one\nLine two"));
+    assert_eq!(restored.comments.len(), 3);
+    let saved = &history::load(&env.history_path()).unwrap()[0];
+    assert_eq!((saved.title.as_str(), saved.info_file.as_ref()), ("Sample Video", Some(&out.path().join("Sample Video_best.info.json"))));
+
+    assert!(archival::rehydrate(&env, &ids[1]).unwrap_err().contains("No .info.json"));
+    assert!(archival::rehydrate(&env, "missing").is_err());
+}
+
+#[tokio::test]
+async fn organizer_moves_sidecars_with_the_download() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    let sink = RecordingSink::default();
+    FakeYtDlp::default().dump_json(archived_info()).writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+    env.settings.organizer_rules = vec![OrganizeRule { name: "by-year".into(), folder: "{year}".into(), file_name: Some("{title}".into()), ..Default::default() }];
+
+    let result = ytdlp::download(&env, &sink, &request(out.path())).await.unwrap();
+    let dir = out.path().join("2024");
+    assert_eq!(result.files, vec![dir.join("Sample Video.mp4")]);
+    assert_eq!(result.info_file, Some(dir.join("Sample Video.info.json")));
+    assert!(dir.join("Sample Video.description").exists());
+    assert!(!out.path().join("Sample Video_best.info.json").exists());
+    let id = history::load(&env.history_path()).unwrap()[0].id.clone();
+    assert_eq!(archival::rehydrate(&env, &id).unwrap().entry.info_file, Some(dir.join("Sample Video.info.json")));
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::{recorded_calls, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, CredentialStore, DownloadRequest};
+
+const COOKIES: &str = "# Netscape HTTP Cookie File\n.youtube.com\tTRUE\t/\tTRUE\t0\tSID\tsecret\n#HttpOnly_.youtube.com\tTRUE\t/\tTRUE\t0\tHSID\tsecret2\n";
+
+fn value_after<'a>(call: &'a [String], flag: &str) -> Option<&'a str> {
+    call.windows(2).find(|w| w[0] == flag).map(|w| w[1].as_str())
+}
+
+#[cfg(unix)]
+fn mode(path: &std::path::Path) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+}
+
+#[tokio::test]
+async fn imported_cookies_are_private_and_used_for_analyze_and_download() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().install(bin.path(), &Component::YtDlp.file_name());
+    let source = bin.path().join("exported.txt");
+    std::fs::write(&source, COOKIES).unwrap();
+
+    CredentialStore::import_cookies(&env, "www.youtube.com", &source).unwrap();
+    let stored = env.auth_dir().join("cookies").join("youtube.com.txt");
+    #[cfg(unix)]
+    {
+        assert_eq!(mode(&stored), 0o600);
+        assert_eq!(mode(&env.auth_dir()), 0o700);
+        assert_eq!(mode(&env.auth_dir().join("credentials.json")), 0o600);
+    }
+
+    let req = DownloadRequest::new("https://music.youtube.com/watch?v=dQw4w9WgXcQ", "audio", "bestaudio", out.path().to_string_lossy().to_string(), "en");
+    ytdlp::download(&env, &RecordingSink::default(), &req).await.unwrap();
+
+    let calls = recorded_calls(bin.path());
+    assert_eq!(calls.len(), 2);
+    for call in &calls {
+        assert_eq!(value_after(call, "--cookies"), Some(stored.to_str().unwrap()));
+    }
+}
+
+#[tokio::test]
+async fn browser_and_login_credentials_map_to_yt_dlp_options() {
+    let bin = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().install(bin.path(), &Component::YtDlp.file_name());
+
+    CredentialStore::use_browser(&env, "youtube.com", "Firefox", Some("default-release".into())).unwrap();
+    CredentialStore::set_login(&env, "https://vimeo.com/", "alice", "p@ss").unwrap();
+    CredentialStore::set_login(&env, "nicovideo.jp", "bob", "pw2").unwrap();
+    // 帳號密碼不常駐在明文 netrc 中
+    assert!(!env.auth_dir().join("netrc").exists());
+
+    ytdlp::analyze(&env, &RecordingSink::default(), "https://youtu.be/dQw4w9WgXcQ", "en").await.unwrap();
+    ytdlp::analyze(&env, &RecordingSink::default(), "https://vimeo.com/123", "en").await.unwrap();
+    ytdlp::analyze(&env, &RecordingSink::default(), "https://example.com/video", "en").await.unwrap();
+    ytdlp::analyze(&env, &RecordingSink::default(), "https://www.nicovideo.jp/watch/sm9", "en").await.unwrap();
+    let calls = recorded_calls(bin.path());
+    assert_eq!(value_after(&calls[0], "--cookies-from-browser"), Some("firefox:default-release"));
+    assert!(calls[1].contains(&"--netrc".to_string()));
+    assert!(!calls[1].iter().any(|a| a.contains("p@ss")));
+    assert!(!calls[2].iter().any(|a| a.starts_with("--cookies") || a.starts_with("--netrc")));
+
+    // 暫存 netrc 只在 yt-dlp 執行期間存在，且只含該網站的帳號
+    let seen = std::fs::read_to_string(bin.path().join("netrc_seen.txt")).unwrap();
+    assert_eq!(seen, "machine vimeo login alice password p@ss\nmachine niconico login bob password pw2\n");
+    for call in [&calls[1], &calls[3]] {
+        assert!(!std::path::Path::new(value_after(call, "--netrc-location").unwrap()).exists());
+    }
+    let auth = cyber_ytdl_lib::auth::args_for_url(&env, "https://vimeo.com/1");
+    let netrc = auth.netrc_path().unwrap().to_path_buf();
+    #[cfg(unix)]
+    assert_eq!(mode(&netrc), 0o600);
+    drop(auth);
+    assert!(!netrc.exists());
+
+    // 列表不含密碼；移除後不再套用
+    let listed = CredentialStore::load(&env).unwrap().list();
+    assert_eq!(listed.len(), 3);
+    assert!(listed.iter().all(|c| !c.detail.contains("p@ss")));
+    CredentialStore::remove(&env, "vimeo.com").unwrap();
+    assert!(cyber_ytdl_lib::auth::args_for_url(&env, "https://vimeo.com/1").args.is_empty());
+}
+
+#[test]
+fn netrc_machines_follow_yt_dlp_extractors() {
+    for (site, machine) in [
+        ("youtube.com", "youtube"),
+        ("nicovideo.jp", "niconico"),
+        ("bbc.co.uk", "bbc"),
+        ("archive.org", "archiveorg"),
+        ("example.co.uk", "example"),
+        ("video.example.com", "example"),
+    ] {
+        assert_eq!(CredentialStore::netrc_machine(site), machine, "{}", site);
+    }
+}
+
+#[test]
+fn unreadable_store_is_reported_instead_of_overwritten() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    CredentialStore::set_login(&env, "vimeo.com", "alice", "pw").unwrap();
+    let store = env.auth_dir().join("credentials.json");
+    std::fs::write(&store, b"\x01 not json").unwrap();
+
+    assert!(CredentialStore::load(&env).is_err());
+    assert!(CredentialStore::set_login(&env, "youtube.com", "bob", "pw").is_err());
+    assert_eq!(std::fs::read(&store).unwrap(), b"\x01 not json");
+}
+
+#[test]
+fn invalid_credentials_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    let bad = dir.path().join("bad.txt");
+    std::fs::write(&bad, "{\"not\": \"cookies\"}").unwrap();
+
+    assert!(CredentialStore::import_cookies(&env, "youtube.com", &bad).is_err());
+    assert!(CredentialStore::use_browser(&env, "youtube.com", "netscape", None).is_err());
+    assert!(CredentialStore::set_login(&env, "vimeo.com", "alice", "two words").is_err());
+    assert!(CredentialStore::set_login(&env, "../etc", "alice", "pw").is_err());
+    assert!(CredentialStore::load(&env).unwrap().list().is_empty());
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use chrono::{Duration as ChronoDuration, Local, NaiveTime};
+use common::{recorded_calls, FakeYtDlp, RecordingSink, TestServer};
+use cyber_ytdl_lib::process::{self, StopSignal};
+use cyber_ytdl_lib::settings::{BandwidthSettings, ThrottleRule};
+use cyber_ytdl_lib::{components, ytdlp, AppEnv, Component, DownloadRequest};
+use std::time::{Duration, Instant};
+
+fn rule(start: &str, end: &str, limit: Option<u64>) -> ThrottleRule {
+    ThrottleRule { start: start.into(), end: end.into(), limit }
+}
+
+fn at(hm: &str) -> NaiveTime {
+    NaiveTime::parse_from_str(hm, "%H:%M").unwrap()
+}
+
+fn limit_after(call: &[String]) -> Option<&str> {
+    call.windows(2).find(|w| w[0] == "--limit-rate").map(|w| w[1].as_str())
+}
+
+#[test]
+fn schedule_picks_the_matching_window() {
+    let settings = BandwidthSettings {
+        limit: Some(5_000_000),
+        schedule: vec![rule("09:00", "18:00", Some(2_000_000)), rule("22:00", "06:00", None)],
+    };
+    assert_eq!(settings.limit_at(at("10:30")), Some(2_000_000));
+    assert_eq!(settings.limit_at(at("18:00")), Some(5_000_000));
+    assert_eq!(settings.limit_at(at("23:15")), None);
+    assert_eq!(settings.limit_at(at("05:59")), None);
+    assert!(BandwidthSettings { limit: None, schedule: vec![rule("9am", "18:00", None)] }.validate().is_err());
+}
+
+#[tokio::test]
+async fn job_limit_is_capped_by_global_limit() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    env.settings.bandwidth.limit = Some(3_000_000);
+    FakeYtDlp::default().install(bin.path(), &Component::YtDlp.file_name());
+
+    let mut req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.path().to_string_lossy().to_string(), "en");
+    req.options.rate_limit = Some(1_000_000);
+    ytdlp::download(&env, &RecordingSink::default(), &req).await.unwrap();
+    req.options.rate_limit = Some(9_000_000);
+    ytdlp::download(&env, &RecordingSink::default(), &req).await.unwrap();
+
+    let calls = recorded_calls(bin.path());
+    assert_eq!(limit_after(&calls[1]), Some("1000000"));
+    assert_eq!(limit_after(&calls[2]), Some("3000000"));
+}
+
+#[tokio::test]
+async fn running_job_restarts_when_the_schedule_changes() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    // 兩秒後進入限速時段
+    let now = Local::now().time();
+    let fmt = |t: NaiveTime| t.format("%H:%M:%S").to_string();
+    env.settings.bandwidth.schedule = vec![ThrottleRule {
+        start: fmt(now + ChronoDuration::seconds(2)),
+        end: fmt(now + ChronoDuration::hours(1)),
+        limit: Some(500_000),
+    }];
+    FakeYtDlp::default().records_live(b"partial").install(bin.path(), &Component::YtDlp.file_name());
+    let stop = StopSignal::default();
+    let sink = RecordingSink::default();
+
+    let req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.path().to_string_lossy().to_string(), "en");
+    let (result, _) = tokio::join!(
+        ytdlp::download_with_stop(&env, &sink, &req, &stop),
+        async {
+            tokio::time::sleep(Duration::from_millis(4500)).await;
+            stop.stop();
+        }
+    );
+    assert!(result.unwrap_err().contains("stopped"));
+
+    let downloads: Vec<Vec<String>> = recorded_calls(bin.path()).into_iter().skip(1).collect();
+    assert_eq!(limit_after(&downloads[0]), None);
+    if process::SOFT_STOP {
+        assert_eq!(downloads.len(), 2);
+        assert_eq!(limit_after(&downloads[1]), Some("500000"));
+        assert!(sink.logs().iter().any(|l| l.contains("Bandwidth schedule changed")));
+    } else {
+        // 無法溫和停止時不中斷下載，新的速率留給下一個任務
+        assert_eq!(downloads.len(), 1);
+        assert!(sink.logs().iter().any(|l| l.contains("apply from the next download")));
+    }
+}
+
+#[tokio::test]
+async fn schedule_changes_do_not_restart_post_processing() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    let now = Local::now().time();
+    let fmt = |t: NaiveTime| t.format("%H:%M:%S").to_string();
+    env.settings.bandwidth.schedule = vec![rule(&fmt(now + ChronoDuration::seconds(1)), &fmt(now + ChronoDuration::hours(1)), Some(500_000))];
+    // 已在合併影音，重新啟動會中斷 ffmpeg
+    FakeYtDlp::default().progress(&["[Merger] Merging formats into \"Sample Video_best.mp4\""]).records_live(b"partial").install(bin.path(), &Component::YtDlp.file_name());
+    let stop = StopSignal::default();
+    let sink = RecordingSink::default();
+
+    let req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.path().to_string_lossy().to_string(), "en");
+    let (result, _) = tokio::join!(
+        ytdlp::download_with_stop(&env, &sink, &req, &stop),
+        async {
+            tokio::time::sleep(Duration::from_millis(3000)).await;
+            stop.stop();
+        }
+    );
+    assert!(result.unwrap_err().contains("stopped"));
+    assert_eq!(recorded_calls(bin.path()).len(), 2);
+    assert!(!sink.logs().iter().any(|l| l.contains("Bandwidth schedule changed")));
+    assert!(sink.logs().iter().any(|l| l.contains("apply from the next download")));
+}
+
+#[tokio::test]
+async fn component_downloads_are_throttled() {
+    let server = TestServer::start().await;
+    server.route("/big.bin", 200, "application/octet-stream", vec![7u8; 64 * 1024]);
+    let dir = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(dir.path());
+    env.settings.bandwidth.limit = Some(32 * 1024);
+
+    let started = Instant::now();
+    let dest = dir.path().join("big.bin");
+    components::perform_download(&env, &RecordingSink::default(), &format!("{}/big.bin", server.base_url), &dest, 0.0, 100.0).await.unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(1500));
+    assert_eq!(std::fs::metadata(&dest).unwrap().len(), 64 * 1024);
+}
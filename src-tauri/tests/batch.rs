@@ -0,0 +1,95 @@
+mod common;
+
+use common::{FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::batch::{self, BatchDefaults, BatchFormat};
+use cyber_ytdl_lib::{AppEnv, Component, DownloadProfile, DownloadQueue, DownloadRequest, JobSchedule};
+use std::path::Path;
+
+fn defaults(folder: &Path) -> BatchDefaults {
+    BatchDefaults { folder: Some(folder.to_string_lossy().to_string()), profile: None, lang: "en".into() }
+}
+
+fn lines(report: &[batch::BatchLine]) -> Vec<usize> {
+    report.iter().map(|l| l.line).collect()
+}
+
+#[test]
+fn text_list_reports_accepted_duplicate_and_invalid_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    let queue = DownloadQueue::load(env.queue_path());
+    queue.enqueue(DownloadRequest::new("https://vimeo.com/9", "video", "best", "/tmp", "en"), JobSchedule::default()).unwrap();
+    let list = dir.path().join("links.txt");
+    std::fs::write(&list, "\
+# links from the team
+https://youtu.be/dQw4w9WgXcQ
+https://www.youtube.com/watch?v=dQw4w9WgXcQ&utm_source=mail   # same video
+
+not a url
+javascript:alert(1)
+https://vimeo.com/1#t=30
+https://vimeo.com/9
+").unwrap();
+
+    let report = batch::import_file(&env, &queue, &list, &defaults(dir.path())).unwrap();
+    assert_eq!(lines(&report.accepted), [2, 7]);
+    assert_eq!(lines(&report.duplicate), [3, 8]);
+    assert_eq!(report.duplicate[0].detail, "Duplicate of line 2");
+    assert_eq!(report.duplicate[1].detail, "Already queued");
+    assert_eq!(lines(&report.invalid), [5, 6]);
+    assert_eq!(queue.list().len(), 3);
+    assert_eq!(queue.get(&report.accepted[0].detail).unwrap().request.url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+
+    // 沒有資料夾時整批都不合法，不會加入任何任務
+    let report = batch::import_file(&env, &queue, &list, &BatchDefaults::default()).unwrap();
+    assert!(report.accepted.is_empty());
+    assert_eq!(queue.list().len(), 3);
+}
+
+#[tokio::test]
+async fn csv_columns_set_profile_folder_and_file_name() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    env.settings.profiles.push(DownloadProfile { name: "music".into(), mode: "audio".into(), quality: "mp3".into(), ..Default::default() });
+    FakeYtDlp::default().writes_output(b"audio").install(bin.path(), &Component::YtDlp.file_name());
+    let queue = DownloadQueue::load(env.queue_path());
+
+    let csv = "\u{feff}URL,Profile,Folder,Filename\n\
+        https://vimeo.com/1,music,talks,\"Keynote: Day 1\"\n\
+        https://vimeo.com/2,missing,,\n\
+        https://vimeo.com/3,,/abs/path,\n";
+    let path = out.path().join("list.csv");
+    std::fs::write(&path, csv).unwrap();
+    assert_eq!(BatchFormat::from_path(&path), BatchFormat::Csv);
+
+    let report = batch::import_file(&env, &queue, &path, &defaults(out.path())).unwrap();
+    assert_eq!(lines(&report.accepted), [2, 4]);
+    assert_eq!(report.invalid[0].detail, "Unknown profile: missing");
+    let first = queue.get(&report.accepted[0].detail).unwrap();
+    assert_eq!(first.request.mode, "audio");
+    assert_eq!(first.request.path, out.path().join("talks").to_string_lossy());
+    assert_eq!(queue.get(&report.accepted[1].detail).unwrap().request.path, "/abs/path");
+
+    // 自訂檔名取代預設的「標題_畫質」
+    queue.remove(&report.accepted[1].detail).unwrap();
+    std::fs::create_dir_all(out.path().join("talks")).unwrap();
+    assert!(queue.run_next(&env, &RecordingSink::default()).await);
+    let done = queue.get(&report.accepted[0].detail).unwrap();
+    assert_eq!(done.result.unwrap().files, [out.path().join("talks").join("Keynote_ Day 1.mp3")]);
+}
+
+#[test]
+fn json_accepts_strings_and_objects() {
+    let rows = batch::parse(r#"["https://vimeo.com/1", {"url": "https://vimeo.com/2", "filename": "two"}, 42]"#, BatchFormat::Json).unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[1].filename.as_deref(), Some("two"));
+    assert_eq!((rows[2].line, rows[2].url.as_str()), (3, ""));
+    assert!(batch::parse("{not json", BatchFormat::Json).is_err());
+
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    let queue = DownloadQueue::load(env.queue_path());
+    let report = batch::import_rows(&env, &queue, rows, &defaults(dir.path())).unwrap();
+    assert_eq!((report.accepted.len(), report.invalid.len()), (2, 1));
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::{recorded_calls, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::cache::MetadataCache;
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, DownloadRequest};
+use std::time::Duration;
+
+fn dump_calls(dir: &std::path::Path) -> usize {
+    recorded_calls(dir).iter().filter(|c| c.contains(&"--dump-json".to_string())).count()
+}
+
+#[tokio::test]
+async fn download_reuses_analysis_and_loads_info_json() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().install(bin.path(), &Component::YtDlp.file_name());
+    let sink = RecordingSink::default();
+
+    ytdlp::analyze(&env, &sink, "https://youtu.be/dQw4w9WgXcQ?si=share", "en").await.unwrap();
+    let req = DownloadRequest::new("https://www.youtube.com/watch?v=dQw4w9WgXcQ", "video", "best", out.path().to_string_lossy().to_string(), "en");
+    ytdlp::download(&env, &sink, &req).await.unwrap();
+
+    assert_eq!(dump_calls(bin.path()), 1);
+    let download_call = recorded_calls(bin.path()).pop().unwrap();
+    let i = download_call.iter().position(|a| a == "--load-info-json").unwrap();
+    assert!(download_call[i + 1].ends_with(".info.json"));
+    assert!(!download_call.iter().any(|a| a.starts_with("https://")));
+    // 暫存的 info.json 用完即刪除
+    assert!(!std::path::Path::new(&download_call[i + 1]).exists());
+}
+
+#[tokio::test]
+async fn expired_entries_are_fetched_again() {
+    let bin = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    env.metadata_cache = std::sync::Arc::new(MetadataCache::new(Duration::ZERO));
+    FakeYtDlp::default().install(bin.path(), &Component::YtDlp.file_name());
+    let sink = RecordingSink::default();
+
+    ytdlp::analyze(&env, &sink, "https://vimeo.com/1", "en").await.unwrap();
+    ytdlp::analyze(&env, &sink, "https://vimeo.com/1", "en").await.unwrap();
+
+    assert_eq!(dump_calls(bin.path()), 2);
+}
+
+#[test]
+fn disk_cache_survives_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let info = serde_json::json!({ "title": "Persisted" });
+
+    MetadataCache::new(Duration::from_secs(60)).with_disk_dir(dir.path()).insert("https://vimeo.com/1", info.clone());
+    let reopened = MetadataCache::new(Duration::from_secs(60)).with_disk_dir(dir.path());
+
+    assert_eq!(*reopened.get("https://vimeo.com/1").unwrap(), info);
+    assert!(reopened.get("https://vimeo.com/2").is_none());
+}
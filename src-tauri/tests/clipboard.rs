@@ -0,0 +1,107 @@
+mod common;
+
+use common::{recorded_calls, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::clipboard::{self, ClipboardAction, ClipboardWatcher};
+use cyber_ytdl_lib::settings::ClipboardSettings;
+use cyber_ytdl_lib::{AppEnv, Component, DownloadProfile, DownloadQueue};
+
+fn urls(found: Vec<cyber_ytdl_lib::NormalizedUrl>) -> Vec<String> {
+    found.into_iter().map(|u| u.url).collect()
+}
+
+#[test]
+fn watcher_reports_each_new_link_once() {
+    let settings = ClipboardSettings { ignore_domains: vec!["www.example.com".into()], ..Default::default() };
+    let mut watcher = ClipboardWatcher::default();
+
+    // 啟動時剪貼簿中已有的內容不處理
+    assert!(watcher.observe("https://youtu.be/dQw4w9WgXcQ", &settings).is_empty());
+    assert!(watcher.observe("https://youtu.be/dQw4w9WgXcQ", &settings).is_empty());
+
+    let text = "look (https://vimeo.com/123?utm_source=x), also https://cdn.example.com/a.mp4 and https://www.youtube.com/watch?v=aaaaaaaaaaa.";
+    assert_eq!(urls(watcher.observe(text, &settings)), ["https://vimeo.com/123", "https://www.youtube.com/watch?v=aaaaaaaaaaa"]);
+
+    // 同一部影片的不同網址形式不重複回報
+    assert!(watcher.observe("https://youtu.be/aaaaaaaaaaa?si=abc", &settings).is_empty());
+    assert!(watcher.observe("plain text, no links", &settings).is_empty());
+    assert_eq!(urls(watcher.observe("https://youtu.be/dQw4w9WgXcQ", &settings)), ["https://www.youtube.com/watch?v=dQw4w9WgXcQ"]);
+
+    watcher.reset();
+    assert!(watcher.observe("https://vimeo.com/456", &settings).is_empty());
+}
+
+#[test]
+fn enqueue_action_requires_a_download_folder() {
+    let mut settings = ClipboardSettings { action: ClipboardAction::Enqueue, ..Default::default() };
+    assert!(settings.validate().is_err());
+    settings.download_dir = Some("/tmp".into());
+    assert!(settings.validate().is_ok());
+    settings.ignore_domains.push("bad domain".into());
+    assert!(settings.validate().is_err());
+}
+
+#[tokio::test]
+async fn detected_links_are_handled_by_action() {
+    let bin = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    FakeYtDlp::default().install(bin.path(), &Component::YtDlp.file_name());
+    std::fs::write(bin.path().join("extractors.txt"), "Vimeo\nYoutube\n").unwrap();
+    let queue = DownloadQueue::load(env.queue_path());
+    let sink = RecordingSink::default();
+    let found = |text: &str| clipboard::extract_urls(text).remove(0);
+
+    // yt-dlp 不支援的網站略過
+    assert!(clipboard::handle(&env, &sink, &queue, found("https://news.example.org/story")).await.is_none());
+
+    let notified = clipboard::handle(&env, &sink, &queue, found("https://vimeo.com/1")).await.unwrap();
+    assert!(notified.metadata.is_none() && notified.job_id.is_none());
+    assert!(recorded_calls(bin.path()).is_empty());
+
+    env.settings.clipboard.action = ClipboardAction::Analyze;
+    let analyzed = clipboard::handle(&env, &sink, &queue, found("https://youtu.be/dQw4w9WgXcQ")).await.unwrap();
+    assert_eq!(analyzed.metadata.unwrap().title, "Sample Video");
+
+    env.settings.clipboard.action = ClipboardAction::Enqueue;
+    env.settings.clipboard.download_dir = Some(bin.path().join("inbox").to_string_lossy().to_string());
+    env.settings.profiles.push(DownloadProfile { name: "default".into(), mode: "audio".into(), quality: "mp3".into(), ..Default::default() });
+    let queued = clipboard::handle(&env, &sink, &queue, found("https://vimeo.com/2")).await.unwrap();
+    let job = queue.get(&queued.job_id.unwrap()).unwrap();
+    assert_eq!((job.request.mode.as_str(), job.request.quality.as_str()), ("audio", "mp3"));
+
+    // 再次複製同一部影片 (網址寫法不同) 不會重複加入
+    let again = clipboard::handle(&env, &sink, &queue, found("https://vimeo.com/2?utm_source=share")).await.unwrap();
+    assert_eq!((again.job_id, again.error.as_deref()), (None, Some("Already queued")));
+    assert_eq!(queue.list().len(), 1);
+
+    let events = sink.events("clipboard-link");
+    assert_eq!(events.len(), 4);
+    assert_eq!(events[2]["action"], "enqueue");
+}
+
+#[tokio::test]
+async fn common_non_video_hosts_are_ignored() {
+    let bin = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().install(bin.path(), &Component::YtDlp.file_name());
+    let queue = DownloadQueue::load(env.queue_path());
+    let sink = RecordingSink::default();
+    let links = [
+        "https://www.google.com/search?q=cats",
+        "https://docs.google.com/document/d/abc/edit",
+        "https://www.amazon.com/dp/B000000000",
+        "https://github.com/yt-dlp/yt-dlp",
+        "https://en.wikipedia.org/wiki/Video",
+        "https://mail.co.uk/inbox",
+    ];
+
+    // 有 extractor 清單 (含名稱相近的 GoogleDrive / AmazonStore) 與沒有清單時都略過
+    std::fs::write(bin.path().join("extractors.txt"), "GoogleDrive\nAmazonStore\nGitHub\nWikipedia\nVimeo\n").unwrap();
+    for link in links {
+        assert!(clipboard::handle(&env, &sink, &queue, clipboard::extract_urls(link).remove(0)).await.is_none(), "{}", link);
+    }
+    std::fs::remove_file(bin.path().join("extractors.txt")).unwrap();
+    for link in links {
+        assert!(clipboard::handle(&env, &sink, &queue, clipboard::extract_urls(link).remove(0)).await.is_none(), "{}", link);
+    }
+    assert!(sink.events("clipboard-link").is_empty());
+}
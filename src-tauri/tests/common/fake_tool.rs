@@ -0,0 +1,101 @@
+// 測試用的假 yt-dlp / ffmpeg (只用標準函式庫，由 common::fake_tool_binary 以 rustc 編譯)。
+// 依自己所在資料夾中的 fake_*.txt 決定輸出；執行檔名稱以 ffmpeg 開頭時扮演 ffmpeg。
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+fn read(dir: &Path, name: &str) -> String {
+    std::fs::read_to_string(dir.join(name)).unwrap_or_default()
+}
+
+fn setting(dir: &Path, key: &str) -> String {
+    read(dir, "fake_config.txt").lines()
+        .find_map(|l| l.strip_prefix(key).and_then(|rest| rest.strip_prefix('=')).map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn record(path: PathBuf, args: &[String]) {
+    let mut log = OpenOptions::new().create(true).append(true).open(path).unwrap();
+    for arg in args {
+        writeln!(log, "{}", arg).unwrap();
+    }
+    writeln!(log, "--END--").unwrap();
+}
+
+fn ffmpeg(dir: &Path, args: &[String]) {
+    record(dir.join("ffmpeg_calls.log"), args);
+    if let Some(last) = args.last() {
+        std::fs::write(last, "track").unwrap();
+    }
+}
+
+fn yt_dlp(dir: &Path, args: &[String]) -> i32 {
+    record(dir.join("calls.log"), args);
+    let mut out = None;
+    let mut prev = "";
+    for arg in args {
+        match arg.as_str() {
+            "--version" => {
+                print!("{}", read(dir, "fake_version.txt"));
+                return 0;
+            }
+            "--dump-json" | "-J" | "--dump-single-json" => {
+                print!("{}", read(dir, "fake_dump.json"));
+                return setting(dir, "dump_exit").parse().unwrap_or(0);
+            }
+            _ => {}
+        }
+        match prev {
+            "-o" => out = Some(arg.clone()),
+            "--netrc-location" => {
+                let mut seen = OpenOptions::new().create(true).append(true).open(dir.join("netrc_seen.txt")).unwrap();
+                seen.write_all(read(Path::new(""), arg).as_bytes()).unwrap();
+            }
+            _ => {}
+        }
+        prev = arg;
+    }
+
+    print!("{}", read(dir, "fake_stdout.txt"));
+    std::io::stdout().flush().unwrap();
+    eprint!("{}", read(dir, "fake_stderr.txt"));
+    let output = dir.join("fake_output.bin");
+    if setting(dir, "live") == "true" {
+        // 直播：寫出 .part 後持續執行，直到被停止
+        if let Some(out) = &out {
+            std::fs::copy(&output, format!("{}.part", out)).unwrap();
+        }
+        std::thread::sleep(std::time::Duration::from_secs(30));
+    } else if let Some(out) = out.filter(|_| output.exists()) {
+        // 多段下載：每個 --download-sections 各寫一個 stem_NN 檔案
+        let sections = args.iter().filter(|a| *a == "--download-sections").count();
+        let ext = if args.iter().any(|a| a == "--extract-audio") { "mp3" } else { "mp4" };
+        let written: Vec<String> = if out.contains("%(section_number)02d") {
+            (1..=sections).map(|n| out.replace("%(section_number)02d", &format!("{:02}", n)).replace("%(ext)s", ext)).collect()
+        } else {
+            vec![out]
+        };
+        for path in &written {
+            std::fs::copy(&output, path).unwrap();
+        }
+        if let Some(i) = args.iter().position(|a| a == "--print-to-file") {
+            let mut printed = OpenOptions::new().create(true).append(true).open(&args[i + 2]).unwrap();
+            for path in &written {
+                writeln!(printed, "{}", path).unwrap();
+            }
+        }
+    }
+    setting(dir, "exit_code").parse().unwrap_or(0)
+}
+
+fn main() {
+    let exe = std::env::current_exe().unwrap();
+    let dir = exe.parent().unwrap().to_path_buf();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let name = exe.file_stem().unwrap().to_string_lossy().to_ascii_lowercase();
+    if name.starts_with("ffmpeg") {
+        ffmpeg(&dir, &args);
+    } else {
+        std::process::exit(yt_dlp(&dir, &args));
+    }
+}
@@ -0,0 +1,313 @@
+// 整合測試共用工具：可程式化的假 yt-dlp / ffmpeg (跨平台)、取代 GitHub 的本機 HTTP 伺服器，以及記錄事件的 EventSink
+#![allow(dead_code)]
+
+use cyber_ytdl_lib::EventSink;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// 收集所有送出的事件，供測試斷言
+#[derive(Default)]
+pub struct RecordingSink {
+    events: Mutex<Vec<(String, serde_json::Value)>>,
+}
+
+impl EventSink for RecordingSink {
+    fn emit_json(&self, event: &str, payload: serde_json::Value) {
+        self.events.lock().unwrap().push((event.to_string(), payload));
+    }
+}
+
+impl RecordingSink {
+    pub fn events(&self, name: &str) -> Vec<serde_json::Value> {
+        self.events.lock().unwrap().iter()
+            .filter(|(e, _)| e == name)
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+
+    pub fn logs(&self) -> Vec<String> {
+        self.events("backend-log").into_iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect()
+    }
+}
+
+/// 假的 yt-dlp：fake_tool.rs 編譯出的小程式，依參數輸出預先寫好的內容並以指定結束碼離開 (各平台相同)。
+/// 每次呼叫的參數會逐行寫入 calls.log (以 "--END--" 分隔)，供測試檢查；--netrc-location 的內容附加到 netrc_seen.txt。
+pub struct FakeYtDlp {
+    pub dump_json: String,
+    pub dump_exit: i32,
+    pub stdout: Vec<String>,
+    pub stderr: Vec<String>,
+    pub exit_code: i32,
+    pub version: String,
+    /// 下載時要建立的檔案內容 (寫到 -o 指定的路徑)
+    pub output_bytes: Option<Vec<u8>>,
+    /// 模擬直播：把內容寫到 <輸出>.part 後一直執行，直到被終止
+    pub live: bool,
+}
+
+impl Default for FakeYtDlp {
+    fn default() -> Self {
+        Self {
+            dump_json: sample_info_json().to_string(),
+            dump_exit: 0,
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+            exit_code: 0,
+            version: "2026.01.01".into(),
+            output_bytes: None,
+            live: false,
+        }
+    }
+}
+
+impl FakeYtDlp {
+    pub fn progress(mut self, lines: &[&str]) -> Self {
+        self.stdout.extend(lines.iter().map(|s| s.to_string()));
+        self
+    }
+
+    pub fn stderr(mut self, lines: &[&str]) -> Self {
+        self.stderr.extend(lines.iter().map(|s| s.to_string()));
+        self
+    }
+
+    pub fn exit_code(mut self, code: i32) -> Self {
+        self.exit_code = code;
+        self
+    }
+
+    pub fn dump_json(mut self, json: serde_json::Value) -> Self {
+        self.dump_json = json.to_string();
+        self
+    }
+
+    pub fn dump_exit(mut self, code: i32) -> Self {
+        self.dump_exit = code;
+        self
+    }
+
+    pub fn writes_output(mut self, bytes: &[u8]) -> Self {
+        self.output_bytes = Some(bytes.to_vec());
+        self
+    }
+
+    pub fn records_live(mut self, bytes: &[u8]) -> Self {
+        self.output_bytes = Some(bytes.to_vec());
+        self.live = true;
+        self
+    }
+
+    /// 直到被停止前都不結束 (不寫出任何檔案)
+    pub fn hangs(mut self) -> Self {
+        self.live = true;
+        self
+    }
+
+    /// 將假程式與其資料檔寫入 dir，回傳執行檔路徑
+    pub fn install(&self, dir: &Path, file_name: &str) -> PathBuf {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("fake_dump.json"), &self.dump_json).unwrap();
+        std::fs::write(dir.join("fake_stdout.txt"), join_lines(&self.stdout)).unwrap();
+        std::fs::write(dir.join("fake_stderr.txt"), join_lines(&self.stderr)).unwrap();
+        std::fs::write(dir.join("fake_version.txt"), format!("{}\n", self.version)).unwrap();
+        std::fs::write(dir.join("fake_config.txt"), format!("dump_exit={}\nexit_code={}\nlive={}\n", self.dump_exit, self.exit_code, self.live)).unwrap();
+        if let Some(bytes) = &self.output_bytes {
+            std::fs::write(dir.join("fake_output.bin"), bytes).unwrap();
+        }
+        install_fake_tool(dir, file_name)
+    }
+}
+
+fn join_lines(lines: &[String]) -> String {
+    lines.iter().map(|l| format!("{}\n", l)).collect()
+}
+
+/// 假的 ffmpeg：記錄參數到 ffmpeg_calls.log，並建立最後一個參數 (輸出檔)；file_name 須以 ffmpeg 開頭
+pub fn install_fake_ffmpeg(dir: &Path, file_name: &str) -> PathBuf {
+    install_fake_tool(dir, file_name)
+}
+
+/// 以 rustc 編譯 fake_tool.rs (每個測試程式一次，原始碼不變時沿用先前的結果)
+fn fake_tool_binary() -> &'static Path {
+    static BINARY: OnceLock<PathBuf> = OnceLock::new();
+    BINARY.get_or_init(|| {
+        let source = include_str!("fake_tool.rs");
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        source.hash(&mut hasher);
+        let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+        let binary = dir.join(format!("fake_tool-{:016x}{}", hasher.finish(), std::env::consts::EXE_SUFFIX));
+        if binary.exists() {
+            return binary;
+        }
+        // 同時執行的測試程式各自編譯到暫存名稱再改名
+        let tmp = dir.join(format!("fake_tool-{}-{}{}", std::process::id(), hasher.finish(), std::env::consts::EXE_SUFFIX));
+        let cargo = Path::new(env!("CARGO"));
+        let rustc = cargo.with_file_name(format!("rustc{}", std::env::consts::EXE_SUFFIX));
+        let rustc = if rustc.exists() { rustc } else { PathBuf::from("rustc") };
+        let status = std::process::Command::new(rustc)
+            .args(["--edition", "2021", "-C", "debuginfo=0", "-o"])
+            .arg(&tmp)
+            .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("common").join("fake_tool.rs"))
+            .status()
+            .expect("cannot run rustc");
+        assert!(status.success(), "cannot build fake_tool.rs");
+        if std::fs::rename(&tmp, &binary).is_err() {
+            // 其他測試程式已先完成
+            let _ = std::fs::remove_file(&tmp);
+        }
+        binary
+    })
+}
+
+fn install_fake_tool(dir: &Path, file_name: &str) -> PathBuf {
+    let path = dir.join(file_name);
+    std::fs::copy(fake_tool_binary(), &path).unwrap();
+    path
+}
+
+/// 讀取假 yt-dlp 被呼叫時的參數，每次呼叫一組
+pub fn recorded_calls(dir: &Path) -> Vec<Vec<String>> {
+    parse_calls(dir.join("calls.log"))
+}
+
+/// 讀取假 ffmpeg 被呼叫時的參數
+pub fn ffmpeg_calls(dir: &Path) -> Vec<Vec<String>> {
+    parse_calls(dir.join("ffmpeg_calls.log"))
+}
+
+fn parse_calls(path: PathBuf) -> Vec<Vec<String>> {
+    let log = std::fs::read_to_string(path).unwrap_or_default();
+    let mut calls = Vec::new();
+    let mut current = Vec::new();
+    for line in log.lines() {
+        if line == "--END--" {
+            calls.push(std::mem::take(&mut current));
+        } else {
+            current.push(line.to_string());
+        }
+    }
+    calls
+}
+
+/// 建立一個空的可執行檔 (用於標記組件已存在)
+pub fn touch(path: &Path) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, b"").unwrap();
+}
+
+pub fn sample_info_json() -> serde_json::Value {
+    serde_json::json!({
+        "id": "dQw4w9WgXcQ",
+        "title": "Sample Video",
+        "thumbnail": "https://i.ytimg.com/vi/dQw4w9WgXcQ/maxresdefault.jpg",
+        "duration": 212,
+        "uploader": "Sample Channel",
+        "webpage_url": "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+        "formats": [
+            { "format_id": "140", "ext": "m4a", "vcodec": "none", "acodec": "mp4a.40.2", "abr": 129.5, "filesize": 3_433_000 },
+            { "format_id": "251", "ext": "webm", "vcodec": "none", "acodec": "opus", "abr": 160.0, "filesize": 3_600_000 },
+            { "format_id": "136", "ext": "mp4", "vcodec": "avc1", "acodec": "none", "resolution": "1280x720", "filesize": 20_000_000 },
+            { "format_id": "137", "ext": "mp4", "vcodec": "avc1", "acodec": "none", "resolution": "1920x1080", "filesize": 40_000_000 }
+        ]
+    })
+}
+
+#[derive(Clone)]
+struct Route {
+    status: u16,
+    content_type: String,
+    body: Vec<u8>,
+}
+
+/// 極簡的本機 HTTP 伺服器，依路徑回傳固定內容，用來取代 GitHub 下載與 API
+pub struct TestServer {
+    pub base_url: String,
+    routes: Arc<Mutex<HashMap<String, Route>>>,
+    hits: Arc<Mutex<Vec<String>>>,
+    bodies: Arc<Mutex<Vec<String>>>,
+}
+
+impl TestServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let routes: Arc<Mutex<HashMap<String, Route>>> = Arc::default();
+        let hits: Arc<Mutex<Vec<String>>> = Arc::default();
+        let bodies: Arc<Mutex<Vec<String>>> = Arc::default();
+
+        let routes_bg = routes.clone();
+        let hits_bg = hits.clone();
+        let bodies_bg = bodies.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else { break };
+                let routes = routes_bg.clone();
+                let hits = hits_bg.clone();
+                let bodies = bodies_bg.clone();
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 4096];
+                    // 只需讀到標頭結束即可 (測試只用 GET / 小型 POST)
+                    loop {
+                        let n = match socket.read(&mut chunk).await { Ok(0) | Err(_) => return, Ok(n) => n };
+                        buf.extend_from_slice(&chunk[..n]);
+                        if buf.windows(4).any(|w| w == b"\r\n\r\n") { break; }
+                    }
+                    let head = String::from_utf8_lossy(&buf).to_string();
+                    let target = head.split_whitespace().nth(1).unwrap_or("/").to_string();
+                    let path = target.split('?').next().unwrap_or("/").to_string();
+                    hits.lock().unwrap().push(target.clone());
+                    // 有 Content-Length 時讀完本文 (Webhook 測試用)
+                    let header_end = buf.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+                    let length = head.lines()
+                        .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap_or(0)))
+                        .unwrap_or(0);
+                    while buf.len() < header_end + length {
+                        let n = match socket.read(&mut chunk).await { Ok(0) | Err(_) => break, Ok(n) => n };
+                        buf.extend_from_slice(&chunk[..n]);
+                    }
+                    if length > 0 {
+                        bodies.lock().unwrap().push(String::from_utf8_lossy(&buf[header_end..]).to_string());
+                    }
+
+                    let route = {
+                        let routes = routes.lock().unwrap();
+                        routes.get(&target).or_else(|| routes.get(&path)).cloned()
+                    };
+                    let route = route.unwrap_or(Route { status: 404, content_type: "text/plain".into(), body: b"not found".to_vec() });
+                    let header = format!(
+                        "HTTP/1.1 {} X\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        route.status, route.content_type, route.body.len()
+                    );
+                    let _ = socket.write_all(header.as_bytes()).await;
+                    let _ = socket.write_all(&route.body).await;
+                    let _ = socket.shutdown().await;
+                });
+            }
+        });
+
+        Self { base_url: format!("http://{}", addr), routes, hits, bodies }
+    }
+
+    pub fn route(&self, path: &str, status: u16, content_type: &str, body: impl Into<Vec<u8>>) {
+        self.routes.lock().unwrap().insert(path.to_string(), Route {
+            status,
+            content_type: content_type.to_string(),
+            body: body.into(),
+        });
+    }
+
+    pub fn hits(&self) -> Vec<String> {
+        self.hits.lock().unwrap().clone()
+    }
+
+    pub fn bodies(&self) -> Vec<String> {
+        self.bodies.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use common::{touch, FakeYtDlp, RecordingSink, TestServer};
+use cyber_ytdl_lib::components::ComponentSource;
+use cyber_ytdl_lib::{components, AppEnv, Component};
+
+#[tokio::test]
+async fn repair_downloads_missing_yt_dlp_from_release_server() {
+    let server = TestServer::start().await;
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path()).with_github_base(&server.base_url);
+    // Linux / macOS 下載對應平台的獨立執行檔，只有 Windows 下載 yt-dlp.exe
+    let url = env.yt_dlp_download_url().unwrap();
+    assert_eq!(url.ends_with(".exe"), cfg!(windows), "{}", url);
+    server.route(&url[server.base_url.len()..], 200, "application/octet-stream", b"fake-binary".to_vec());
+    touch(&env.ffmpeg());
+    touch(&env.deno());
+    let sink = RecordingSink::default();
+
+    let result = components::repair(&env, &sink, "en").await;
+
+    assert_eq!(result.unwrap(), "OK");
+    assert_eq!(std::fs::read(env.yt_dlp()).unwrap(), b"fake-binary");
+    assert_eq!(sink.events("core-status-update").last().unwrap(), &serde_json::json!(true));
+}
+
+#[tokio::test]
+async fn repair_fails_on_http_error() {
+    let server = TestServer::start().await;
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path()).with_github_base(&server.base_url);
+    touch(&env.ffmpeg());
+    touch(&env.deno());
+    let sink = RecordingSink::default();
+
+    let err = components::repair(&env, &sink, "en").await.unwrap_err();
+
+    assert!(err.contains("404"));
+    assert!(!env.yt_dlp().exists());
+}
+
+#[tokio::test]
+async fn check_reports_missing_components() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    touch(&env.yt_dlp());
+    let sink = RecordingSink::default();
+
+    assert!(!components::check(&env, &sink, "en"));
+    let missing = format!("{}, {}", Component::Ffmpeg.file_name(), Component::Deno.file_name());
+    assert!(sink.logs().iter().any(|l| l.contains(&missing)));
+
+    // 沒有官方 zip 的平台不下載 Windows 版 ffmpeg，改請使用者以套件管理員安裝
+    assert_eq!(env.ffmpeg_download_url().is_some(), cfg!(windows));
+}
+
+// Windows 有官方的 ffmpeg 建置
+#[cfg(not(windows))]
+#[tokio::test]
+async fn repair_asks_for_package_manager_when_no_build_exists() {
+    let server = TestServer::start().await;
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path()).with_github_base(&server.base_url);
+    touch(&env.yt_dlp());
+    touch(&env.deno());
+    let sink = RecordingSink::default();
+
+    let err = components::repair(&env, &sink, "en").await.unwrap_err();
+
+    assert!(err.contains("Install ffmpeg with your system package manager"), "{}", err);
+    assert!(server.hits().is_empty());
+    assert!(sink.logs().iter().any(|l| l.contains("package manager")));
+}
+
+#[test]
+fn locator_follows_search_order() {
+    let root = tempfile::tempdir().unwrap();
+    let app_dir = root.path().join("app");
+    let data_dir = root.path().join("data");
+    let system_dir = root.path().join("system");
+    let custom_dir = root.path().join("custom");
+
+    let mut env = AppEnv::new(&app_dir);
+    env.data_dir = data_dir.clone();
+    env.search_path = Some(std::env::join_paths([&system_dir]).unwrap());
+    let name = Component::Ffmpeg.file_name();
+
+    assert!(env.locator().locate(Component::Ffmpeg).is_none());
+
+    touch(&system_dir.join(&name));
+    assert_eq!(env.locator().locate(Component::Ffmpeg).unwrap().source, ComponentSource::SystemPath);
+
+    touch(&app_dir.join(&name));
+    assert_eq!(env.locator().locate(Component::Ffmpeg).unwrap().source, ComponentSource::AppDir);
+
+    touch(&data_dir.join("bin").join(&name));
+    assert_eq!(env.locator().locate(Component::Ffmpeg).unwrap().source, ComponentSource::UserData);
+
+    // 設定可以指向資料夾或執行檔本身
+    touch(&custom_dir.join(&name));
+    env.settings.components.ffmpeg = Some(custom_dir.clone());
+    let found = env.locator().locate(Component::Ffmpeg).unwrap();
+    assert_eq!(found.source, ComponentSource::Configured);
+    assert_eq!(found.path, custom_dir.join(&name));
+
+    // 設定的路徑不存在時退回一般搜尋
+    env.settings.components.ffmpeg = Some(root.path().join("missing").join(&name));
+    assert_eq!(env.locator().locate(Component::Ffmpeg).unwrap().source, ComponentSource::UserData);
+}
+
+#[tokio::test]
+async fn repair_installs_into_user_dir_and_uses_components_on_path() {
+    let server = TestServer::start().await;
+    let url = AppEnv::new("/").with_github_base(&server.base_url).yt_dlp_download_url().unwrap();
+    server.route(&url[server.base_url.len()..], 200, "application/octet-stream", b"fake-binary".to_vec());
+
+    let root = tempfile::tempdir().unwrap();
+    let system_dir = root.path().join("usr-bin");
+    touch(&system_dir.join(Component::Ffmpeg.file_name()));
+    touch(&system_dir.join(Component::Deno.file_name()));
+
+    let mut env = AppEnv::new(root.path().join("program-files")).with_github_base(&server.base_url);
+    env.data_dir = root.path().join("data");
+    env.search_path = Some(std::env::join_paths([&system_dir]).unwrap());
+    let sink = RecordingSink::default();
+
+    components::repair(&env, &sink, "en").await.unwrap();
+
+    let yt = env.locator().locate(Component::YtDlp).unwrap();
+    assert_eq!(yt.source, ComponentSource::UserData);
+    assert_eq!(yt.path, root.path().join("data").join("bin").join(Component::YtDlp.file_name()));
+    assert!(!root.path().join("program-files").exists());
+    // 系統 PATH 上已有的組件不會被重新下載
+    assert_eq!(server.hits().len(), 1);
+}
+
+#[tokio::test]
+async fn versions_come_from_fake_binary_and_local_api() {
+    let server = TestServer::start().await;
+    server.route("/repos/yt-dlp/yt-dlp/releases/latest", 200, "application/json", br#"{"tag_name":"2026.09.30"}"#.to_vec());
+
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path()).with_github_api_base(&server.base_url);
+    FakeYtDlp::default().install(dir.path(), &Component::YtDlp.file_name());
+
+    assert_eq!(components::local_yt_dlp_version(&env.yt_dlp()).await.unwrap(), "2026.01.01");
+    assert_eq!(components::remote_yt_dlp_version(&env).await.unwrap(), "2026.09.30");
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::{recorded_calls, sample_info_json, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::diskspace::{self, SizeEstimate, SpaceCheck};
+use cyber_ytdl_lib::sections::Sections;
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, DownloadRequest};
+use serde_json::json;
+
+const MIB: u64 = 1024 * 1024;
+
+fn request(mode: &str, quality: &str) -> DownloadRequest {
+    DownloadRequest::new("https://vimeo.com/1", mode, quality, "/tmp", "en")
+}
+
+#[test]
+fn estimate_follows_the_selected_formats() {
+    let info = sample_info_json();
+
+    // bestvideo (1080p) + bestaudio (opus 160k)，合併時需要兩倍空間
+    let video = diskspace::estimate(&info, &request("video", "best"), None);
+    assert_eq!(video.output_bytes, Some(43_600_000));
+    assert_eq!(video.required_bytes, Some(87_200_000 + 50 * MIB));
+    assert!(!video.approximate);
+    let ids: Vec<&str> = diskspace::selected_formats(&info, "video", "136").iter().map(|f| f["format_id"].as_str().unwrap()).collect();
+    assert_eq!(ids, ["136", "251"]);
+
+    // 轉成 256k mp3：原始音訊 + mp3
+    let audio = diskspace::estimate(&info, &request("audio", "bestaudio"), None);
+    assert_eq!(audio.output_bytes, Some(32_000 * 212));
+    assert_eq!(audio.required_bytes, Some(3_600_000 + 32_000 * 212 + 50 * MIB));
+    assert!(audio.approximate);
+
+    // 沒有 filesize 時以位元率 × 長度估計；完全無法估計時為 None
+    let approx = json!({ "duration": 100, "formats": [{ "format_id": "hls", "vcodec": "avc1", "acodec": "mp4a", "tbr": 800.0 }] });
+    assert_eq!(diskspace::estimate(&approx, &request("video", "best"), None).output_bytes, Some(10_000_000));
+    let unknown = json!({ "formats": [{ "format_id": "x", "vcodec": "avc1", "acodec": "mp4a" }] });
+    assert_eq!(diskspace::estimate(&unknown, &request("video", "best"), None), SizeEstimate::default());
+
+    // 只下載片段時依長度比例縮小：212 秒中的 53 秒 = 1/4
+    let clip = Sections::Range { start: 10.0, end: Some(63.0) };
+    let partial = diskspace::estimate(&info, &request("video", "best"), Some(&clip));
+    assert_eq!(partial.output_bytes, Some(43_600_000 / 4));
+    assert!(partial.approximate);
+    let partial_audio = diskspace::estimate(&info, &request("audio", "bestaudio"), Some(&clip));
+    assert_eq!(partial_audio.output_bytes, Some(32_000 * 53));
+}
+
+#[test]
+fn space_check_refuses_or_warns() {
+    let estimate = |available: u64, approximate: bool| SizeEstimate {
+        output_bytes: Some(100),
+        required_bytes: Some(250),
+        available_bytes: Some(available),
+        approximate,
+    };
+    assert_eq!(estimate(300, false).check(), SpaceCheck::Ok);
+    assert_eq!(estimate(150, true).check(), SpaceCheck::Tight);
+    assert_eq!(estimate(150, false).check(), SpaceCheck::Insufficient);
+    assert_eq!(estimate(50, true).check(), SpaceCheck::Insufficient);
+    assert_eq!(SizeEstimate::default().check(), SpaceCheck::Unknown);
+    assert!(diskspace::available_space(&std::env::temp_dir().join("not/created/yet")).is_some());
+}
+
+#[tokio::test]
+async fn download_is_refused_before_starting_when_the_disk_is_too_small() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    let mut info = sample_info_json();
+    info["formats"][3]["filesize"] = json!(u64::MAX / 4);
+    FakeYtDlp::default().dump_json(info).writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+    let sink = RecordingSink::default();
+    let req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.path().to_string_lossy(), "en");
+
+    let estimate = ytdlp::estimate_size(&env, &req).await.unwrap();
+    assert!(estimate.available_bytes.is_some());
+    assert_eq!(estimate.check(), SpaceCheck::Insufficient);
+
+    let err = ytdlp::download(&env, &sink, &req).await.unwrap_err();
+    assert!(err.contains("Not enough disk space"), "{}", err);
+    // 只有解析資訊，沒有啟動下載
+    assert_eq!(recorded_calls(bin.path()).len(), 1);
+    assert_eq!(sink.events("size-estimate").len(), 1);
+    assert!(std::fs::read_dir(out.path()).unwrap().next().is_none());
+
+    // 媒體庫的節目 / 季資料夾也不會先建立
+    let mut req = req;
+    req.options.media_library = true;
+    assert!(ytdlp::download(&env, &sink, &req).await.unwrap_err().contains("Not enough disk space"));
+    assert!(std::fs::read_dir(out.path()).unwrap().next().is_none());
+}
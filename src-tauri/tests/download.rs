@@ -0,0 +1,66 @@
+mod common;
+
+use common::{recorded_calls, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, DownloadRequest};
+
+fn request(path: &std::path::Path, mode: &str, quality: &str) -> DownloadRequest {
+    DownloadRequest::new("https://www.youtube.com/watch?v=dQw4w9WgXcQ", mode, quality, path.to_string_lossy().to_string(), "en")
+}
+
+#[tokio::test]
+async fn download_reports_progress_and_passes_format() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default()
+        .progress(&[
+            "[download]  12.5% of 40.00MiB at  2.00MiB/s ETA 00:17",
+            "[download] 100.0% of 40.00MiB at  4.00MiB/s ETA 00:00",
+        ])
+        .writes_output(b"video")
+        .install(bin.path(), &Component::YtDlp.file_name());
+    let sink = RecordingSink::default();
+
+    let result = ytdlp::download(&env, &sink, &request(out.path(), "video", "137")).await.unwrap();
+    assert_eq!(result.files, [out.path().join("Sample Video_137.mp4")]);
+    assert!(result.removed_segments.is_empty());
+
+    let progress: Vec<f64> = sink.events("download-progress").iter()
+        .map(|p| p["progress"].as_f64().unwrap())
+        .collect();
+    assert_eq!(progress, [12.5, 100.0]);
+    assert!(out.path().join("Sample Video_137.mp4").exists());
+
+    let calls = recorded_calls(bin.path());
+    let download_call = calls.last().unwrap();
+    let f = download_call.iter().position(|a| a == "-f").unwrap();
+    assert_eq!(download_call[f + 1], "137+bestaudio/best");
+    assert!(download_call.contains(&"--merge-output-format".to_string()));
+}
+
+#[tokio::test]
+async fn download_failure_returns_stderr() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default()
+        .stderr(&["ERROR: [youtube] dQw4w9WgXcQ: Video unavailable"])
+        .exit_code(1)
+        .install(bin.path(), &Component::YtDlp.file_name());
+    let sink = RecordingSink::default();
+
+    let err = ytdlp::download(&env, &sink, &request(out.path(), "audio", "bestaudio")).await.unwrap_err();
+    assert!(err.contains("Video unavailable"));
+}
+
+#[tokio::test]
+async fn download_requires_quality() {
+    let bin = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().install(bin.path(), &Component::YtDlp.file_name());
+    let sink = RecordingSink::default();
+
+    let err = ytdlp::download(&env, &sink, &request(bin.path(), "video", "")).await.unwrap_err();
+    assert!(err.contains("Quality or format not selected"));
+    assert!(recorded_calls(bin.path()).is_empty());
+}
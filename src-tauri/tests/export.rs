@@ -0,0 +1,166 @@
+mod common;
+
+use common::{FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::export::{self, ExportFilter, ExportFormat, ExportRow, ExportSource};
+use cyber_ytdl_lib::history::{self, HistoryStatus};
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, DownloadQueue, DownloadRequest, JobSchedule};
+use std::path::PathBuf;
+
+fn row(title: &str, files: Vec<PathBuf>, duration: Option<f64>) -> ExportRow {
+    ExportRow {
+        timestamp: 0,
+        status: "completed".into(),
+        title: title.into(),
+        url: "https://vimeo.com/1".into(),
+        folder: String::new(),
+        files,
+        duration,
+        error: None,
+    }
+}
+
+#[tokio::test]
+async fn finished_downloads_are_recorded_and_exported() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    let sink = RecordingSink::default();
+    let req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.path().to_string_lossy(), "en");
+
+    FakeYtDlp::default().writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+    ytdlp::download(&env, &sink, &req).await.unwrap();
+    FakeYtDlp::default().stderr(&["ERROR: Video unavailable"]).exit_code(1).install(bin.path(), &Component::YtDlp.file_name());
+    ytdlp::download(&env, &sink, &req).await.unwrap_err();
+
+    let entries = history::load(&env.history_path()).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!((entries[0].status, entries[0].title.as_str(), entries[0].duration), (HistoryStatus::Completed, "Sample Video", Some(212.0)));
+    assert_eq!(entries[1].status, HistoryStatus::Failed);
+    assert!(entries[1].error.as_deref().unwrap().contains("Video unavailable"));
+
+    let queue = DownloadQueue::load(env.queue_path());
+    let csv_path = out.path().join("export/history.csv");
+    let completed = ExportFilter { statuses: vec!["completed".into()], ..Default::default() };
+    assert_eq!(export::export(&env, &queue, ExportSource::History, ExportFormat::Csv, &completed, &csv_path).unwrap(), 1);
+    let csv = std::fs::read_to_string(&csv_path).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "date,status,title,url,folder,files,duration,error");
+    assert!(lines[1].contains(",completed,Sample Video,https://vimeo.com/1,"), "{}", lines[1]);
+    assert!(lines[1].ends_with("Sample Video_best.mp4,212,"), "{}", lines[1]);
+
+    // 日期範圍之外沒有任何項目
+    let future = ExportFilter { from: Some(entries[1].finished_at + 60), ..Default::default() };
+    let json_path = out.path().join("history.json");
+    assert_eq!(export::export(&env, &queue, ExportSource::History, ExportFormat::Json, &future, &json_path).unwrap(), 0);
+    assert_eq!(std::fs::read_to_string(&json_path).unwrap(), "[]");
+}
+
+#[test]
+fn history_ids_are_stable_and_concurrent_writes_are_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history.json");
+    // 舊版紀錄沒有 id
+    let legacy = serde_json::json!([{
+        "url": "https://vimeo.com/1", "title": "Old", "mode": "video", "quality": "best", "folder": "",
+        "status": "completed", "files": [], "duration": null, "error": null, "finished_at": 0
+    }]);
+    std::fs::write(&path, legacy.to_string()).unwrap();
+    let id = history::load(&path).unwrap()[0].id.clone();
+    assert!(!id.is_empty());
+    assert_eq!(history::load(&path).unwrap()[0].id, id);
+
+    let template = history::load(&path).unwrap().remove(0);
+    std::thread::scope(|scope| {
+        for i in 0..8 {
+            let (path, id, mut entry) = (&path, &id, template.clone());
+            scope.spawn(move || {
+                entry.id = format!("new-{}", i);
+                history::append(path, entry).unwrap();
+                history::update(path, |entries| {
+                    entries.iter_mut().find(|e| &e.id == id).unwrap().title = format!("Edited {}", i);
+                    Ok(())
+                }).unwrap();
+            });
+        }
+    });
+    let entries = history::load(&path).unwrap();
+    assert_eq!(entries.len(), 9);
+    assert_eq!(entries[0].id, id);
+    assert!(entries[0].title.starts_with("Edited"));
+}
+
+#[test]
+fn m3u_playlist_references_existing_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let video = dir.path().join("clips/Talk_best.mp4");
+    let tracks = [dir.path().join("Album/01 - Intro.mp3"), dir.path().join("Album/02 - Outro.mp3")];
+    for file in [&video, &tracks[0], &tracks[1]] {
+        common::touch(file);
+    }
+    let elsewhere = tempfile::tempdir().unwrap();
+    let outside = elsewhere.path().join("Other.mp4");
+    common::touch(&outside);
+
+    let rows = [
+        row("Talk\nPart 1", vec![video], Some(212.4)),
+        row("Album", tracks.to_vec(), None),
+        row("Deleted", vec![dir.path().join("gone.mp4")], Some(10.0)),
+        row("Other", vec![outside.clone()], None),
+    ];
+    let dest = dir.path().join("list.m3u8");
+    assert_eq!(export::write(&rows, ExportFormat::M3u, &dest).unwrap(), 4);
+    let expected = format!(
+        "#EXTM3U\n#EXTINF:212,Talk Part 1\nclips/Talk_best.mp4\n#EXTINF:-1,01 - Intro\nAlbum/01 - Intro.mp3\n#EXTINF:-1,02 - Outro\nAlbum/02 - Outro.mp3\n#EXTINF:-1,Other\n{}\n",
+        outside.display()
+    );
+    assert_eq!(std::fs::read_to_string(&dest).unwrap(), expected);
+}
+
+#[test]
+fn queue_export_filters_by_folder_and_status() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    let queue = DownloadQueue::load(env.queue_path());
+    for (url, folder) in [("https://vimeo.com/1", "/media/music"), ("https://vimeo.com/2", "/media/music/live"), ("https://vimeo.com/3", "/media/musicals")] {
+        queue.enqueue(DownloadRequest::new(url, "audio", "mp3", folder, "en"), JobSchedule::default()).unwrap();
+    }
+    let dest = dir.path().join("queue.json");
+    let filter = ExportFilter { folder: Some("/media/music".into()), statuses: vec!["scheduled".into()], ..Default::default() };
+    assert_eq!(export::export(&env, &queue, ExportSource::Queue, ExportFormat::Json, &filter, &dest).unwrap(), 2);
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&dest).unwrap()).unwrap();
+    assert_eq!(json[1]["url"], "https://vimeo.com/2");
+    assert_eq!(json[1]["title"], "https://vimeo.com/2");
+
+    let failed = ExportFilter { statuses: vec!["failed".into()], ..Default::default() };
+    assert_eq!(export::export(&env, &queue, ExportSource::Queue, ExportFormat::Csv, &failed, &dir.path().join("q.csv")).unwrap(), 0);
+
+    let mut csv_row = row("=HYPERLINK(\"x\")", vec![PathBuf::from("+cmd.mp4")], None);
+    csv_row.folder = "\t=1+1".into();
+    csv_row.url = "\r@SUM(A1)".into();
+    export::write(&[csv_row], ExportFormat::Csv, &dir.path().join("safe.csv")).unwrap();
+    let csv = std::fs::read_to_string(dir.path().join("safe.csv")).unwrap();
+    for cell in ["'=HYPERLINK", "'\t=1+1", "'\r@SUM", "'+cmd.mp4"] {
+        assert!(csv.contains(cell), "{}: {}", cell, csv);
+    }
+}
+
+#[test]
+fn corrupt_history_is_reported_and_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history.json");
+    std::fs::write(&path, "[{\"url\": ").unwrap();
+    assert!(history::load(&path).unwrap_err().contains("Cannot read download history"));
+
+    let entry = serde_json::from_value(serde_json::json!({
+        "url": "https://vimeo.com/1", "title": "New", "mode": "video", "quality": "best", "folder": "",
+        "status": "completed", "files": [], "duration": null, "error": null, "finished_at": 0
+    })).unwrap();
+    history::append(&path, entry).unwrap();
+    assert_eq!(history::load(&path).unwrap().len(), 1);
+    let backups: Vec<String> = std::fs::read_dir(dir.path()).unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|n| n.starts_with("history.corrupt-"))
+        .collect();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(std::fs::read_to_string(dir.path().join(&backups[0])).unwrap(), "[{\"url\": ");
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::TestServer;
+use cyber_ytdl_lib::subscriptions::{self, SubscriptionKind};
+use cyber_ytdl_lib::{feeds, AppEnv, DownloadQueue, SubscriptionConfig, SubscriptionStore};
+use serde_json::json;
+
+const YOUTUBE_FEED: &str = include_str!("fixtures/youtube_feed.xml");
+const PODCAST_FEED: &str = include_str!("fixtures/podcast.xml");
+
+#[test]
+fn youtube_atom_feed_uses_archive_keys() {
+    let entries = feeds::parse_feed(YOUTUBE_FEED).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].archive_key, "youtube NewVideo002");
+    assert_eq!(entries[0].url, "https://www.youtube.com/watch?v=NewVideo002");
+    assert_eq!(entries[0].title, "Tom & Jerry recap");
+    assert_eq!(entries[0].upload_date.as_deref(), Some("20261018"));
+    assert_eq!(entries[1].archive_key, "youtube OldVideo001");
+}
+
+#[test]
+fn podcast_rss_prefers_enclosures() {
+    let entries = feeds::parse_feed(PODCAST_FEED).unwrap();
+    let keys: Vec<&str> = entries.iter().map(|e| e.archive_key.as_str()).collect();
+    // 沒有 GUID 的項目以連結識別
+    assert_eq!(keys, ["example-podcast-ep2", "example-podcast-ep1", "https://podcast.example.com/notes"]);
+    assert_eq!(entries[0].url, "https://cdn.example.com/audio/ep2.mp3");
+    assert_eq!(entries[0].title, "Episode 2: <Live> Q&A");
+    assert_eq!(entries[0].duration, Some(3723.0));
+    assert_eq!(entries[1].duration, Some(3300.0));
+    assert_eq!(entries[2].url, "https://podcast.example.com/notes");
+
+    assert!(feeds::parse_feed("<html><body>not a feed</body></html>").is_err());
+    assert!(feeds::parse_feed("<rss><channel><item></channel>").is_err());
+}
+
+#[tokio::test]
+async fn feed_subscription_enqueues_new_items_by_guid() {
+    let server = TestServer::start().await;
+    server.route("/feed.xml", 200, "application/rss+xml", PODCAST_FEED);
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    let store = SubscriptionStore::load(env.subscriptions_path());
+    let queue = DownloadQueue::load(env.queue_path());
+    let config: SubscriptionConfig = serde_json::from_value(json!({
+        "url": format!("{}/feed.xml", server.base_url),
+        "kind": "feed",
+        "target_dir": dir.path().join("podcasts").to_string_lossy(),
+    })).unwrap();
+    assert_eq!(config.kind, SubscriptionKind::Feed);
+    let sub = store.add(config).unwrap();
+
+    assert!(subscriptions::check(&env, &store, &queue, &sub.id).await.unwrap().is_empty());
+
+    // 新的一集出現在最上方
+    let updated = PODCAST_FEED.replacen("<item>", r#"<item>
+   <title>Episode 3</title>
+   <guid>example-podcast-ep3</guid>
+   <enclosure url="https://cdn.example.com/audio/ep3.mp3" type="audio/mpeg"/>
+  </item>
+  <item>"#, 1);
+    server.route("/feed.xml", 200, "application/rss+xml", updated);
+    let jobs = subscriptions::check(&env, &store, &queue, &sub.id).await.unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].request.url, "https://cdn.example.com/audio/ep3.mp3");
+    assert!(jobs[0].request.options.use_archive);
+
+    server.route("/feed.xml", 500, "text/plain", "down");
+    let err = subscriptions::check(&env, &store, &queue, &sub.id).await.unwrap_err();
+    assert!(err.contains("500"), "{}", err);
+    assert_eq!(store.get(&sub.id).unwrap().last_error, Some(err));
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use common::{recorded_calls, sample_info_json, FakeYtDlp, RecordingSink, TestServer};
+use cyber_ytdl_lib::hooks::{self, Hook, HookAction, HookPayload, HookTrigger};
+use cyber_ytdl_lib::process::StopSignal;
+use cyber_ytdl_lib::{history, ytdlp, AppEnv, Component, DownloadProfile, DownloadRequest, DownloadResult};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+fn command(program: &Path, args: &[&str]) -> HookAction {
+    HookAction::Command { program: program.to_string_lossy().into(), args: args.iter().map(|a| a.to_string()).collect() }
+}
+
+fn default_profile(hooks: Vec<Hook>) -> DownloadProfile {
+    DownloadProfile { hooks, ..Default::default() }
+}
+
+#[test]
+fn arguments_are_substituted_one_by_one() {
+    let req = DownloadRequest::new("https://vimeo.com/1", "audio", "mp3", "/music", "en");
+    let result = Ok(DownloadResult {
+        title: "Say \"hi\"; rm -rf ~".into(),
+        video_id: Some("abc".into()),
+        files: vec!["/music/01 a.mp3".into(), "/music/02 b.mp3".into()],
+        ..Default::default()
+    });
+    let payload = HookPayload::new(&req, &result);
+    assert_eq!(payload.event, "download.completed");
+    let args: Vec<String> = ["--title={title}", "{files}", "{id}:{status}"].iter().map(|a| a.to_string()).collect();
+    assert_eq!(hooks::render_args(&args, &payload), ["--title=Say \"hi\"; rm -rf ~", "/music/01 a.mp3", "/music/02 b.mp3", "abc:completed"]);
+
+    let hook = |action: HookAction| Hook { action, ..Default::default() };
+    assert!(hook(command(Path::new("notify"), &["{path}"])).validate().is_ok());
+    assert!(hook(command(Path::new("notify"), &["{size}"])).validate().unwrap_err().contains("Unknown placeholder {size}"));
+    assert!(hook(command(Path::new(" "), &[])).validate().is_err());
+    assert!(hook(HookAction::Webhook { url: "ftp://example.com/hook".into() }).validate().is_err());
+    assert!(Hook { timeout_secs: 0, ..hook(HookAction::Webhook { url: "https://example.com/hook".into() }) }.validate().is_err());
+}
+
+#[tokio::test]
+async fn command_hooks_run_without_a_shell_and_log_their_output() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    let sink = RecordingSink::default();
+    let mut info = sample_info_json();
+    info["title"] = "Clip $(touch pwned)".into();
+    FakeYtDlp::default().dump_json(info).writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+
+    // 掛鉤程式也用假程式：參數記錄在各自資料夾的 calls.log
+    let scripts = tempfile::tempdir().unwrap();
+    let (rescan_dir, failing_dir) = (scripts.path().join("rescan"), scripts.path().join("fail"));
+    let exe = |name: &str| format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    let script = FakeYtDlp::default().progress(&["scanned"]).install(&rescan_dir, &exe("rescan"));
+    let failing = FakeYtDlp::default().stderr(&["boom"]).exit_code(3).install(&failing_dir, &exe("fail"));
+
+    env.settings.profiles = vec![default_profile(vec![
+        Hook { name: "rescan".into(), action: command(&script, &["{title}", "{id}", "{path}"]), ..Default::default() },
+        Hook { name: "flaky".into(), action: command(&failing, &[]), retries: 1, ..Default::default() },
+        Hook { name: "on-error".into(), action: command(&script, &["failed"]), when: HookTrigger::Failed, ..Default::default() },
+    ])];
+    let req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.path().to_string_lossy(), "en");
+    let result = ytdlp::download(&env, &sink, &req).await.unwrap();
+
+    let args = recorded_calls(&rescan_dir);
+    assert_eq!(args, [vec!["Clip $(touch pwned)".to_string(), "dQw4w9WgXcQ".into(), result.files[0].to_string_lossy().to_string()]]);
+    assert!(!Path::new("pwned").exists() && !bin.path().join("pwned").exists());
+
+    let logs = sink.logs();
+    assert!(logs.contains(&"[rescan] scanned".to_string()));
+    assert!(logs.iter().any(|l| l.contains("[rescan] Hook finished")));
+    assert_eq!(logs.iter().filter(|l| *l == "[flaky] boom").count(), 2);
+    assert!(logs.iter().any(|l| l.contains("[flaky] Retrying hook (1/1)")));
+    assert!(!logs.iter().any(|l| l.contains("[on-error]")));
+
+    // 結果存入下載紀錄
+    let recorded = &history::load(&env.history_path()).unwrap()[0].hooks;
+    assert_eq!(recorded.len(), 2);
+    assert_eq!((recorded[0].name.as_str(), recorded[0].success, recorded[0].output.clone()), ("rescan", true, vec!["scanned".to_string()]));
+    assert_eq!((recorded[1].success, recorded[1].attempts, recorded[1].output.clone()), (false, 2, vec!["boom".to_string()]));
+    assert!(recorded[1].error.as_deref().unwrap().starts_with("Exited with"));
+}
+
+#[tokio::test]
+async fn hooks_stream_output_keep_both_tails_and_time_out() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    let sink = RecordingSink::default();
+    FakeYtDlp::default().writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+
+    let scripts = tempfile::tempdir().unwrap();
+    let exe = |name: &str| format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    let noisy_lines: Vec<String> = (1..=30).map(|i| format!("line {}", i)).collect();
+    let noisy_refs: Vec<&str> = noisy_lines.iter().map(String::as_str).collect();
+    let noisy = FakeYtDlp::default().progress(&noisy_refs).stderr(&["warning"]).install(&scripts.path().join("noisy"), &exe("noisy"));
+    let stuck = FakeYtDlp::default().progress(&["started"]).hangs().install(&scripts.path().join("stuck"), &exe("stuck"));
+    env.settings.profiles = vec![default_profile(vec![
+        Hook { name: "noisy".into(), action: command(&noisy, &[]), ..Default::default() },
+        Hook { name: "stuck".into(), action: command(&stuck, &[]), timeout_secs: 1, ..Default::default() },
+    ])];
+
+    // 掛鉤延後執行：下載紀錄先寫入，掛鉤結果在執行完後補上
+    let req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.path().to_string_lossy(), "en");
+    let (result, pending) = ytdlp::download_deferring_hooks(&env, &sink, &req, &StopSignal::default()).await;
+    result.unwrap();
+    assert!(history::load(&env.history_path()).unwrap()[0].hooks.is_empty());
+    let started = Instant::now();
+    let outcomes = pending.run(&env, &sink).await;
+    assert!(started.elapsed() < Duration::from_secs(10));
+
+    // stdout 與 stderr 各自保留最後的幾行
+    assert_eq!(outcomes[0].output.len(), 21);
+    assert_eq!(outcomes[0].output[0], "line 11");
+    assert_eq!(outcomes[0].output.last().map(String::as_str), Some("warning"));
+    assert!(sink.logs().contains(&"[stuck] started".to_string()));
+    assert!(outcomes[1].error.as_deref().unwrap().contains("Timed out after 1s"));
+    assert_eq!(history::load(&env.history_path()).unwrap()[0].hooks, outcomes);
+}
+
+#[tokio::test]
+async fn webhooks_post_json_and_retry_on_errors() {
+    let server = TestServer::start().await;
+    server.route("/failed", 500, "text/plain", "oops");
+    server.route("/done", 204, "text/plain", "");
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    let sink = RecordingSink::default();
+    FakeYtDlp::default().stderr(&["ERROR: Video unavailable"]).exit_code(1).install(bin.path(), &Component::YtDlp.file_name());
+
+    let webhook = |path: &str| HookAction::Webhook { url: format!("{}{}", server.base_url, path) };
+    env.settings.profiles = vec![DownloadProfile {
+        name: "podcasts".into(),
+        hooks: vec![
+            Hook { action: webhook("/failed"), when: HookTrigger::Failed, retries: 1, ..Default::default() },
+            Hook { action: webhook("/done"), ..Default::default() },
+        ],
+        ..Default::default()
+    }];
+    let mut req = DownloadRequest::new("https://vimeo.com/1", "audio", "mp3", out.path().to_string_lossy(), "en");
+    req.profile = Some("podcasts".into());
+    ytdlp::download(&env, &sink, &req).await.unwrap_err();
+
+    assert_eq!(server.hits(), ["/failed", "/failed"]);
+    let body: serde_json::Value = serde_json::from_str(&server.bodies()[0]).unwrap();
+    assert_eq!((body["event"].as_str(), body["profile"].as_str(), body["url"].as_str()), (Some("download.failed"), Some("podcasts"), Some("https://vimeo.com/1")));
+    assert!(body["error"].as_str().unwrap().contains("Video unavailable"));
+    assert!(sink.logs().iter().any(|l| l.contains("Hook failed: HTTP 500")));
+}
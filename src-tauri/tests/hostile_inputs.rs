@@ -0,0 +1,110 @@
+// 惡意輸入：網址與壓縮檔內容都不應該能執行指令或寫出目標以外的檔案
+mod common;
+
+use common::{recorded_calls, touch, FakeYtDlp, RecordingSink, TestServer};
+use cyber_ytdl_lib::urls::validate_external_url;
+use cyber_ytdl_lib::{archive, components, ytdlp, AppEnv, Component};
+use std::io::Write;
+
+fn build_zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut buf = std::io::Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        let opts = zip::write::SimpleFileOptions::default();
+        for (name, data) in entries {
+            zip.start_file(*name, opts).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+    buf.into_inner()
+}
+
+#[test]
+fn rejects_non_http_schemes() {
+    for raw in [
+        "javascript:alert(1)",
+        "file:///C:/Windows/System32/calc.exe",
+        "ms-settings:",
+        "ftp://example.com/a",
+        "--exec=calc.exe",
+        "",
+        "https://exa\nmple.com",
+        "https://",
+    ] {
+        assert!(validate_external_url(raw).is_err(), "accepted {:?}", raw);
+    }
+}
+
+#[test]
+fn whitespace_in_url_is_encoded() {
+    let url = validate_external_url("https://example.com/a'; Start-Process calc; '").unwrap();
+    assert!(!url.as_str().contains(' '));
+    assert_eq!(url.host_str(), Some("example.com"));
+}
+
+#[tokio::test]
+async fn hostile_url_never_reaches_yt_dlp() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    FakeYtDlp::default().install(dir.path(), &Component::YtDlp.file_name());
+    let sink = RecordingSink::default();
+
+    assert!(ytdlp::analyze(&env, &sink, "--exec=touch pwned", "en").await.is_err());
+    assert!(recorded_calls(dir.path()).is_empty());
+
+    ytdlp::analyze(&env, &sink, "https://example.com/watch?v=1", "en").await.unwrap();
+    let call = &recorded_calls(dir.path())[0];
+    assert_eq!(call[call.len() - 2], "--");
+}
+
+#[test]
+fn extraction_skips_path_traversal_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let target_dir = dir.path().join("bin");
+    std::fs::create_dir_all(&target_dir).unwrap();
+    let name = Component::Ffmpeg.file_name();
+    let zip_path = dir.path().join("ffmpeg.zip");
+    std::fs::write(&zip_path, build_zip(&[
+        (&format!("../../{}", name), b"evil"),
+        (&format!("ffmpeg-master/bin/{}", name), b"good"),
+    ])).unwrap();
+
+    let dest = target_dir.join(&name);
+    archive::extract_file(&zip_path, &name, &dest).unwrap();
+
+    assert_eq!(std::fs::read(&dest).unwrap(), b"good");
+    assert!(!dir.path().parent().unwrap().join(&name).exists());
+}
+
+#[test]
+fn extraction_fails_when_member_missing() {
+    let dir = tempfile::tempdir().unwrap();
+    let zip_path = dir.path().join("deno.zip");
+    std::fs::write(&zip_path, build_zip(&[("README.md", b"hi")])).unwrap();
+
+    let err = archive::extract_file(&zip_path, &Component::Deno.file_name(), &dir.path().join("deno")).unwrap_err();
+    assert!(err.contains("not found"));
+}
+
+#[tokio::test]
+async fn repair_handles_quotes_and_semicolons_in_paths() {
+    let server = TestServer::start().await;
+    let de = Component::Deno.file_name();
+
+    let root = tempfile::tempdir().unwrap();
+    let data_dir = root.path().join("it's; $(touch pwned) 'dir'");
+    let mut env = AppEnv::new(root.path().join("app")).with_github_base(&server.base_url);
+    env.data_dir = data_dir.clone();
+    let url = env.deno_download_url().unwrap();
+    server.route(&url[server.base_url.len()..], 200, "application/zip", build_zip(&[(&format!("deno-bin/{}", de), b"deno")]));
+    touch(&data_dir.join("bin").join(Component::YtDlp.file_name()));
+    touch(&data_dir.join("bin").join(Component::Ffmpeg.file_name()));
+    let sink = RecordingSink::default();
+
+    components::repair(&env, &sink, "en").await.unwrap();
+
+    assert_eq!(std::fs::read(data_dir.join("bin").join(&de)).unwrap(), b"deno");
+    assert!(!data_dir.join("bin").join("deno.zip").exists());
+    assert!(!std::path::Path::new("pwned").exists());
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{ffmpeg_calls, install_fake_ffmpeg, recorded_calls, sample_info_json, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::live::LiveStatus;
+use cyber_ytdl_lib::process::StopSignal;
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, DownloadRequest};
+use std::time::Duration;
+
+fn live_info(status: &str) -> serde_json::Value {
+    let mut info = sample_info_json();
+    info["live_status"] = serde_json::json!(status);
+    info["is_live"] = serde_json::json!(status == "is_live");
+    info["release_timestamp"] = serde_json::json!(1_800_000_000);
+    info["duration"] = serde_json::Value::Null;
+    info
+}
+
+fn request(path: &std::path::Path) -> DownloadRequest {
+    DownloadRequest::new("https://www.youtube.com/live/dQw4w9WgXcQ", "video", "best", path.to_string_lossy().to_string(), "en")
+}
+
+#[tokio::test]
+async fn analyze_reports_live_status() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    FakeYtDlp::default().dump_json(live_info("is_upcoming")).install(dir.path(), &Component::YtDlp.file_name());
+
+    let meta = ytdlp::analyze(&env, &RecordingSink::default(), "https://youtu.be/dQw4w9WgXcQ", "en").await.unwrap();
+    assert_eq!(meta.live_status, LiveStatus::IsUpcoming);
+    assert_eq!(meta.scheduled_start, Some(1_800_000_000));
+}
+
+#[tokio::test]
+async fn stopping_a_recording_finalizes_the_file() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().dump_json(live_info("is_live")).records_live(&[0u8; 4096]).install(bin.path(), &Component::YtDlp.file_name());
+    install_fake_ffmpeg(bin.path(), &Component::Ffmpeg.file_name());
+    let sink = RecordingSink::default();
+    let stop = StopSignal::default();
+
+    let mut req = request(out.path());
+    req.options.live_from_start = true;
+    let (result, _) = tokio::join!(
+        ytdlp::download_with_stop(&env, &sink, &req, &stop),
+        async {
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            stop.stop();
+        }
+    );
+    let result = result.unwrap();
+
+    let final_path = out.path().join("Sample Video_best.mp4");
+    assert_eq!(result.files, std::slice::from_ref(&final_path));
+    assert!(final_path.exists());
+    assert!(!out.path().join("Sample Video_best.mp4.part").exists());
+
+    // 進度改以時間與大小回報，不送百分比
+    let live = sink.events("live-progress");
+    assert!(live.iter().any(|p| p["bytes"].as_u64() == Some(4096)));
+    assert!(sink.events("download-progress").is_empty());
+
+    let call = recorded_calls(bin.path()).pop().unwrap();
+    assert!(call.contains(&"--live-from-start".to_string()));
+    assert!(call.contains(&"--hls-use-mpegts".to_string()));
+    assert!(!call.contains(&"--load-info-json".to_string()));
+    let remux = ffmpeg_calls(bin.path()).pop().unwrap();
+    assert!(remux.iter().any(|a| a.ends_with("Sample Video_best.mp4.part")));
+}
+
+#[tokio::test]
+async fn upcoming_stream_requires_waiting() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().dump_json(live_info("is_upcoming")).install(bin.path(), &Component::YtDlp.file_name());
+
+    let err = ytdlp::download(&env, &RecordingSink::default(), &request(out.path())).await.unwrap_err();
+    assert!(err.contains("not started"));
+    assert_eq!(recorded_calls(bin.path()).len(), 1);
+
+    // 等待開播：yt-dlp 結束但沒有錄到任何內容
+    let mut req = request(out.path());
+    req.options.wait_for_start = true;
+    let err = ytdlp::download(&env, &RecordingSink::default(), &req).await.unwrap_err();
+    assert!(err.contains("Nothing was recorded"));
+    let call = recorded_calls(bin.path()).pop().unwrap();
+    let wait = call.iter().position(|a| a == "--wait-for-video").unwrap();
+    assert_eq!(call[wait + 1], "60");
+    assert!(call.contains(&"--no-live-from-start".to_string()));
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::{sample_info_json, FakeYtDlp, RecordingSink, TestServer};
+use cyber_ytdl_lib::medialib;
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, DownloadRequest};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn episodes_are_named_by_channel_and_air_date() {
+    let info = json!({
+        "id": "dQw4w9WgXcQ",
+        "title": "Q&A: <Live>",
+        "channel": "Tech. Talk/Show",
+        "upload_date": "20240315",
+        "description": "Questions & answers",
+        "duration": 1830,
+        "extractor_key": "Youtube"
+    });
+    let path = medialib::episode_path(Path::new("/library"), &info, "mp4");
+    assert_eq!(path, Path::new("/library/Tech. Talk_Show/Season 2024/Tech. Talk_Show - S2024E0315 - Q&A_ _Live_.mp4"));
+
+    let nfo = medialib::episode_nfo(&info);
+    assert!(nfo.contains("<title>Q&amp;A: &lt;Live&gt;</title>"), "{}", nfo);
+    assert!(nfo.contains("<plot>Questions &amp; answers</plot>"));
+    assert!(nfo.contains("<aired>2024-03-15</aired>"));
+    assert!(nfo.contains("<studio>Tech. Talk/Show</studio>"));
+    assert!(nfo.contains("<runtime>31</runtime>"));
+    assert!(nfo.contains("<uniqueid type=\"youtube\" default=\"true\">dQw4w9WgXcQ</uniqueid>"));
+    assert!(medialib::show_nfo(&info).contains("<title>Tech. Talk/Show</title>"));
+
+    // 沒有上傳日期時以下載當天為準
+    let undated = json!({ "title": "Clip", "uploader": "Someone" });
+    assert_eq!(medialib::aired_date(&undated), chrono::Local::now().format("%Y%m%d").to_string());
+}
+
+#[tokio::test]
+async fn media_library_downloads_write_nfo_and_artwork() {
+    let server = TestServer::start().await;
+    server.route("/thumb.jpg", 200, "image/jpeg", b"jpeg".to_vec());
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    let sink = RecordingSink::default();
+    let mut info = sample_info_json();
+    info["upload_date"] = "20240315".into();
+    info["thumbnail"] = format!("{}/thumb.jpg", server.base_url).into();
+    FakeYtDlp::default().dump_json(info).writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+
+    let mut req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.path().to_string_lossy(), "en");
+    req.options.media_library = true;
+    let result = ytdlp::download(&env, &sink, &req).await.unwrap();
+
+    let season = out.path().join("Sample Channel/Season 2024");
+    let episode = season.join("Sample Channel - S2024E0315 - Sample Video.mp4");
+    assert_eq!(result.files, vec![episode.clone()]);
+    let nfo = std::fs::read_to_string(season.join("Sample Channel - S2024E0315 - Sample Video.nfo")).unwrap();
+    assert!(nfo.contains("<title>Sample Video</title>") && nfo.contains("<runtime>4</runtime>"), "{}", nfo);
+    assert_eq!(std::fs::read(season.join("Sample Channel - S2024E0315 - Sample Video-thumb.jpg")).unwrap(), b"jpeg");
+    for name in ["tvshow.nfo", "poster.jpg", "fanart.jpg"] {
+        assert!(out.path().join("Sample Channel").join(name).exists(), "{}", name);
+    }
+
+    // 再下載一次：檔名加上序號，節目層的圖片不覆寫
+    std::fs::write(out.path().join("Sample Channel/poster.jpg"), b"custom").unwrap();
+    let again = ytdlp::download(&env, &sink, &req).await.unwrap();
+    assert_eq!(again.files, vec![season.join("Sample Channel - S2024E0315 - Sample Video_1.mp4")]);
+    assert!(season.join("Sample Channel - S2024E0315 - Sample Video_1.nfo").exists());
+    assert_eq!(std::fs::read(out.path().join("Sample Channel/poster.jpg")).unwrap(), b"custom");
+}
+
+#[tokio::test]
+async fn media_library_rejects_custom_file_names() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    let sink = RecordingSink::default();
+    FakeYtDlp::default().writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+
+    let mut req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.path().to_string_lossy(), "en");
+    req.options.media_library = true;
+    req.options.file_name = Some("My Clip".into());
+    let err = ytdlp::download(&env, &sink, &req).await.unwrap_err();
+    assert!(err.contains("Media library output cannot be combined"), "{}", err);
+    assert_eq!(std::fs::read_dir(out.path()).unwrap().count(), 0);
+}
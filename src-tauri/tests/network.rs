@@ -0,0 +1,69 @@
+mod common;
+
+use common::{recorded_calls, FakeYtDlp, RecordingSink, TestServer};
+use cyber_ytdl_lib::network::IpVersion;
+use cyber_ytdl_lib::settings::NetworkSettings;
+use cyber_ytdl_lib::{components, ytdlp, AppEnv, Component, EnvState};
+
+fn value_after<'a>(call: &'a [String], flag: &str) -> Option<&'a str> {
+    call.windows(2).find(|w| w[0] == flag).map(|w| w[1].as_str())
+}
+
+#[tokio::test]
+async fn yt_dlp_receives_network_options_with_site_overrides() {
+    let bin = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    env.settings.network = NetworkSettings {
+        proxy: Some("socks5://127.0.0.1:1080".into()),
+        site_proxies: [("vimeo.com".to_string(), "direct".to_string())].into(),
+        source_address: Some("10.0.0.5".into()),
+        ip_version: IpVersion::V4,
+        geo_bypass_country: Some("jp".into()),
+    };
+    FakeYtDlp::default().install(bin.path(), &Component::YtDlp.file_name());
+
+    ytdlp::analyze(&env, &RecordingSink::default(), "https://youtu.be/dQw4w9WgXcQ", "en").await.unwrap();
+    ytdlp::analyze(&env, &RecordingSink::default(), "https://player.vimeo.com/video/1", "en").await.unwrap();
+
+    let calls = recorded_calls(bin.path());
+    assert_eq!(value_after(&calls[0], "--proxy"), Some("socks5://127.0.0.1:1080"));
+    assert_eq!(value_after(&calls[0], "--source-address"), Some("10.0.0.5"));
+    assert_eq!(value_after(&calls[0], "--xff"), Some("JP"));
+    assert!(calls[0].contains(&"--force-ipv4".to_string()));
+    assert_eq!(value_after(&calls[1], "--proxy"), Some(""));
+}
+
+#[tokio::test]
+async fn http_requests_go_through_the_configured_proxy() {
+    // 本機測試伺服器扮演 HTTP 代理：收到的請求目標是完整網址
+    let proxy = TestServer::start().await;
+    proxy.route("http://github.invalid/repos/yt-dlp/yt-dlp/releases/latest", 200, "application/json", r#"{"tag_name":"2026.09.30"}"#);
+    let dir = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(dir.path()).with_github_api_base("http://github.invalid");
+
+    env.settings.network.proxy = Some(proxy.base_url.clone());
+    assert_eq!(components::remote_yt_dlp_version(&env).await.unwrap(), "2026.09.30");
+
+    // 全域代理無法連線，但 github.invalid 有自己的代理設定
+    env.settings.network.proxy = Some("http://127.0.0.1:9".into());
+    env.settings.network.site_proxies.insert("github.invalid".into(), proxy.base_url.clone());
+    assert_eq!(components::remote_yt_dlp_version(&env).await.unwrap(), "2026.09.30");
+    assert_eq!(proxy.hits().len(), 2);
+}
+
+#[test]
+fn invalid_network_settings_are_not_saved() {
+    let dir = tempfile::tempdir().unwrap();
+    let state = EnvState::new(AppEnv::new(dir.path()));
+
+    for network in [
+        NetworkSettings { proxy: Some("ftp://proxy:21".into()), ..Default::default() },
+        NetworkSettings { geo_bypass_country: Some("USA".into()), ..Default::default() },
+        NetworkSettings { source_address: Some("::1".into()), ip_version: IpVersion::V4, ..Default::default() },
+    ] {
+        let mut settings = state.snapshot().settings;
+        settings.network = network;
+        assert!(state.update_settings(settings).is_err());
+    }
+    assert!(!state.snapshot().settings_path().exists());
+}
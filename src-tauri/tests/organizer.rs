@@ -0,0 +1,109 @@
+mod common;
+
+use common::{sample_info_json, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::history;
+use cyber_ytdl_lib::organizer::{self, ConflictPolicy, MediaFacts, MoveOutcome, OrganizeRule, RuleConditions};
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, DownloadRequest};
+use std::path::PathBuf;
+
+fn facts(folder: &str, files: Vec<PathBuf>) -> MediaFacts {
+    MediaFacts {
+        url: "https://music.youtube.com/watch?v=dQw4w9WgXcQ".into(),
+        site: "music.youtube.com".into(),
+        title: "Live at ../Wembley".into(),
+        uploader: Some("AC/DC".into()),
+        upload_date: Some("20240315".into()),
+        mode: "audio".into(),
+        quality: "bestaudio".into(),
+        duration: Some(300.0),
+        folder: folder.into(),
+        files,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn rules_match_conditions_and_render_safe_paths() {
+    let item = facts("/downloads", Vec::new());
+    let when = |conditions: RuleConditions| conditions.matches(&item);
+    assert!(when(RuleConditions { site: Some("youtube.com".into()), mode: Some("audio".into()), ..Default::default() }));
+    assert!(when(RuleConditions { uploader: Some("(?i)^ac/dc$".into()), min_duration: Some(120.0), ..Default::default() }));
+    assert!(!when(RuleConditions { site: Some("vimeo.com".into()), ..Default::default() }));
+    assert!(!when(RuleConditions { max_duration: Some(60.0), ..Default::default() }));
+    assert!(!when(RuleConditions { playlist: Some("Mix".into()), ..Default::default() }));
+
+    assert_eq!(organizer::render("Music/{uploader}/{year}-{month}/{playlist}", &item), "Music/AC_DC/2024-03/Unknown");
+    assert_eq!(organizer::render("{title}", &item), "Live at .._Wembley");
+
+    let rule = |folder: &str, file_name: Option<&str>| OrganizeRule { name: "r".into(), folder: folder.into(), file_name: file_name.map(str::to_string), ..Default::default() };
+    assert!(rule("Music/{artist}", None).validate().unwrap_err().contains("Unknown placeholder {artist}"));
+    assert!(rule("../outside", None).validate().is_err());
+    assert!(rule("", Some("{uploader}/{title}")).validate().is_err());
+    assert!(rule("", Some("{date} - {title}")).validate().is_ok());
+}
+
+#[test]
+fn conflicts_follow_the_rule_policy() {
+    let dir = tempfile::tempdir().unwrap();
+    let folder = dir.path().to_string_lossy().to_string();
+    let tracks = [dir.path().join("01 - Intro.mp3"), dir.path().join("02 - Outro.mp3")];
+    for file in &tracks {
+        common::touch(file);
+    }
+    common::touch(&dir.path().join("Sorted/01 - Intro.mp3"));
+    let mut files = tracks.to_vec();
+    files.push(dir.path().join("deleted.mp3"));
+    let item = facts(&folder, files);
+
+    let mut rule = OrganizeRule { name: "sort".into(), folder: "Sorted".into(), ..Default::default() };
+    let plan = organizer::plan(std::slice::from_ref(&rule), &item);
+    assert_eq!(plan.rule.as_deref(), Some("sort"));
+    let outcomes: Vec<MoveOutcome> = plan.moves.iter().map(|m| m.outcome).collect();
+    assert_eq!(outcomes, [MoveOutcome::Renamed, MoveOutcome::Move, MoveOutcome::Missing]);
+    assert_eq!(plan.moves[0].to, dir.path().join("Sorted/01 - Intro_1.mp3"));
+
+    rule.on_conflict = ConflictPolicy::Skip;
+    let plan = organizer::plan(std::slice::from_ref(&rule), &item);
+    let (files, errors) = organizer::execute(&plan);
+    assert!(errors.is_empty());
+    assert_eq!(files, [tracks[0].clone(), dir.path().join("Sorted/02 - Outro.mp3"), dir.path().join("deleted.mp3")]);
+    assert!(tracks[0].exists() && !tracks[1].exists());
+
+    // 停用的規則不參與比對
+    rule.enabled = false;
+    assert!(organizer::plan(&[rule], &item).rule.is_none());
+}
+
+#[tokio::test]
+async fn finished_downloads_are_moved_and_history_can_be_previewed() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    let sink = RecordingSink::default();
+    let mut info = sample_info_json();
+    info["upload_date"] = "20240315".into();
+    FakeYtDlp::default().dump_json(info).writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+
+    env.settings.organizer_rules = vec![
+        OrganizeRule { name: "audio".into(), conditions: RuleConditions { mode: Some("audio".into()), ..Default::default() }, folder: "Music".into(), ..Default::default() },
+        OrganizeRule { name: "channels".into(), folder: "Channels/{uploader}/{year}".into(), file_name: Some("{date} {title}".into()), ..Default::default() },
+    ];
+    let req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.path().to_string_lossy(), "en");
+    let result = ytdlp::download(&env, &sink, &req).await.unwrap();
+
+    let moved = out.path().join("Channels/Sample Channel/2024/20240315 Sample Video.mp4");
+    assert_eq!(result.files, vec![moved.clone()]);
+    assert_eq!(std::fs::read(&moved).unwrap(), b"video");
+    assert!(sink.logs().iter().any(|l| l.contains("[channels] Moved to")));
+    let entry = &history::load(&env.history_path()).unwrap()[0];
+    assert_eq!((entry.files.clone(), entry.uploader.as_deref()), (vec![moved.clone()], Some("Sample Channel")));
+
+    // 試跑：已在目的地；改用未儲存的規則時只計算不搬移
+    let plan = organizer::preview(&env, &entry.id, None).unwrap();
+    assert_eq!(plan.moves[0].outcome, MoveOutcome::Unchanged);
+    let draft = [OrganizeRule { name: "flat".into(), folder: "/archive/{site}".into(), ..Default::default() }];
+    let plan = organizer::preview(&env, &entry.id, Some(&draft)).unwrap();
+    assert_eq!(plan.moves[0].to, PathBuf::from("/archive/vimeo.com/20240315 Sample Video.mp4"));
+    assert!(moved.exists());
+    assert!(organizer::preview(&env, "missing", None).is_err());
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::{FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::process::{self, StreamLine};
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, DownloadRequest};
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn chatty_stderr_does_not_deadlock() {
+    // 輸出 stdout 後塞滿 stderr (遠超過管道緩衝)；只讀 stdout 到結束的實作會卡住
+    let bin = tempfile::tempdir().unwrap();
+    let warnings: Vec<String> = (0..20000).map(|i| format!("warning line {}", i)).collect();
+    let warnings: Vec<&str> = warnings.iter().map(String::as_str).collect();
+    let fake = FakeYtDlp::default().progress(&["done"]).stderr(&warnings).install(bin.path(), &Component::YtDlp.file_name());
+    let cmd = process::command(fake);
+
+    let mut stdout = Vec::new();
+    let mut stderr_count = 0;
+    let outcome = tokio::time::timeout(Duration::from_secs(20), process::run_streaming(cmd, |line| match line {
+        StreamLine::Stdout(l) => stdout.push(l),
+        StreamLine::Stderr(_) => stderr_count += 1,
+    })).await.expect("deadlocked").unwrap();
+
+    assert!(outcome.status.success());
+    assert_eq!(stdout, ["done"]);
+    assert_eq!(stderr_count, 20000);
+    assert_eq!(outcome.stderr_tail.len(), 50);
+}
+
+#[tokio::test]
+async fn capture_times_out_and_kills_child() {
+    let bin = tempfile::tempdir().unwrap();
+    let cmd = process::command(FakeYtDlp::default().hangs().install(bin.path(), &Component::YtDlp.file_name()));
+
+    let started = Instant::now();
+    let err = process::run_capture(cmd, Duration::from_millis(200)).await.unwrap_err();
+
+    assert!(err.contains("Timed out"));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn stderr_lines_are_forwarded_live_as_logs() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default()
+        .stderr(&["WARNING: [youtube] nsig extraction slow"])
+        .install(bin.path(), &Component::YtDlp.file_name());
+    let sink = RecordingSink::default();
+
+    let req = DownloadRequest::new("https://www.youtube.com/watch?v=dQw4w9WgXcQ", "audio", "bestaudio", out.path().to_string_lossy().to_string(), "en");
+    ytdlp::download(&env, &sink, &req).await.unwrap();
+
+    assert!(sink.logs().iter().any(|l| l.contains("nsig extraction slow")));
+}
+
+// 以 shell 模擬程序群組與 SIGINT
+#[cfg(unix)]
+#[tokio::test]
+async fn stopping_waits_for_grandchildren_to_finish_their_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let done = dir.path().join("done.txt");
+    // 模擬 yt-dlp 啟動的 ffmpeg：收到 SIGINT 後花一點時間寫完檔案，且不持有 yt-dlp 的輸出管道
+    let inner = dir.path().join("ffmpeg.sh");
+    std::fs::write(&inner, format!("trap 'sleep 0.5; echo flushed > \"{}\"; exit 0' INT\nwhile :; do sleep 0.1; done\n", done.display())).unwrap();
+    let mut cmd = process::command("sh");
+    cmd.args(["-c", &format!("sh '{}' >/dev/null 2>&1 </dev/null; exit 0", inner.display())]);
+
+    let stop = async { tokio::time::sleep(Duration::from_millis(300)).await };
+    let outcome = tokio::time::timeout(Duration::from_secs(20), process::run_streaming_until(cmd, stop, |_| {})).await.expect("hung").unwrap();
+
+    assert!(outcome.stopped);
+    assert_eq!(std::fs::read_to_string(&done).unwrap().trim(), "flushed");
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use chrono::{Duration as ChronoDuration, Local, NaiveTime};
+use common::{install_fake_ffmpeg, recorded_calls, sample_info_json, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::queue::{JobState, TimeWindow};
+use cyber_ytdl_lib::{AppEnv, Component, DownloadQueue, DownloadRequest, JobSchedule};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn request(out: &Path) -> DownloadRequest {
+    DownloadRequest::new("https://vimeo.com/1", "video", "best", out.to_string_lossy().to_string(), "en")
+}
+
+fn clock(t: NaiveTime) -> String {
+    t.format("%H:%M:%S").to_string()
+}
+
+#[tokio::test]
+async fn scheduled_jobs_persist_with_next_run_times() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("queue.json");
+    let queue = DownloadQueue::load(&path);
+    let start_at = Local::now().timestamp() + 3600;
+
+    let later = queue.enqueue(request(dir.path()), JobSchedule { start_at: Some(start_at), window: None }).unwrap();
+    assert_eq!(later.state, JobState::Scheduled);
+    assert_eq!(later.next_run, Some(start_at));
+
+    // 時段尚未開始：下次執行時間為下一次時段開始
+    let now = Local::now().time();
+    let window = TimeWindow { start: clock(now + ChronoDuration::hours(2)), end: clock(now + ChronoDuration::hours(3)) };
+    let night = queue.enqueue(request(dir.path()), JobSchedule { start_at: None, window: Some(window) }).unwrap();
+    let wait = night.next_run.unwrap() - Local::now().timestamp();
+    assert!((7000..=7300).contains(&wait), "{}", wait);
+    assert!(!queue.has_due());
+
+    let reloaded = DownloadQueue::load(&path);
+    let ids: Vec<String> = reloaded.list().into_iter().map(|j| j.id).collect();
+    assert_eq!(ids, [later.id, night.id]);
+    assert!(queue.enqueue(request(dir.path()), JobSchedule { start_at: None, window: Some(TimeWindow { start: "1am".into(), end: "06:00".into() }) }).is_err());
+}
+
+#[tokio::test]
+async fn due_job_runs_to_completion() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+    let queue = DownloadQueue::load(bin.path().join("queue.json"));
+    let job = queue.enqueue(request(out.path()), JobSchedule::default()).unwrap();
+    let sink = RecordingSink::default();
+
+    assert!(queue.has_due());
+    assert!(queue.run_next(&env, &sink).await);
+    assert!(!queue.run_next(&env, &sink).await);
+
+    let done = queue.get(&job.id).unwrap();
+    assert_eq!(done.state, JobState::Completed);
+    assert_eq!(done.next_run, None);
+    assert_eq!(done.result.unwrap().files, [out.path().join("Sample Video_best.mp4")]);
+    assert_eq!(recorded_calls(bin.path()).len(), 2);
+    assert!(!sink.events("queue-updated").is_empty());
+}
+
+#[tokio::test]
+async fn window_end_and_user_pause_are_distinguished() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().records_live(b"partial").install(bin.path(), &Component::YtDlp.file_name());
+    let queue = Arc::new(DownloadQueue::load(bin.path().join("queue.json")));
+    let sink = RecordingSink::default();
+
+    // 時段在兩秒後結束
+    let now = Local::now().time();
+    let window = TimeWindow { start: clock(now - ChronoDuration::hours(1)), end: clock(now + ChronoDuration::seconds(2)) };
+    let job = queue.enqueue(request(out.path()), JobSchedule { start_at: None, window: Some(window) }).unwrap();
+    assert!(queue.run_next(&env, &sink).await);
+    let paused = queue.get(&job.id).unwrap();
+    assert_eq!(paused.state, JobState::PausedBySchedule);
+    assert!(paused.next_run.unwrap() > Local::now().timestamp());
+
+    // 使用者暫停：不會自動重新開始
+    queue.remove(&job.id).unwrap();
+    let job = queue.enqueue(request(out.path()), JobSchedule::default()).unwrap();
+    let (ran, _) = tokio::join!(queue.run_next(&env, &sink), async {
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+        queue.pause(&job.id).unwrap();
+    });
+    assert!(ran);
+    let paused = queue.get(&job.id).unwrap();
+    assert_eq!(paused.state, JobState::PausedByUser);
+    assert_eq!(paused.next_run, None);
+    assert!(!queue.has_due());
+
+    queue.resume(&job.id).unwrap();
+    assert_eq!(queue.get(&job.id).unwrap().state, JobState::Scheduled);
+    assert!(queue.has_due());
+}
+
+#[tokio::test]
+async fn interrupted_live_recordings_stay_paused() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    let mut info = sample_info_json();
+    info["live_status"] = "is_live".into();
+    info["is_live"] = true.into();
+    info["duration"] = serde_json::Value::Null;
+    FakeYtDlp::default().dump_json(info).records_live(b"partial").install(bin.path(), &Component::YtDlp.file_name());
+    install_fake_ffmpeg(bin.path(), &Component::Ffmpeg.file_name());
+    let queue = Arc::new(DownloadQueue::load(bin.path().join("queue.json")));
+    let sink = RecordingSink::default();
+
+    // 時段結束：已錄到的內容會封裝，但任務等下一個時段繼續錄製
+    let now = Local::now().time();
+    let window = TimeWindow { start: clock(now - ChronoDuration::hours(1)), end: clock(now + ChronoDuration::seconds(2)) };
+    let job = queue.enqueue(request(out.path()), JobSchedule { start_at: None, window: Some(window) }).unwrap();
+    assert!(queue.run_next(&env, &sink).await);
+    let paused = queue.get(&job.id).unwrap();
+    assert_eq!(paused.state, JobState::PausedBySchedule);
+    assert!(paused.result.unwrap().stopped);
+
+    // 使用者暫停
+    queue.remove(&job.id).unwrap();
+    let job = queue.enqueue(request(out.path()), JobSchedule::default()).unwrap();
+    let (ran, _) = tokio::join!(queue.run_next(&env, &sink), async {
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+        queue.pause(&job.id).unwrap();
+    });
+    assert!(ran);
+    assert_eq!(queue.get(&job.id).unwrap().state, JobState::PausedByUser);
+}
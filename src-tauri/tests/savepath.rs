@@ -0,0 +1,82 @@
+mod common;
+
+use common::{recorded_calls, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::history;
+use cyber_ytdl_lib::savepath;
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, DownloadRequest};
+
+#[test]
+fn folders_are_created_only_on_request() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("new/sub");
+    let missing = missing.to_str().unwrap();
+
+    assert!(savepath::check_folder(missing, false).unwrap_err().contains("does not exist"));
+    assert!(savepath::check_folder(missing, true).unwrap());
+    assert!(!savepath::check_folder(missing, true).unwrap());
+    // 探測檔不會留下
+    assert_eq!(std::fs::read_dir(missing).unwrap().count(), 0);
+
+    let file = dir.path().join("file.txt");
+    common::touch(&file);
+    assert!(savepath::check_folder(file.to_str().unwrap(), true).unwrap_err().contains("Not a folder"));
+    assert!(savepath::check_folder("relative/dir", true).unwrap_err().contains("absolute"));
+    assert!(savepath::check_folder("  ", true).is_err());
+}
+
+// Linux / macOS 的掛載點；Windows 的磁碟機代號見 volume_root 的前綴處理
+#[cfg(unix)]
+#[test]
+fn missing_drives_fall_back_without_creating_folders() {
+    use cyber_ytdl_lib::settings::DownloadSettings;
+    use std::path::{Path, PathBuf};
+
+    assert_eq!(savepath::volume_root(Path::new("/media/alice/USB/Videos")), Some(PathBuf::from("/media/alice/USB")));
+    assert_eq!(savepath::volume_root(Path::new("/Volumes/NAS/Movies")), Some(PathBuf::from("/Volumes/NAS")));
+    assert_eq!(savepath::volume_root(Path::new("/home/alice/Videos")), None);
+
+    let unplugged = format!("/mnt/cyber-ytdl-missing-{}/Videos", std::process::id());
+    let fallback = tempfile::tempdir().unwrap();
+    let default_folder = fallback.path().join("Downloads");
+    let settings = DownloadSettings { default_folder: Some(default_folder.to_string_lossy().into()), create_missing: true };
+
+    let save = savepath::resolve(&settings, &unplugged, true).unwrap();
+    assert!(save.fallback && save.created);
+    assert_eq!(save.path, default_folder.to_string_lossy());
+    assert!(save.reason.unwrap().contains("not available"));
+    assert!(!Path::new(&unplugged).parent().unwrap().exists());
+
+    // 沒有預設資料夾時直接回報錯誤
+    assert!(savepath::resolve(&DownloadSettings::default(), &unplugged, true).is_err());
+
+    // 與上層同一個檔案系統的資料夾：空的視為未掛載，有內容的是一般資料夾 (例如根檔案系統上的 /mnt/data)
+    let plain = tempfile::tempdir().unwrap();
+    assert!(!savepath::volume_available(plain.path()));
+    std::fs::create_dir(plain.path().join("videos")).unwrap();
+    assert!(savepath::volume_available(plain.path()));
+}
+
+#[tokio::test]
+async fn download_uses_the_default_folder_when_the_target_is_unusable() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    let sink = RecordingSink::default();
+    let blocked = out.path().join("blocked");
+    common::touch(&blocked);
+
+    // 沒有預設資料夾：在啟動 yt-dlp 之前就失敗，並寫入紀錄
+    FakeYtDlp::default().writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+    let req = DownloadRequest::new("https://vimeo.com/1", "video", "best", blocked.to_string_lossy(), "en");
+    let err = ytdlp::download(&env, &sink, &req).await.unwrap_err();
+    assert!(err.contains("Cannot use the download folder"), "{}", err);
+    assert!(recorded_calls(bin.path()).is_empty());
+    assert_eq!(history::load(&env.history_path()).unwrap().len(), 1);
+
+    let fallback = out.path().join("fallback");
+    env.settings.downloads.default_folder = Some(fallback.to_string_lossy().into());
+    let result = ytdlp::download(&env, &sink, &req).await.unwrap();
+    assert!(result.files.iter().all(|f| f.starts_with(&fallback)), "{:?}", result.files);
+    assert!(sink.logs().iter().any(|l| l.contains("saving to the default folder instead")));
+    assert_eq!(history::load(&env.history_path()).unwrap()[1].folder, fallback.to_string_lossy());
+}
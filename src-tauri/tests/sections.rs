@@ -0,0 +1,132 @@
+mod common;
+
+use common::{recorded_calls, sample_info_json, touch, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::sections::{parse_timestamp, time_label};
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, DownloadRequest};
+
+fn info_with_chapters() -> serde_json::Value {
+    let mut info = sample_info_json();
+    info["duration"] = serde_json::json!(7200);
+    info["chapters"] = serde_json::json!([
+        { "title": "Intro", "start_time": 0.0, "end_time": 60.0 },
+        { "title": "Q&A (part 1)", "start_time": 60.0, "end_time": 3600.0 },
+        { "title": "Outro", "start_time": 3600.0, "end_time": 7200.0 }
+    ]);
+    info
+}
+
+fn arg_after<'a>(call: &'a [String], flag: &str) -> Vec<&'a str> {
+    call.windows(2).filter(|w| w[0] == flag).map(|w| w[1].as_str()).collect()
+}
+
+#[test]
+fn timestamps_accept_common_forms() {
+    assert_eq!(parse_timestamp("83").unwrap(), 83.0);
+    assert_eq!(parse_timestamp("1:23").unwrap(), 83.0);
+    assert_eq!(parse_timestamp("01:01:23.5").unwrap(), 3683.5);
+    assert!(parse_timestamp("1:75").is_err());
+    assert!(parse_timestamp("abc").is_err());
+    assert!(parse_timestamp("1:2:3:4").is_err());
+    // f64 能解析但不是時間的字串
+    for raw in ["nan", "inf", "-inf", "1e3", "-0", "+5", "1.2.3", "1.5:30", ".5", "5.", "1: 2", "0x10", &"9".repeat(400)] {
+        assert!(parse_timestamp(raw).is_err(), "{}", raw);
+    }
+    assert_eq!(time_label(3683.5), "1h01m23s");
+}
+
+#[tokio::test]
+async fn analyze_lists_chapters_and_duration() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    FakeYtDlp::default().dump_json(info_with_chapters()).install(dir.path(), &Component::YtDlp.file_name());
+
+    let meta = ytdlp::analyze(&env, &RecordingSink::default(), "https://vimeo.com/1", "en").await.unwrap();
+
+    assert_eq!(meta.duration, Some(7200.0));
+    let titles: Vec<&str> = meta.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, ["Intro", "Q&A (part 1)", "Outro"]);
+}
+
+#[tokio::test]
+async fn time_range_maps_to_download_sections_and_filename() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().dump_json(info_with_chapters()).install(bin.path(), &Component::YtDlp.file_name());
+
+    let mut req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.path().to_string_lossy().to_string(), "en");
+    req.options.start_time = Some("1:00:00".into());
+    req.options.end_time = Some("1:00:30".into());
+    req.options.precise_cuts = true;
+    ytdlp::download(&env, &RecordingSink::default(), &req).await.unwrap();
+
+    let call = recorded_calls(bin.path()).pop().unwrap();
+    assert_eq!(arg_after(&call, "--download-sections"), ["*3600-3630"]);
+    assert!(call.contains(&"--force-keyframes-at-cuts".to_string()));
+    assert!(arg_after(&call, "-o")[0].ends_with("Sample Video_best_1h00m00s-1h00m30s.mp4"));
+}
+
+#[tokio::test]
+async fn chapters_are_cut_by_time_and_split_into_files() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().dump_json(info_with_chapters()).writes_output(b"section").install(bin.path(), &Component::YtDlp.file_name());
+    // 與上一次下載同名的第一段，以及資料夾中不相關的 stem_ 檔案
+    touch(&out.path().join("Sample Video_bestaudio_ch2+3_01.mp3"));
+    touch(&out.path().join("Sample Video_bestaudio_ch2+3_1_notes.txt"));
+
+    let mut req = DownloadRequest::new("https://vimeo.com/1", "audio", "bestaudio", out.path().to_string_lossy().to_string(), "en");
+    req.options.chapters = vec!["Outro".into(), "Q&A (part 1)".into()];
+    let result = ytdlp::download(&env, &RecordingSink::default(), &req).await.unwrap();
+
+    let call = recorded_calls(bin.path()).pop().unwrap();
+    assert_eq!(arg_after(&call, "--download-sections"), ["*60-3600", "*3600-7200"]);
+    assert!(arg_after(&call, "-o")[0].ends_with("Sample Video_bestaudio_ch2+3_1_%(section_number)02d.%(ext)s"));
+    let names: Vec<String> = result.files.iter().map(|f| f.file_name().unwrap().to_string_lossy().to_string()).collect();
+    assert_eq!(names, ["Sample Video_bestaudio_ch2+3_1_01.mp3", "Sample Video_bestaudio_ch2+3_1_02.mp3"]);
+}
+
+#[tokio::test]
+async fn duplicate_chapter_titles_are_selected_by_index() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    let mut info = info_with_chapters();
+    info["chapters"][2]["title"] = "Intro".into();
+    FakeYtDlp::default().dump_json(info).install(bin.path(), &Component::YtDlp.file_name());
+
+    let mut req = DownloadRequest::new("https://vimeo.com/1", "video", "best", out.path().to_string_lossy().to_string(), "en");
+    req.options.chapters = vec!["Intro".into()];
+    let err = ytdlp::download(&env, &RecordingSink::default(), &req).await.unwrap_err();
+    assert!(err.contains("select it by index"), "{}", err);
+
+    req.options.chapters.clear();
+    req.options.chapter_indices = vec![2];
+    ytdlp::download(&env, &RecordingSink::default(), &req).await.unwrap();
+    let call = recorded_calls(bin.path()).pop().unwrap();
+    assert_eq!(arg_after(&call, "--download-sections"), ["*3600-7200"]);
+    assert!(arg_after(&call, "-o")[0].ends_with("Sample Video_best_ch3.mp4"));
+}
+
+#[tokio::test]
+async fn invalid_ranges_fail_before_download() {
+    let bin = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().dump_json(info_with_chapters()).install(bin.path(), &Component::YtDlp.file_name());
+
+    for (start, end, chapter) in [
+        (Some("0:30"), Some("0:10"), None),
+        (Some("3:00:00"), None, None),
+        (None, None, Some("Missing chapter")),
+        (Some("0:10"), None, Some("Intro")),
+    ] {
+        let mut req = DownloadRequest::new("https://vimeo.com/1", "video", "best", bin.path().to_string_lossy().to_string(), "en");
+        req.options.start_time = start.map(String::from);
+        req.options.end_time = end.map(String::from);
+        req.options.chapters = chapter.map(|c| vec![c.to_string()]).unwrap_or_default();
+        assert!(ytdlp::download(&env, &RecordingSink::default(), &req).await.is_err());
+    }
+    // 只有第一次解析資訊，沒有任何下載呼叫
+    assert_eq!(recorded_calls(bin.path()).len(), 1);
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::RecordingSink;
+use cyber_ytdl_lib::{history, ytdlp, AppEnv, DownloadProfile, DownloadRequest, EnvState, Settings};
+
+#[test]
+fn unreadable_settings_are_reported_and_backed_up_before_saving() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("settings.json");
+    std::fs::write(&path, "{\"network\": ").unwrap();
+    assert!(Settings::load(&path).unwrap_err().contains("Cannot read settings"));
+    assert!(Settings::load(&dir.path().join("missing.json")).is_ok());
+
+    let state = EnvState::new(AppEnv::from_app_dir(dir.path()));
+    assert!(state.settings().unwrap_err().contains("Cannot read settings"));
+
+    // 儲存新的設定時保留原本無法讀取的檔案
+    state.update_settings(Settings::default()).unwrap();
+    assert!(state.settings().is_ok());
+    assert!(Settings::load(&path).is_ok());
+    let backups: Vec<String> = std::fs::read_dir(dir.path()).unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|n| n.starts_with("settings.corrupt-"))
+        .collect();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(std::fs::read_to_string(dir.path().join(&backups[0])).unwrap(), "{\"network\": ");
+}
+
+#[tokio::test]
+async fn unknown_profiles_are_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(dir.path());
+    env.settings.profiles = vec![DownloadProfile { name: "podcasts".into(), mode: "audio".into(), ..Default::default() }];
+    assert_eq!(env.settings.profile(Some("podcasts")).unwrap().mode, "audio");
+    assert_eq!(env.settings.profile(None).unwrap().mode, "video");
+    assert_eq!(env.settings.profile(Some("default")).unwrap().mode, "video");
+    assert!(env.settings.profile(Some("music")).unwrap_err().contains("Unknown profile: music"));
+
+    // 指定不存在的設定檔時不開始下載，並記錄為失敗
+    let mut req = DownloadRequest::new("https://vimeo.com/1", "video", "best", dir.path().to_string_lossy(), "en");
+    req.profile = Some("music".into());
+    assert!(ytdlp::download(&env, &RecordingSink::default(), &req).await.unwrap_err().contains("Unknown profile"));
+    assert_eq!(history::load(&env.history_path()).unwrap().len(), 1);
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use common::{recorded_calls, FakeYtDlp, RecordingSink, TestServer};
+use cyber_ytdl_lib::sponsorblock::{self, Segment};
+use cyber_ytdl_lib::{ytdlp, AppEnv, Component, DownloadRequest};
+
+const SEGMENTS: &str = r#"[
+    {"category": "sponsor", "actionType": "skip", "segment": [120.0, 150.5], "UUID": "b"},
+    {"category": "intro", "actionType": "skip", "segment": [0.0, 12.0], "UUID": "a"},
+    {"category": "sponsor", "actionType": "mute", "segment": [170.0, 175.0], "UUID": "c"}
+]"#;
+
+fn value_after<'a>(call: &'a [String], flag: &str) -> Option<&'a str> {
+    call.windows(2).find(|w| w[0] == flag).map(|w| w[1].as_str())
+}
+
+fn request(path: &std::path::Path, remove: &[&str], mark: &[&str]) -> DownloadRequest {
+    let mut req = DownloadRequest::new("https://youtu.be/dQw4w9WgXcQ", "video", "best", path.to_string_lossy().to_string(), "en");
+    req.options.sponsorblock_remove = remove.iter().map(|s| s.to_string()).collect();
+    req.options.sponsorblock_mark = mark.iter().map(|s| s.to_string()).collect();
+    req
+}
+
+#[tokio::test]
+async fn removed_segments_are_recorded_in_result() {
+    let server = TestServer::start().await;
+    server.route("/api/skipSegments", 200, "application/json", SEGMENTS);
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    env.settings.sponsorblock.api_base = server.base_url.clone();
+    FakeYtDlp::default().writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+
+    let result = ytdlp::download(&env, &RecordingSink::default(), &request(out.path(), &["sponsor", "intro"], &["outro", "intro"])).await.unwrap();
+
+    assert_eq!(result.removed_segments, [
+        Segment { category: "intro".into(), start: 0.0, end: 12.0 },
+        Segment { category: "sponsor".into(), start: 120.0, end: 150.5 },
+    ]);
+    assert_eq!(result.duration, Some(212.0 - 12.0 - 30.5));
+    // 剪掉的正是紀錄中的片段，yt-dlp 不再自行查詢
+    let call = recorded_calls(bin.path()).pop().unwrap();
+    let cuts: Vec<&str> = call.windows(2).filter(|w| w[0] == "--remove-chapters").map(|w| w[1].as_str()).collect();
+    assert_eq!(cuts, ["*0-12", "*120-150.5"]);
+    assert_eq!(value_after(&call, "--sponsorblock-remove"), None);
+    assert_eq!(value_after(&call, "--sponsorblock-api"), Some(server.base_url.as_str()));
+    assert_eq!(value_after(&call, "--sponsorblock-mark"), Some("outro"));
+    let queries = server.hits().iter().filter(|h| h.starts_with("/api/skipSegments?videoID=dQw4w9WgXcQ")).count();
+    assert_eq!(queries, 1);
+}
+
+#[tokio::test]
+async fn failed_query_leaves_removal_to_yt_dlp() {
+    let server = TestServer::start().await;
+    server.route("/api/skipSegments", 500, "text/plain", "down");
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    env.settings.sponsorblock.api_base = server.base_url.clone();
+    FakeYtDlp::default().writes_output(b"video").install(bin.path(), &Component::YtDlp.file_name());
+
+    let result = ytdlp::download(&env, &RecordingSink::default(), &request(out.path(), &["sponsor"], &[])).await.unwrap();
+    assert!(result.removed_segments.is_empty());
+    let call = recorded_calls(bin.path()).pop().unwrap();
+    assert_eq!(value_after(&call, "--sponsorblock-remove"), Some("sponsor"));
+    assert_eq!(value_after(&call, "--remove-chapters"), None);
+}
+
+#[test]
+fn overlapping_segments_are_counted_once() {
+    let segment = |category: &str, start, end| Segment { category: category.into(), start, end };
+    let segments = [segment("sponsor", 100.0, 130.0), segment("intro", 0.0, 10.0), segment("selfpromo", 120.0, 140.0), segment("outro", 200.0, 230.0)];
+    assert_eq!(sponsorblock::merge(&segments), [segment("intro", 0.0, 10.0), segment("sponsor", 100.0, 140.0), segment("outro", 200.0, 230.0)]);
+    // 結尾超出影片長度的部分不計
+    assert_eq!(sponsorblock::removed_duration(&segments, 212.0), 10.0 + 40.0 + 12.0);
+}
+
+#[tokio::test]
+async fn no_segments_is_not_an_error() {
+    let server = TestServer::start().await;
+    let bin = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    env.settings.sponsorblock.api_base = server.base_url.clone();
+
+    let segments = sponsorblock::fetch_segments(&env, "dQw4w9WgXcQ", &["sponsor".into()]).await.unwrap();
+    assert!(segments.is_empty());
+}
+
+#[tokio::test]
+async fn unknown_category_is_rejected_before_download() {
+    let bin = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().install(bin.path(), &Component::YtDlp.file_name());
+
+    let err = ytdlp::download(&env, &RecordingSink::default(), &request(bin.path(), &["sponsor", "everything"], &[])).await.unwrap_err();
+    assert!(err.contains("everything"));
+    assert!(!recorded_calls(bin.path()).iter().any(|c| c.contains(&"--progress".to_string())));
+}
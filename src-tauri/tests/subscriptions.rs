@@ -0,0 +1,189 @@
+mod common;
+
+use common::{recorded_calls, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::subscriptions::{self, SubscriptionFilters};
+use cyber_ytdl_lib::{AppEnv, Component, DownloadProfile, DownloadQueue, SubscriptionConfig, SubscriptionStore};
+use serde_json::json;
+use std::path::Path;
+
+fn channel(entries: serde_json::Value) -> serde_json::Value {
+    json!({ "_type": "playlist", "id": "UC123", "extractor_key": "YoutubeTab", "entries": entries })
+}
+
+fn video(id: &str, title: &str, duration: f64) -> serde_json::Value {
+    json!({ "id": id, "ie_key": "Youtube", "url": format!("https://www.youtube.com/watch?v={}", id), "title": title, "duration": duration })
+}
+
+fn config(url: &str, out: &Path) -> SubscriptionConfig {
+    serde_json::from_value(json!({ "url": url, "target_dir": out.to_string_lossy() })).unwrap()
+}
+
+#[test]
+fn flat_playlist_entries_are_parsed_and_filtered() {
+    let json = channel(json!([
+        video("NewVideo001", "Episode 12", 900.0),
+        { "id": "short1", "ie_key": "Youtube", "url": "https://www.youtube.com/shorts/short1", "title": "Clip", "duration": 30.0 },
+        { "id": "live1", "ie_key": "Youtube", "url": "https://www.youtube.com/watch?v=live1", "title": "Episode live", "live_status": "was_live", "timestamp": 1700000000 },
+        { "_type": "playlist", "id": "UC123_shorts", "url": "https://www.youtube.com/@x/shorts" },
+    ]));
+    let entries = subscriptions::parse_entries(&json);
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].archive_key, "youtube NewVideo001");
+    assert_eq!(entries[2].upload_date.as_deref(), Some("20231114"));
+
+    let filters = SubscriptionFilters {
+        min_duration: Some(60),
+        title_regex: Some("^Episode".into()),
+        date_after: Some("20240101".into()),
+        skip_shorts: true,
+        ..Default::default()
+    };
+    let kept: Vec<&str> = entries.iter().filter(|e| filters.matches(e)).map(|e| e.archive_key.as_str()).collect();
+    assert_eq!(kept, ["youtube NewVideo001"]);
+
+    let skip_live = SubscriptionFilters { skip_live: true, ..Default::default() };
+    assert!(!skip_live.matches(&entries[2]));
+    assert!(SubscriptionFilters { title_regex: Some("(".into()), ..Default::default() }.validate().is_err());
+    assert!(SubscriptionFilters { min_duration: Some(600), max_duration: Some(60), ..Default::default() }.validate().is_err());
+}
+
+#[tokio::test]
+async fn new_items_are_enqueued_after_the_first_check() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let mut env = AppEnv::new(bin.path());
+    let mut profile = DownloadProfile { name: "podcast".into(), mode: "audio".into(), quality: "mp3".into(), ..Default::default() };
+    profile.options.rate_limit = Some(500_000);
+    env.settings.profiles.push(profile);
+
+    let url = "https://www.youtube.com/@example/videos";
+    let store = SubscriptionStore::load(env.subscriptions_path());
+    let queue = DownloadQueue::load(env.queue_path());
+    let mut cfg = config(url, out.path());
+    cfg.profile = Some("podcast".into());
+    cfg.filters.max_duration = Some(3600);
+    let sub = store.add(cfg.clone()).unwrap();
+    assert!(store.add(cfg).is_err());
+    // 同一頻道的其他寫法也算重複訂閱
+    assert!(store.add(config("https://youtube.com/@example?si=share", out.path())).unwrap_err().contains("Already subscribed"));
+    let other = store.add(config("https://www.youtube.com/@other", out.path())).unwrap();
+    assert!(store.edit(&other.id, config("https://m.youtube.com/@example/videos", out.path())).is_err());
+    store.remove(&other.id).unwrap();
+
+    // 第一次檢查：既有影片只記錄、不下載
+    FakeYtDlp::default().dump_json(channel(json!([video("OldVideo001", "Old", 600.0)]))).install(bin.path(), &Component::YtDlp.file_name());
+    assert!(subscriptions::check(&env, &store, &queue, &sub.id).await.unwrap().is_empty());
+    let call = &recorded_calls(bin.path())[0];
+    assert!(call.contains(&"--flat-playlist".to_string()));
+    assert_eq!(call.last().unwrap(), url);
+    assert!(store.due().is_empty());
+
+    // 新影片出現：已在 archive 的與超過長度的略過，其餘加入佇列
+    std::fs::write(env.archive_path(), "youtube DoneVideo01\n").unwrap();
+    FakeYtDlp::default().dump_json(channel(json!([
+        video("NewVideo002", "Newest", 300.0),
+        video("LongVideo01", "Stream replay", 7200.0),
+        video("DoneVideo01", "Already downloaded", 300.0),
+        video("NewVideo001", "Newer", 300.0),
+        video("OldVideo001", "Old", 600.0),
+    ]))).install(bin.path(), &Component::YtDlp.file_name());
+    let jobs = subscriptions::check(&env, &store, &queue, &sub.id).await.unwrap();
+    let urls: Vec<&str> = jobs.iter().map(|j| j.request.url.as_str()).collect();
+    assert_eq!(urls, ["https://www.youtube.com/watch?v=NewVideo001", "https://www.youtube.com/watch?v=NewVideo002"]);
+    assert_eq!(jobs[0].request.mode, "audio");
+    assert_eq!(jobs[0].request.path, out.path().to_string_lossy());
+    assert_eq!(jobs[0].request.options.rate_limit, Some(500_000));
+    assert!(jobs[0].request.options.use_archive);
+
+    // 再次檢查不會重複加入
+    assert!(subscriptions::check(&env, &store, &queue, &sub.id).await.unwrap().is_empty());
+    assert_eq!(queue.list().len(), 2);
+    assert_eq!(SubscriptionStore::load(env.subscriptions_path()).get(&sub.id).unwrap().seen.len(), 4);
+}
+
+#[tokio::test]
+async fn subscription_jobs_record_the_download_archive() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default()
+        .dump_json(channel(json!([video("dQw4w9WgXcQ", "Sample Video", 212.0)])))
+        .writes_output(b"video")
+        .install(bin.path(), &Component::YtDlp.file_name());
+    let store = SubscriptionStore::load(env.subscriptions_path());
+    let queue = DownloadQueue::load(env.queue_path());
+    let mut cfg = config("https://www.youtube.com/playlist?list=PL1", out.path());
+    cfg.download_existing = true;
+    let sub = store.add(cfg).unwrap();
+    let sink = RecordingSink::default();
+
+    assert_eq!(subscriptions::check_due(&env, &sink, &store, &queue).await, 1);
+    assert!(!sink.events("subscriptions-updated").is_empty());
+    assert!(queue.run_next(&env, &sink).await);
+
+    let download = recorded_calls(bin.path()).pop().unwrap();
+    let pos = download.iter().position(|a| a == "--download-archive").unwrap();
+    assert_eq!(download[pos + 1], env.archive_path().to_string_lossy());
+    assert!(store.get(&sub.id).unwrap().last_error.is_none());
+
+    store.set_enabled(&sub.id, false).unwrap();
+    store.remove(&sub.id).unwrap();
+    assert!(store.list().is_empty());
+}
+
+#[tokio::test]
+async fn channel_roots_dates_and_profiles_are_checked() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    let store = SubscriptionStore::load(env.subscriptions_path());
+    let queue = DownloadQueue::load(env.queue_path());
+
+    assert_eq!(subscriptions::playlist_url("https://youtube.com/@example?si=x"), "https://www.youtube.com/@example/videos");
+    assert_eq!(subscriptions::playlist_url("https://www.youtube.com/channel/UC123"), "https://www.youtube.com/channel/UC123/videos");
+    assert_eq!(subscriptions::playlist_url("https://www.youtube.com/@example/streams"), "https://www.youtube.com/@example/streams");
+
+    // 平面清單沒有上傳日期：解析影片後依日期篩選
+    let mut listing = channel(json!([video("NewVideo001", "New", 300.0)]));
+    listing["upload_date"] = json!("20250101");
+    FakeYtDlp::default().dump_json(listing).install(bin.path(), &Component::YtDlp.file_name());
+    let mut cfg = config("https://www.youtube.com/@example", out.path());
+    cfg.download_existing = true;
+    cfg.filters.date_after = Some("20240101".into());
+    let sub = store.add(cfg.clone()).unwrap();
+    assert_eq!(subscriptions::check(&env, &store, &queue, &sub.id).await.unwrap().len(), 1);
+    let calls = recorded_calls(bin.path());
+    assert_eq!(calls[0].last().unwrap(), "https://www.youtube.com/@example/videos");
+    assert_eq!(calls[1].last().unwrap(), "https://www.youtube.com/watch?v=NewVideo001");
+
+    // 已刪除的設定檔回報錯誤，不改用預設設定
+    cfg.url = "https://www.youtube.com/playlist?list=PL1".into();
+    cfg.profile = Some("deleted".into());
+    let sub = store.add(cfg).unwrap();
+    assert!(subscriptions::check(&env, &store, &queue, &sub.id).await.unwrap_err().contains("deleted"));
+    assert!(store.get(&sub.id).unwrap().last_error.is_some());
+    assert_eq!(queue.list().len(), 1);
+}
+
+#[tokio::test]
+async fn concurrent_checks_of_one_subscription_queue_each_item_once() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default()
+        .dump_json(channel(json!([video("NewVideo002", "Newer", 300.0), video("NewVideo001", "New", 300.0)])))
+        .install(bin.path(), &Component::YtDlp.file_name());
+    let store = SubscriptionStore::load(env.subscriptions_path());
+    let queue = DownloadQueue::load(env.queue_path());
+    let mut cfg = config("https://www.youtube.com/playlist?list=PL1", out.path());
+    cfg.download_existing = true;
+    let sub = store.add(cfg).unwrap();
+
+    let (a, b) = tokio::join!(
+        subscriptions::check(&env, &store, &queue, &sub.id),
+        subscriptions::check(&env, &store, &queue, &sub.id),
+    );
+    assert_eq!(a.unwrap().len() + b.unwrap().len(), 2);
+    assert_eq!(queue.list().len(), 2);
+    assert_eq!(store.get(&sub.id).unwrap().seen.len(), 2);
+}
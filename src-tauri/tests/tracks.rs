@@ -0,0 +1,91 @@
+mod common;
+
+use common::{ffmpeg_calls, install_fake_ffmpeg, recorded_calls, sample_info_json, FakeYtDlp, RecordingSink};
+use cyber_ytdl_lib::tracks::{track_args, track_file_name, AlbumInfo};
+use cyber_ytdl_lib::{ytdlp, AppEnv, Chapter, Component, DownloadRequest};
+use std::path::Path;
+
+fn mix_info() -> serde_json::Value {
+    let mut info = sample_info_json();
+    info["title"] = serde_json::json!("Live Mix: Vol/1");
+    info["chapters"] = serde_json::json!([
+        { "title": "Opening", "start_time": 0.0, "end_time": 95.5 },
+        { "title": "Night Drive?", "start_time": 95.5, "end_time": 212.0 }
+    ]);
+    info
+}
+
+fn value_after<'a>(call: &'a [String], flag: &str) -> Vec<&'a str> {
+    call.windows(2).filter(|w| w[0] == flag).map(|w| w[1].as_str()).collect()
+}
+
+fn split_request(path: &Path) -> DownloadRequest {
+    let mut req = DownloadRequest::new("https://vimeo.com/1", "audio", "bestaudio", path.to_string_lossy().to_string(), "en");
+    req.options.split_chapters = true;
+    req
+}
+
+#[test]
+fn track_args_embed_cover_and_tags() {
+    let chapter = Chapter { title: "Night Drive".into(), start_time: 95.5, end_time: 212.0 };
+    let album = AlbumInfo { album: "Live Mix".into(), artist: Some("DJ Sample".into()) };
+    let args = track_args(Path::new("full.mp3"), Some(Path::new("cover.jpg")), &chapter, 2, 5, &album, Path::new("02 - Night Drive.mp3"));
+
+    assert_eq!(value_after(&args, "-ss"), ["95.5"]);
+    assert_eq!(value_after(&args, "-to"), ["212"]);
+    assert_eq!(value_after(&args, "-i"), ["full.mp3", "cover.jpg"]);
+    assert_eq!(value_after(&args, "-disposition:v"), ["attached_pic"]);
+    let tags = value_after(&args, "-metadata");
+    for expected in ["title=Night Drive", "track=2/5", "album=Live Mix", "artist=DJ Sample"] {
+        assert!(tags.contains(&expected), "missing {}", expected);
+    }
+    assert_eq!(args.last().unwrap(), "02 - Night Drive.mp3");
+    assert_eq!(track_file_name(7, "A/B", "mp3"), "07 - A_B.mp3");
+}
+
+#[tokio::test]
+async fn audio_download_is_split_into_album_folder() {
+    let bin = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().dump_json(mix_info()).writes_output(b"full").install(bin.path(), &Component::YtDlp.file_name());
+    install_fake_ffmpeg(bin.path(), &Component::Ffmpeg.file_name());
+
+    ytdlp::download(&env, &RecordingSink::default(), &split_request(out.path())).await.unwrap();
+
+    let album = out.path().join("Live Mix_ Vol_1");
+    let mut files: Vec<String> = std::fs::read_dir(&album).unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["01 - Opening.mp3", "02 - Night Drive_.mp3"]);
+
+    let download = recorded_calls(bin.path()).pop().unwrap();
+    assert!(download.contains(&"--write-thumbnail".to_string()));
+    assert!(!download.contains(&"--download-sections".to_string()));
+
+    let calls = ffmpeg_calls(bin.path());
+    assert_eq!(calls.len(), 2);
+    let tags = value_after(&calls[1], "-metadata");
+    assert!(tags.contains(&"track=2/2"));
+    assert!(tags.contains(&"album=Live Mix: Vol/1"));
+    assert!(tags.contains(&"artist=Sample Channel"));
+}
+
+#[tokio::test]
+async fn split_requires_audio_mode_and_chapters() {
+    let bin = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(bin.path());
+    FakeYtDlp::default().install(bin.path(), &Component::YtDlp.file_name());
+
+    // sample_info_json 沒有章節
+    let err = ytdlp::download(&env, &RecordingSink::default(), &split_request(bin.path())).await.unwrap_err();
+    assert!(err.contains("no chapters"));
+
+    let mut req = split_request(bin.path());
+    req.mode = "video".into();
+    req.quality = "best".into();
+    let err = ytdlp::download(&env, &RecordingSink::default(), &req).await.unwrap_err();
+    assert!(err.contains("audio mode"));
+    assert_eq!(recorded_calls(bin.path()).len(), 1);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::FakeYtDlp;
+use cyber_ytdl_lib::urls::{normalize, prevalidate, ExtractorList, UrlKind};
+use cyber_ytdl_lib::{AppEnv, Component, UrlError};
+
+#[test]
+fn youtube_variants_share_one_canonical_form() {
+    let canonical = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+    for raw in [
+        "https://youtu.be/dQw4w9WgXcQ?si=abcdef",
+        "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+        "https://m.youtube.com/watch?v=dQw4w9WgXcQ&feature=share",
+        "https://music.youtube.com/watch?v=dQw4w9WgXcQ",
+        "  https://www.youtube.com/watch?v=dQw4w9WgXcQ&utm_source=x#t=10  ",
+        "https://www.youtube.com/live/dQw4w9WgXcQ?pp=ygU",
+    ] {
+        let n = normalize(raw).unwrap();
+        assert_eq!(n.url, canonical, "{}", raw);
+        assert_eq!(n.kind, UrlKind::Video);
+        assert_eq!(n.video_id.as_deref(), Some("dQw4w9WgXcQ"));
+    }
+}
+
+#[test]
+fn extracts_playlist_and_channel_ids() {
+    let n = normalize("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL123&index=3").unwrap();
+    assert_eq!(n.kind, UrlKind::VideoInPlaylist);
+    assert_eq!(n.playlist_id.as_deref(), Some("PL123"));
+    assert_eq!(n.url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL123");
+
+    let n = normalize("https://youtube.com/playlist?list=PL123&si=x").unwrap();
+    assert_eq!(n.kind, UrlKind::Playlist);
+    assert_eq!(n.url, "https://www.youtube.com/playlist?list=PL123");
+
+    let n = normalize("https://www.youtube.com/@SomeChannel/videos").unwrap();
+    assert_eq!(n.kind, UrlKind::Channel);
+    assert_eq!(n.url, "https://www.youtube.com/@SomeChannel/videos");
+
+    // 沒有特別處理的頁面保留主機與查詢參數 (只移除追蹤參數)
+    let n = normalize("https://music.youtube.com/browse/VLPL123?si=x").unwrap();
+    assert_eq!((n.kind, n.url.as_str()), (UrlKind::Other, "https://music.youtube.com/browse/VLPL123"));
+    let n = normalize("https://m.youtube.com/results?search_query=lofi+beats&feature=share").unwrap();
+    assert_eq!(n.url, "https://m.youtube.com/results?search_query=lofi+beats");
+}
+
+#[test]
+fn strips_tracking_params_on_other_sites() {
+    let n = normalize("https://vimeo.com/12345?utm_campaign=a&fbclid=b&h=keep").unwrap();
+    assert_eq!(n.url, "https://vimeo.com/12345?h=keep");
+    assert_eq!(n.site, "vimeo.com");
+
+    // ref / feature / pp / spm 只在 YouTube 上移除
+    let n = normalize("https://example.com/watch?ref=abc&feature=hd&pp=2&spm=1").unwrap();
+    assert_eq!(n.url, "https://example.com/watch?ref=abc&feature=hd&pp=2&spm=1");
+    let n = normalize("https://m.youtube.com/results?search_query=x&ref=abc&spm=1").unwrap();
+    assert_eq!(n.url, "https://m.youtube.com/results?search_query=x");
+}
+
+#[test]
+fn rejects_bad_input_with_typed_errors() {
+    assert_eq!(normalize("   "), Err(UrlError::Empty));
+    assert_eq!(normalize("file:///etc/passwd"), Err(UrlError::UnsupportedScheme("file".into())));
+    assert_eq!(normalize("https://youtu.be/short"), Err(UrlError::InvalidVideoId("short".into())));
+    assert!(matches!(normalize("not a url"), Err(UrlError::Invalid(_))));
+}
+
+#[test]
+fn extractor_list_matches_registered_domains() {
+    let list = ExtractorList::parse("Youtube\nyoutube:tab\nVimeo\nBiliBili\nfacebook\nbbc\nGoogleDrive\nAmazonStore\nCo\ngeneric\n");
+    assert!(list.supports("vimeo.com"));
+    assert!(list.supports("player.vimeo.com"));
+    assert!(list.supports("bilibili.com"));
+    // 短網址與國別網域依完整網域對照
+    assert!(list.supports("b23.tv"));
+    assert!(list.supports("fb.watch"));
+    assert!(list.supports("bbc.co.uk"));
+    // 已知網站的 extractor 不在清單中 (舊版 yt-dlp) 才算不支援
+    for site in ["twitch.tv", "www.tiktok.com", "nicovideo.jp"] {
+        assert!(!list.supports(site), "{}", site);
+    }
+    // 不在對照表中的網站交由 yt-dlp 判斷 (可能由其他 extractor 或 generic 處理)
+    for site in ["example.org", "google.com", "co.uk", "notvimeo.com", "vimeo.com.evil.net"] {
+        assert!(list.supports(site), "{}", site);
+    }
+}
+
+#[tokio::test]
+async fn prevalidate_uses_cached_extractor_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::new(dir.path());
+    FakeYtDlp::default().progress(&["Vimeo", "youtube"]).install(dir.path(), &Component::YtDlp.file_name());
+
+    assert!(prevalidate(&env, "https://vimeo.com/1", true).await.is_ok());
+    assert!(prevalidate(&env, "https://example.org/v", true).await.is_ok());
+    assert_eq!(prevalidate(&env, "https://www.twitch.tv/v", true).await, Err(UrlError::UnsupportedSite("twitch.tv".into())));
+    // 不檢查時一律放行
+    assert!(prevalidate(&env, "https://www.twitch.tv/v", false).await.is_ok());
+    assert!(dir.path().join("extractors.txt").exists());
+}